@@ -8,7 +8,7 @@ use crate::tasks::{
 };
 use anyhow::Result;
 use clap::{App, Arg, SubCommand};
-use onefuzz::{blob::BlobContainerUrl, syncdir::SyncedDir};
+use onefuzz::{blob::BlobContainerUrl, dedup::DedupConfig, syncdir::SyncedDir};
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
@@ -21,7 +21,7 @@ async fn run_impl(input: String, config: Config) -> Result<()> {
     let input_path = Path::new(&input);
     let test_url = Url::parse("https://contoso.com/sample-container/blob.txt")?;
     let heartbeat_client = config.common.init_heartbeat().await?;
-    let processor = GenericReportProcessor::new(&config, heartbeat_client);
+    let processor = GenericReportProcessor::new(&config, heartbeat_client)?;
     let result = processor.test_input(test_url, input_path).await?;
     println!("{:#?}", result);
     Ok(())
@@ -50,10 +50,12 @@ pub fn run(args: &clap::ArgMatches) -> Result<()> {
         check_asan_log,
         check_debugger,
         check_retry_count,
+        dedup: DedupConfig::default(),
         crashes: None,
         input_queue: None,
         no_repro: None,
         reports: None,
+        cores: None,
         unique_reports: SyncedDir {
             path: "unique_reports".into(),
             url: BlobContainerUrl::new(url::Url::parse("https://contoso.com/unique_reports")?)?,
@@ -62,6 +64,7 @@ pub fn run(args: &clap::ArgMatches) -> Result<()> {
             heartbeat_queue: None,
             instrumentation_key: None,
             telemetry_key: None,
+            strict_placeholders: false,
             job_id: Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
             task_id: Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
         },
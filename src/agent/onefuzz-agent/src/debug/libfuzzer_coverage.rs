@@ -62,6 +62,7 @@ pub fn run(args: &clap::ArgMatches) -> Result<()> {
             heartbeat_queue: None,
             instrumentation_key: None,
             telemetry_key: None,
+            strict_placeholders: false,
             job_id: Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
             task_id: Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
         },
@@ -8,7 +8,7 @@ use crate::tasks::{
 };
 use anyhow::Result;
 use clap::{App, Arg, SubCommand};
-use onefuzz::{blob::BlobContainerUrl, syncdir::SyncedDir};
+use onefuzz::{blob::BlobContainerUrl, dedup::DedupConfig, syncdir::SyncedDir};
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
@@ -46,6 +46,7 @@ pub fn run(args: &clap::ArgMatches) -> Result<()> {
         target_options,
         target_timeout,
         check_retry_count,
+        dedup: DedupConfig::default(),
         input_queue: None,
         crashes: None,
         reports: None,
@@ -58,6 +59,7 @@ pub fn run(args: &clap::ArgMatches) -> Result<()> {
             heartbeat_queue: None,
             instrumentation_key: None,
             telemetry_key: None,
+            strict_placeholders: false,
             job_id: Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
             task_id: Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
         },
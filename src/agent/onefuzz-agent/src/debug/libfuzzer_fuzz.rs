@@ -26,6 +26,7 @@ pub fn run(args: &clap::ArgMatches) -> Result<()> {
     let inputs_dir = value_t!(args, "inputs_dir", String)?;
     let target_exe = value_t!(args, "target_exe", PathBuf)?;
     let target_options = args.values_of_lossy("target_options").unwrap_or_default();
+    let target_dict = value_t!(args, "target_dict", String).ok();
     let mut target_env = HashMap::new();
     for opt in args.values_of_lossy("target_env").unwrap_or_default() {
         let (k, v) = parse_key_value(opt)?;
@@ -51,15 +52,21 @@ pub fn run(args: &clap::ArgMatches) -> Result<()> {
         inputs,
         readonly_inputs,
         crashes,
+        hangs: None,
+        ooms: None,
+        leaks: None,
         target_exe,
         target_env,
         target_options,
         target_workers,
         ensemble_sync_delay,
+        target_dict,
+        dictionaries: None,
         common: CommonConfig {
             heartbeat_queue: None,
             instrumentation_key: None,
             telemetry_key: None,
+            strict_placeholders: false,
             job_id: Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
             task_id: Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
         },
@@ -93,6 +100,11 @@ pub fn args() -> App<'static, 'static> {
                 .allow_hyphen_values(true)
                 .help("Supports hyphens.  Recommendation: Set target_env first"),
         )
+        .arg(
+            Arg::with_name("target_dict")
+                .long("target_dict")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("inputs_dir")
                 .takes_value(true)
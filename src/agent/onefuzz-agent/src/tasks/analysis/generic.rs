@@ -5,9 +5,7 @@ use crate::tasks::{config::CommonConfig, heartbeat::HeartbeatSender};
 use anyhow::Result;
 use futures::stream::StreamExt;
 use onefuzz::{az_copy, blob::url::BlobUrl};
-use onefuzz::{
-    expand::Expand, fs::set_executable, fs::OwnedDir, jitter::delay_with_jitter, syncdir::SyncedDir,
-};
+use onefuzz::{fs::set_executable, fs::OwnedDir, jitter::delay_with_jitter, syncdir::SyncedDir};
 use reqwest::Url;
 use serde::Deserialize;
 use std::{
@@ -113,7 +111,7 @@ async fn poll_inputs(config: &Config, tmp_dir: OwnedDir) -> Result<()> {
 }
 
 pub async fn run_tool(input: impl AsRef<Path>, config: &Config) -> Result<()> {
-    let mut tool_args = Expand::new();
+    let mut tool_args = config.common.expand();
 
     tool_args
         .input_path(&input)
@@ -123,7 +121,13 @@ pub async fn run_tool(input: impl AsRef<Path>, config: &Config) -> Result<()> {
         .analyzer_options(&config.analyzer_options)
         .output_dir(&config.analysis.path);
 
-    let analyzer_path = Expand::new()
+    if let Some(crashes) = &config.crashes {
+        tool_args.crashes_account(&crashes.url.account());
+    }
+
+    let analyzer_path = config
+        .common
+        .expand()
         .tools_dir(&config.tools.path)
         .evaluate_value(&config.analyzer_exe)?;
 
@@ -2,9 +2,10 @@
 // Licensed under the MIT License.
 
 #![allow(clippy::large_enum_variant)]
-use crate::tasks::{analysis, coverage, fuzz, heartbeat::*, merge, report};
+use crate::tasks::{analysis, coverage, fuzz, heartbeat::*, merge, minimize, report};
 use anyhow::Result;
 use onefuzz::{
+    expand::Expand,
     machine_id::{get_machine_id, get_scaleset_name},
     telemetry::{self, Event::task_start, EventData},
 };
@@ -31,8 +32,16 @@ pub struct CommonConfig {
     pub heartbeat_queue: Option<Url>,
 
     pub telemetry_key: Option<Uuid>,
+
+    /// Fail if a command line or environment template of the task contains an
+    /// unknown placeholder, instead of passing it to the target as-is.
+    #[serde(default)]
+    pub strict_placeholders: bool,
 }
 
+// Directory of the job's setup container, relative to the task working directory.
+const SETUP_DIR: &str = "setup";
+
 impl CommonConfig {
     pub async fn init_heartbeat(&self) -> Result<Option<TaskHeartbeatClient>> {
         match &self.heartbeat_queue {
@@ -43,6 +52,17 @@ impl CommonConfig {
             None => Ok(None),
         }
     }
+
+    /// Create an `Expand` with the placeholder values shared by all tasks.
+    pub fn expand<'a>(&self) -> Expand<'a> {
+        let mut expand = Expand::new();
+        expand
+            .job_id(&self.job_id)
+            .task_id(&self.task_id)
+            .setup_dir(SETUP_DIR)
+            .strict(self.strict_placeholders);
+        expand
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -60,6 +80,15 @@ pub enum Config {
     #[serde(alias = "libfuzzer_coverage")]
     LibFuzzerCoverage(coverage::libfuzzer_coverage::Config),
 
+    #[serde(alias = "libfuzzer_minimize")]
+    LibFuzzerMinimize(minimize::libfuzzer_minimize::Config),
+
+    #[serde(alias = "afl_fuzz")]
+    AflFuzz(fuzz::afl_fuzz::Config),
+
+    #[serde(alias = "honggfuzz_fuzz")]
+    HonggfuzzFuzz(fuzz::honggfuzz_fuzz::Config),
+
     #[serde(alias = "generic_analysis")]
     GenericAnalysis(analysis::generic::Config),
 
@@ -74,6 +103,9 @@ pub enum Config {
 
     #[serde(alias = "generic_crash_report")]
     GenericReport(report::generic::Config),
+
+    #[serde(alias = "generic_minimize")]
+    GenericMinimize(minimize::generic::Config),
 }
 
 impl Config {
@@ -88,11 +120,15 @@ impl Config {
             Config::LibFuzzerMerge(c) => &c.common,
             Config::LibFuzzerReport(c) => &c.common,
             Config::LibFuzzerCoverage(c) => &c.common,
+            Config::LibFuzzerMinimize(c) => &c.common,
+            Config::AflFuzz(c) => &c.common,
+            Config::HonggfuzzFuzz(c) => &c.common,
             Config::GenericAnalysis(c) => &c.common,
             Config::GenericMerge(c) => &c.common,
             Config::GenericReport(c) => &c.common,
             Config::GenericSupervisor(c) => &c.common,
             Config::GenericGenerator(c) => &c.common,
+            Config::GenericMinimize(c) => &c.common,
         }
     }
 
@@ -102,11 +138,15 @@ impl Config {
             Config::LibFuzzerMerge(_) => "libfuzzer_merge",
             Config::LibFuzzerReport(_) => "libfuzzer_crash_report",
             Config::LibFuzzerCoverage(_) => "libfuzzer_coverage",
+            Config::LibFuzzerMinimize(_) => "libfuzzer_minimize",
+            Config::AflFuzz(_) => "afl_fuzz",
+            Config::HonggfuzzFuzz(_) => "honggfuzz_fuzz",
             Config::GenericAnalysis(_) => "generic_analysis",
             Config::GenericMerge(_) => "generic_merge",
             Config::GenericReport(_) => "generic_crash_report",
             Config::GenericSupervisor(_) => "generic_supervisor",
             Config::GenericGenerator(_) => "generic_generator",
+            Config::GenericMinimize(_) => "generic_minimize",
         };
 
         match self {
@@ -151,11 +191,25 @@ impl Config {
                     .await
             }
             Config::LibFuzzerMerge(config) => merge::libfuzzer_merge::spawn(Arc::new(config)).await,
+            Config::LibFuzzerMinimize(config) => {
+                minimize::libfuzzer_minimize::MinimizeTask::new(config)
+                    .run()
+                    .await
+            }
+            Config::AflFuzz(config) => fuzz::afl_fuzz::AflFuzzTask::new(config).start().await,
+            Config::HonggfuzzFuzz(config) => {
+                fuzz::honggfuzz_fuzz::HonggfuzzFuzzTask::new(config)
+                    .start()
+                    .await
+            }
             Config::GenericAnalysis(config) => analysis::generic::spawn(config).await,
             Config::GenericGenerator(config) => fuzz::generator::spawn(Arc::new(config)).await,
             Config::GenericSupervisor(config) => fuzz::supervisor::spawn(config).await,
             Config::GenericMerge(config) => merge::generic::spawn(Arc::new(config)).await,
             Config::GenericReport(config) => report::generic::ReportTask::new(&config).run().await,
+            Config::GenericMinimize(config) => {
+                minimize::generic::MinimizeTask::new(&config).run().await
+            }
         }
     }
 }
@@ -0,0 +1,321 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::tasks::{
+    config::CommonConfig,
+    stats::{afl, common::STATS_DELAY},
+    utils::{heartbeat_process, monitor_process},
+};
+use anyhow::Result;
+use futures::future::try_join_all;
+use onefuzz::{
+    fs::{exists, list_files, set_executable, OwnedDir},
+    jitter::delay_with_jitter,
+    sha256,
+    syncdir::{SyncOperation::Pull, SyncedDir},
+    telemetry::{
+        track_event,
+        Event::{new_hang, new_result, runtime_stats},
+        EventData,
+    },
+};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+use tokio::{
+    fs,
+    process::{Child, Command},
+    sync::Notify,
+};
+
+const HARVEST_PERIOD: Duration = Duration::from_secs(10);
+
+// Name of the instance that runs with `-M`. The others run with `-S`.
+const MAIN_INSTANCE: &str = "main";
+
+// AFL writes a README to the `crashes` directory of each instance.
+const README: &str = "README.txt";
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub inputs: SyncedDir,
+    pub crashes: SyncedDir,
+
+    /// Inputs that time out, which go to `crashes` if unset.
+    #[serde(default)]
+    pub hangs: Option<SyncedDir>,
+
+    pub tools: SyncedDir,
+
+    /// Path to `afl-fuzz`, usually in the tools container, as in
+    /// `{tools_dir}/afl-fuzz`.
+    pub afl_exe: String,
+    #[serde(default)]
+    pub afl_env: HashMap<String, String>,
+    #[serde(default)]
+    pub afl_options: Vec<String>,
+
+    pub target_exe: PathBuf,
+    pub target_options: Vec<String>,
+
+    /// Number of instances per node, one main and the rest secondary.
+    pub target_workers: Option<u64>,
+
+    pub ensemble_sync_delay: Option<u64>,
+
+    #[serde(flatten)]
+    pub common: CommonConfig,
+}
+
+pub struct AflFuzzTask {
+    config: Config,
+    runtime_dir: OwnedDir,
+}
+
+impl AflFuzzTask {
+    pub fn new(config: Config) -> Self {
+        let runtime_dir = OwnedDir::new(config.common.task_id.to_string());
+        Self {
+            config,
+            runtime_dir,
+        }
+    }
+
+    pub async fn start(&self) -> Result<()> {
+        let workers = self.config.target_workers.unwrap_or_else(|| {
+            let cpus = num_cpus::get() as u64;
+            u64::max(1, cpus - 1)
+        });
+
+        self.init_directories().await?;
+        let afl_exe = self
+            .config
+            .common
+            .expand()
+            .tools_dir(&self.config.tools.path)
+            .evaluate_value(&self.config.afl_exe)?;
+
+        let instances: Vec<_> = (0..workers).map(instance_name).collect();
+        let mut processes = vec![];
+        for name in &instances {
+            processes.push(self.start_instance(&afl_exe, name).await?);
+        }
+
+        let labels: Vec<_> = instances
+            .iter()
+            .map(|name| format!("afl instance {}", name))
+            .collect();
+        let stopped = Notify::new();
+        let monitor_processes = try_join_all(
+            processes
+                .into_iter()
+                .zip(labels.iter())
+                .map(|(process, label)| monitor_process(process, label, &stopped)),
+        );
+
+        let hb_client = self.config.common.init_heartbeat().await?;
+        let heartbeat = heartbeat_process(&stopped, hb_client);
+
+        let resync = self
+            .config
+            .inputs
+            .continuous_sync(Pull, self.config.ensemble_sync_delay);
+        let new_crashes = self.config.crashes.monitor_results(new_result);
+        let new_hangs = async {
+            match &self.config.hangs {
+                Some(hangs) => hangs.monitor_results(new_hang).await,
+                None => Ok(()),
+            }
+        };
+        let harvest = self.harvest_artifacts(&instances);
+        let report_stats = self.report_stats(&instances);
+
+        futures::try_join!(
+            monitor_processes,
+            heartbeat,
+            resync,
+            new_crashes,
+            new_hangs,
+            harvest,
+            report_stats,
+        )?;
+
+        Ok(())
+    }
+
+    async fn init_directories(&self) -> Result<()> {
+        self.runtime_dir.create_if_missing().await?;
+
+        self.config.tools.init_pull().await?;
+        set_executable(&self.config.tools.path).await?;
+
+        self.config.inputs.init_pull().await?;
+        self.config.crashes.init().await?;
+        if let Some(hangs) = &self.config.hangs {
+            hangs.init().await?;
+        }
+
+        Ok(())
+    }
+
+    // The `-o` directory shared by the instances, in which each has its own
+    // `queue`, `crashes` and `hangs`, and AFL syncs the queues between them.
+    fn sync_dir(&self) -> PathBuf {
+        self.runtime_dir.path().join("sync")
+    }
+
+    async fn start_instance(&self, afl_exe: impl AsRef<Path>, name: &str) -> Result<Child> {
+        let sync_dir = self.sync_dir();
+        let mut expand = self.config.common.expand();
+        expand
+            .input_marker("@@")
+            .input_corpus(&self.config.inputs.path)
+            .crashes(&self.config.crashes.path)
+            .runtime_dir(self.runtime_dir.path())
+            .tools_dir(&self.config.tools.path)
+            .target_exe(&self.config.target_exe)
+            .target_options(&self.config.target_options);
+
+        let role = if name == MAIN_INSTANCE { "-M" } else { "-S" };
+
+        let mut cmd = Command::new(afl_exe.as_ref());
+        // The sync directory outlives the instances, so that a restarted task
+        // resumes fuzzing from it, rather than failing because it exists.
+        cmd.kill_on_drop(true)
+            .env_remove("RUST_LOG")
+            .env("AFL_NO_UI", "1")
+            .env("AFL_AUTORESUME", "1")
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .arg("-i")
+            .arg(&self.config.inputs.path)
+            .arg("-o")
+            .arg(&sync_dir)
+            .arg(role)
+            .arg(name)
+            .args(expand.evaluate(&self.config.afl_options)?)
+            .arg("--")
+            .arg(&self.config.target_exe)
+            .args(expand.evaluate(&self.config.target_options)?);
+
+        for (k, v) in &self.config.afl_env {
+            cmd.env(k, expand.evaluate_value(v)?);
+        }
+
+        info!("starting afl instance {}: {:?}", name, cmd);
+        Ok(cmd.spawn()?)
+    }
+
+    // Copy the crashes and hangs of every instance to the directories of the
+    // task, from which they are uploaded.
+    async fn harvest_artifacts(&self, instances: &[String]) -> Result<()> {
+        let crashes = &self.config.crashes;
+        let hangs = self.config.hangs.as_ref().unwrap_or(crashes);
+        let mut seen = HashSet::new();
+
+        loop {
+            for name in instances {
+                let instance_dir = self.sync_dir().join(name);
+                harvest(&instance_dir.join("crashes"), &crashes.path, &mut seen).await?;
+                harvest(&instance_dir.join("hangs"), &hangs.path, &mut seen).await?;
+            }
+            delay_with_jitter(HARVEST_PERIOD).await;
+        }
+    }
+
+    async fn report_stats(&self, instances: &[String]) -> Result<()> {
+        loop {
+            for (worker_id, name) in instances.iter().enumerate() {
+                let path = self.sync_dir().join(name).join("fuzzer_stats");
+                if !exists(&path).await? {
+                    continue;
+                }
+
+                match afl::read_stats(&path).await {
+                    Ok(mut stats) => {
+                        stats.push(EventData::WorkerId(worker_id as u64));
+                        track_event(runtime_stats, stats);
+                    }
+                    Err(err) => warn!("unable to read stats of afl instance {}: {}", name, err),
+                }
+            }
+            delay_with_jitter(STATS_DELAY).await;
+        }
+    }
+}
+
+fn instance_name(worker_id: u64) -> String {
+    if worker_id == 0 {
+        MAIN_INSTANCE.to_string()
+    } else {
+        format!("secondary-{}", worker_id)
+    }
+}
+
+// Copy the new files of an instance's artifact directory to `dest`, named by
+// the SHA-256 of their contents, since AFL numbers the artifacts of every
+// instance on every node from 0.
+async fn harvest(src: &Path, dest: &Path, seen: &mut HashSet<PathBuf>) -> Result<usize> {
+    if !exists(src).await? {
+        return Ok(0);
+    }
+
+    let mut count = 0;
+    for file in list_files(src).await? {
+        let file_name = match file.file_name() {
+            Some(file_name) => file_name.to_string_lossy().to_string(),
+            None => continue,
+        };
+
+        if file_name == README || seen.contains(&file) {
+            continue;
+        }
+
+        let dest = dest.join(sha256::digest_file(&file).await?);
+        fs::copy(&file, &dest).await?;
+        seen.insert(file);
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_name() {
+        assert_eq!(instance_name(0), "main");
+        assert_eq!(instance_name(1), "secondary-1");
+        assert_eq!(instance_name(7), "secondary-7");
+    }
+
+    #[tokio::test]
+    async fn test_harvest() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let mut seen = HashSet::new();
+
+        let missing = src.path().join("missing");
+        let count = harvest(&missing, dest.path(), &mut seen).await.unwrap();
+        assert_eq!(count, 0);
+
+        let crash = "id:000000,sig:11,src:000000,op:havoc,rep:4";
+        fs::write(src.path().join(README), "").await.unwrap();
+        fs::write(src.path().join(crash), "crash").await.unwrap();
+
+        let count = harvest(src.path(), dest.path(), &mut seen).await.unwrap();
+        assert_eq!(count, 1);
+
+        let copied = dest.path().join(sha256::digest("crash"));
+        assert_eq!(fs::read(&copied).await.unwrap(), b"crash");
+
+        let count = harvest(src.path(), dest.path(), &mut seen).await.unwrap();
+        assert_eq!(count, 0);
+    }
+}
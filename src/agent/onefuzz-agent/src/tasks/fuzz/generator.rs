@@ -6,8 +6,10 @@ use anyhow::{Error, Result};
 use futures::stream::StreamExt;
 use onefuzz::{
     expand::Expand,
-    fs::set_executable,
+    fs::{list_files, set_executable},
+    grammar::{Grammar, GrammarGenerator},
     input_tester::Tester,
+    mutator::Mutator,
     sha256,
     syncdir::{continuous_sync, SyncOperation::Pull, SyncedDir},
     telemetry::Event::new_result,
@@ -22,15 +24,59 @@ use std::{
 };
 use tokio::{fs, process::Command};
 
+// Inputs generated by the built-in generators between runs of the target.
+const GENERATOR_BATCH_SIZE: usize = 100;
+
+// Largest input generated by the built-in mutator.
+const MUTATOR_MAX_LEN: usize = 1024 * 1024;
+
+// Depth of the derivations of the grammar generator past which they are ended
+// as soon as possible.
+const DEFAULT_GRAMMAR_MAX_DEPTH: usize = 32;
+
 fn default_bool_true() -> bool {
     true
 }
 
+fn default_generator_type() -> GeneratorType {
+    GeneratorType::External
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub enum GeneratorType {
+    /// Run `generator_exe`, such as Radamsa.
+    #[serde(alias = "external")]
+    External,
+
+    /// Mutate the inputs with the built-in mutator, which needs no tools.
+    #[serde(alias = "mutator")]
+    Mutator,
+
+    /// Derive inputs from `generator_grammar`, which needs no tools but the
+    /// grammar.
+    #[serde(alias = "grammar")]
+    Grammar,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct GeneratorConfig {
+    #[serde(default = "default_generator_type")]
+    pub generator_type: GeneratorType,
+    #[serde(default)]
     pub generator_exe: String,
+    #[serde(default)]
     pub generator_env: HashMap<String, String>,
+    #[serde(default)]
     pub generator_options: Vec<String>,
+
+    /// Seed of the built-in generators, random if unset.
+    pub generator_seed: Option<u64>,
+
+    /// Path to the grammar of the grammar generator, usually in the tools
+    /// container, as in `{tools_dir}/json.bnf`.
+    pub generator_grammar: Option<String>,
+    pub generator_max_depth: Option<usize>,
+
     pub readonly_inputs: Vec<SyncedDir>,
     pub crashes: SyncedDir,
     pub tools: SyncedDir,
@@ -73,6 +119,7 @@ pub async fn spawn(config: Arc<GeneratorConfig>) -> Result<(), Error> {
         false,
         config.check_debugger,
         config.check_retry_count,
+        config.common.expand(),
     );
     let inputs: Vec<_> = config.readonly_inputs.iter().map(|x| &x.path).collect();
     let fuzzing_monitor = start_fuzzing(&config, inputs, tester, hb_client);
@@ -80,15 +127,15 @@ pub async fn spawn(config: Arc<GeneratorConfig>) -> Result<(), Error> {
     Ok(())
 }
 
-async fn generate_input(
+async fn generate_input<'a>(
+    mut expand: Expand<'a>,
     generator_exe: &str,
     generator_env: &HashMap<String, String>,
-    generator_options: &[String],
+    generator_options: &'a [String],
     tools_dir: impl AsRef<Path>,
     corpus_dir: impl AsRef<Path>,
     output_dir: impl AsRef<Path>,
 ) -> Result<()> {
-    let mut expand = Expand::new();
     expand
         .generated_inputs(&output_dir)
         .input_corpus(&corpus_dir)
@@ -98,9 +145,7 @@ async fn generate_input(
 
     utils::reset_tmp_dir(&output_dir).await?;
 
-    let generator_path = Expand::new()
-        .tools_dir(tools_dir.as_ref())
-        .evaluate_value(generator_exe)?;
+    let generator_path = expand.evaluate_value(generator_exe)?;
 
     let mut generator = Command::new(&generator_path);
     generator
@@ -135,13 +180,60 @@ async fn start_fuzzing<'a>(
 
     info!("Starting generator fuzzing loop");
 
+    match config.generator_type {
+        GeneratorType::External => {}
+        GeneratorType::Mutator => {
+            let mut mutator = Mutator::new(generator_seed(config), MUTATOR_MAX_LEN);
+            let mut corpus = Corpus::default();
+
+            loop {
+                heartbeat_client.alive();
+
+                corpus.refresh(&corpus_dirs).await?;
+                let inputs = &corpus.inputs;
+                write_inputs(generator_tmp, || mutator.generate(inputs)).await?;
+                test_inputs(config, &tester, generator_tmp).await?;
+            }
+        }
+        GeneratorType::Grammar => {
+            let grammar = config
+                .generator_grammar
+                .as_ref()
+                .ok_or_else(|| format_err!("the grammar generator needs a generator_grammar"))?;
+            let grammar = config
+                .common
+                .expand()
+                .tools_dir(&config.tools.path)
+                .evaluate_value(grammar)?;
+            let grammar = Grammar::load(grammar).await?;
+
+            let max_depth = config
+                .generator_max_depth
+                .unwrap_or(DEFAULT_GRAMMAR_MAX_DEPTH);
+            let mut generator = GrammarGenerator::new(grammar, generator_seed(config), max_depth);
+
+            loop {
+                heartbeat_client.alive();
+
+                write_inputs(generator_tmp, || generator.generate()).await?;
+                test_inputs(config, &tester, generator_tmp).await?;
+            }
+        }
+    }
+
     loop {
         heartbeat_client.alive();
 
         for corpus_dir in &corpus_dirs {
             let corpus_dir = corpus_dir.as_ref();
 
+            let mut expand = config.common.expand();
+            expand
+                .crashes_account(&config.crashes.url.account())
+                .target_timeout(config.target_timeout);
+
             generate_input(
+                expand,
                 &config.generator_exe,
                 &config.generator_env,
                 &config.generator_options,
@@ -151,27 +243,7 @@ async fn start_fuzzing<'a>(
             )
             .await?;
 
-            let mut read_dir = fs::read_dir(generator_tmp).await?;
-            while let Some(file) = read_dir.next().await {
-                verbose!("Processing file {:?}", file);
-                let file = file?;
-
-                let destination_file = if config.rename_output {
-                    let hash = sha256::digest_file(file.path()).await?;
-                    OsString::from(hash)
-                } else {
-                    file.file_name()
-                };
-
-                let destination_file = config.crashes.path.join(destination_file);
-                if tester.is_crash(file.path()).await? {
-                    info!("Crash found, path = {}", file.path().display());
-
-                    if let Err(err) = fs::rename(file.path(), &destination_file).await {
-                        warn!("Unable to move file {:?} : {:?}", file.path(), err);
-                    }
-                }
-            }
+            test_inputs(config, &tester, generator_tmp).await?;
 
             verbose!(
                 "Tested generated inputs for corpus = {}",
@@ -181,6 +253,96 @@ async fn start_fuzzing<'a>(
     }
 }
 
+fn generator_seed(config: &GeneratorConfig) -> u64 {
+    let seed = config.generator_seed.unwrap_or_else(Mutator::random_seed);
+    info!("Generating inputs with seed {}", seed);
+    seed
+}
+
+// Write a batch of inputs made by a built-in generator to `generated_inputs`,
+// replacing the previous batch.
+//
+// The inputs are named by their sha256, so crashing inputs of different
+// batches don't overwrite each other in `crashes`.
+async fn write_inputs(
+    generated_inputs: impl AsRef<Path>,
+    mut generate: impl FnMut() -> Vec<u8>,
+) -> Result<()> {
+    let generated_inputs = generated_inputs.as_ref();
+    utils::reset_tmp_dir(generated_inputs).await?;
+
+    for _ in 0..GENERATOR_BATCH_SIZE {
+        let input = generate();
+        let path = generated_inputs.join(sha256::digest(&input));
+        fs::write(path, input).await?;
+    }
+
+    Ok(())
+}
+
+async fn test_inputs<'a>(
+    config: &GeneratorConfig,
+    tester: &Tester<'a>,
+    generated_inputs: impl AsRef<Path>,
+) -> Result<()> {
+    let mut read_dir = fs::read_dir(generated_inputs).await?;
+    while let Some(file) = read_dir.next().await {
+        verbose!("Processing file {:?}", file);
+        let file = file?;
+
+        let destination_file = if config.rename_output {
+            let hash = sha256::digest_file(file.path()).await?;
+            OsString::from(hash)
+        } else {
+            file.file_name()
+        };
+
+        let destination_file = config.crashes.path.join(destination_file);
+        if tester.is_crash(file.path()).await? {
+            info!("Crash found, path = {}", file.path().display());
+
+            if let Err(err) = fs::rename(file.path(), &destination_file).await {
+                warn!("Unable to move file {:?} : {:?}", file.path(), err);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The inputs of every corpus, from which the built-in mutator picks the inputs
+// to mutate and splice.
+#[derive(Default)]
+struct Corpus {
+    files: Vec<PathBuf>,
+    inputs: Vec<Vec<u8>>,
+}
+
+impl Corpus {
+    // Reload the inputs if the continuous sync changed the files of any corpus
+    // since they were last loaded.
+    async fn refresh(&mut self, corpus_dirs: &[impl AsRef<Path>]) -> Result<()> {
+        let mut files = vec![];
+        for corpus_dir in corpus_dirs {
+            files.extend(list_files(corpus_dir).await?);
+        }
+
+        if files == self.files {
+            return Ok(());
+        }
+
+        let mut inputs = vec![];
+        for file in &files {
+            inputs.push(fs::read(file).await?);
+        }
+
+        verbose!("Loaded {} corpus inputs", inputs.len());
+        self.files = files;
+        self.inputs = inputs;
+        Ok(())
+    }
+}
+
 mod tests {
     #[tokio::test]
     #[cfg(target_os = "linux")]
@@ -215,6 +377,7 @@ mod tests {
 
         tokio::fs::write(seed_file_name, "test").await.unwrap();
         let _output = generate_input(
+            Expand::new(),
             &radamsa_exe,
             &radamsa_env,
             &generator_options,
@@ -0,0 +1,164 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::tasks::{
+    config::CommonConfig,
+    stats::common::{monitor_stats, StatsFormat},
+    utils::{heartbeat_process, monitor_process},
+};
+use anyhow::Result;
+use onefuzz::{
+    fs::{set_executable, OwnedDir},
+    syncdir::{SyncOperation::Pull, SyncedDir},
+    telemetry::Event::{new_coverage, new_result},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf, process::Stdio};
+use tokio::{
+    process::{Child, Command},
+    sync::Notify,
+};
+
+// Placeholder honggfuzz replaces with the path of the input, for targets that
+// read it from a file instead of running in persistent mode.
+const INPUT_MARKER: &str = "___FILE___";
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub inputs: SyncedDir,
+    pub crashes: SyncedDir,
+    pub tools: SyncedDir,
+
+    /// Path to `honggfuzz`, usually in the tools container, as in
+    /// `{tools_dir}/honggfuzz`.
+    pub honggfuzz_exe: String,
+    #[serde(default)]
+    pub honggfuzz_options: Vec<String>,
+
+    pub target_exe: PathBuf,
+    pub target_env: HashMap<String, String>,
+    pub target_options: Vec<String>,
+
+    /// Number of fuzzing threads, passed to `--threads`.
+    pub target_workers: Option<u64>,
+
+    pub ensemble_sync_delay: Option<u64>,
+
+    #[serde(flatten)]
+    pub common: CommonConfig,
+}
+
+pub struct HonggfuzzFuzzTask {
+    config: Config,
+    runtime_dir: OwnedDir,
+}
+
+impl HonggfuzzFuzzTask {
+    pub fn new(config: Config) -> Self {
+        let runtime_dir = OwnedDir::new(config.common.task_id.to_string());
+        Self {
+            config,
+            runtime_dir,
+        }
+    }
+
+    pub async fn start(&self) -> Result<()> {
+        let workers = self.config.target_workers.unwrap_or_else(|| {
+            let cpus = num_cpus::get() as u64;
+            u64::max(1, cpus - 1)
+        });
+
+        self.init_directories().await?;
+
+        let process = self.start_fuzzer(workers)?;
+        let stopped = Notify::new();
+        let monitor_process = monitor_process(process, "honggfuzz", &stopped);
+
+        let hb_client = self.config.common.init_heartbeat().await?;
+        let heartbeat = heartbeat_process(&stopped, hb_client);
+
+        // Honggfuzz writes new coverage to its `--input` directory, like
+        // libFuzzer does to its corpus.
+        let resync = self
+            .config
+            .inputs
+            .continuous_sync(Pull, self.config.ensemble_sync_delay);
+        let new_inputs = self.config.inputs.monitor_results(new_coverage);
+        let new_crashes = self.config.crashes.monitor_results(new_result);
+
+        let stats_file = self.stats_file().to_string_lossy().to_string();
+        let report_stats = monitor_stats(Some(stats_file), Some(StatsFormat::Honggfuzz), None);
+
+        futures::try_join!(
+            monitor_process,
+            heartbeat,
+            resync,
+            new_inputs,
+            new_crashes,
+            report_stats,
+        )?;
+
+        Ok(())
+    }
+
+    async fn init_directories(&self) -> Result<()> {
+        self.runtime_dir.create_if_missing().await?;
+
+        self.config.tools.init_pull().await?;
+        set_executable(&self.config.tools.path).await?;
+
+        self.config.inputs.init_pull().await?;
+        self.config.crashes.init().await?;
+
+        Ok(())
+    }
+
+    fn stats_file(&self) -> PathBuf {
+        self.runtime_dir.path().join("stats")
+    }
+
+    fn start_fuzzer(&self, workers: u64) -> Result<Child> {
+        let mut expand = self.config.common.expand();
+        expand
+            .input_marker(INPUT_MARKER)
+            .input_corpus(&self.config.inputs.path)
+            .crashes(&self.config.crashes.path)
+            .runtime_dir(self.runtime_dir.path())
+            .tools_dir(&self.config.tools.path)
+            .target_exe(&self.config.target_exe)
+            .target_options(&self.config.target_options);
+
+        let honggfuzz_exe = expand.evaluate_value(&self.config.honggfuzz_exe)?;
+        let target_options = expand.evaluate(&self.config.target_options)?;
+
+        let mut cmd = Command::new(&honggfuzz_exe);
+        cmd.kill_on_drop(true)
+            .env_remove("RUST_LOG")
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        // Targets given the input as an argument read it from a file, so only
+        // targets without one run in persistent mode.
+        if !target_options.iter().any(|o| o.contains(INPUT_MARKER)) {
+            cmd.arg("--persistent");
+        }
+
+        cmd.arg("--quiet")
+            .arg(format!("--threads={}", workers))
+            .arg(format!("--input={}", self.config.inputs.path.display()))
+            .arg(format!("--crashdir={}", self.config.crashes.path.display()))
+            .arg(format!("--workspace={}", self.runtime_dir.path().display()))
+            .arg(format!("--statsfile={}", self.stats_file().display()))
+            .args(expand.evaluate(&self.config.honggfuzz_options)?)
+            .arg("--")
+            .arg(&self.config.target_exe)
+            .args(target_options);
+
+        for (k, v) in &self.config.target_env {
+            cmd.env(k, expand.evaluate_value(v)?);
+        }
+
+        info!("starting honggfuzz: {:?}", cmd);
+        Ok(cmd.spawn()?)
+    }
+}
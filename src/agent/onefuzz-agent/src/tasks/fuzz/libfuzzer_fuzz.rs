@@ -5,23 +5,33 @@ use crate::tasks::{config::CommonConfig, heartbeat::HeartbeatSender};
 use anyhow::Result;
 use futures::{future::try_join_all, stream::StreamExt};
 use onefuzz::{
+    blob::BlobClient,
+    dictionary::Dictionary,
     fs::list_files,
-    libfuzzer::{LibFuzzer, LibFuzzerLine},
+    libfuzzer::{ArtifactType, LibFuzzer, LibFuzzerLine, LibFuzzerNewFunc, PreflightError},
+    machine_id::get_machine_id,
     process::ExitStatus,
     syncdir::{continuous_sync, SyncOperation::Pull, SyncedDir},
     system,
     telemetry::{
-        Event::{new_coverage, new_result, process_stats, runtime_stats},
+        track_event,
+        Event::{
+            self, new_coverage, new_hang, new_leak, new_oom, new_result, preflight_failed,
+            process_stats, runtime_stats,
+        },
         EventData,
     },
 };
 use serde::Deserialize;
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use tempfile::tempdir;
 use tokio::{
     fs::rename,
     io::{AsyncBufReadExt, BufReader},
-    sync::mpsc,
+    sync::{mpsc, Mutex},
     task,
     time::{self, Duration},
 };
@@ -41,23 +51,53 @@ pub struct Config {
     pub inputs: SyncedDir,
     pub readonly_inputs: Option<Vec<SyncedDir>>,
     pub crashes: SyncedDir,
+
+    /// Inputs that time out or are slow, which go to `crashes` if unset.
+    #[serde(default)]
+    pub hangs: Option<SyncedDir>,
+
+    /// Inputs that run out of memory, which go to `crashes` if unset.
+    #[serde(default)]
+    pub ooms: Option<SyncedDir>,
+
+    /// Inputs that leak memory, which go to `crashes` if unset.
+    #[serde(default)]
+    pub leaks: Option<SyncedDir>,
+
     pub target_exe: PathBuf,
     pub target_env: HashMap<String, String>,
     pub target_options: Vec<String>,
     pub target_workers: Option<u64>,
     pub ensemble_sync_delay: Option<u64>,
 
+    /// Dictionary to fuzz with, such as `{setup_dir}/fuzz.dict`.
+    #[serde(default)]
+    pub target_dict: Option<String>,
+
+    /// Dictionaries shared by the workers of the ensemble.
+    ///
+    /// Each worker fuzzes with all of them, and adds the entries libFuzzer
+    /// recommends on exit.
+    #[serde(default)]
+    pub dictionaries: Option<SyncedDir>,
+
     #[serde(flatten)]
     pub common: CommonConfig,
 }
 
 pub struct LibFuzzerFuzzTask {
     config: Config,
+
+    // Entries recommended by the libFuzzer runs of all workers of this task.
+    recommended: Mutex<Dictionary>,
 }
 
 impl LibFuzzerFuzzTask {
     pub fn new(config: Config) -> Result<Self> {
-        Ok(Self { config })
+        Ok(Self {
+            config,
+            recommended: Mutex::new(Dictionary::new()),
+        })
     }
 
     pub async fn start(&self) -> Result<()> {
@@ -67,12 +107,17 @@ impl LibFuzzerFuzzTask {
         });
 
         self.init_directories().await?;
+        self.preflight().await?;
         let hb_client = self.config.common.init_heartbeat().await?;
 
         // To be scheduled.
         let resync = self.continuous_sync_inputs();
         let new_inputs = self.config.inputs.monitor_results(new_coverage);
         let new_crashes = self.config.crashes.monitor_results(new_result);
+        let new_hangs = monitor_optional_results(&self.config.hangs, new_hang);
+        let new_ooms = monitor_optional_results(&self.config.ooms, new_oom);
+        let new_leaks = monitor_optional_results(&self.config.leaks, new_leak);
+        let new_results = futures::future::try_join4(new_crashes, new_hangs, new_ooms, new_leaks);
 
         let (stats_sender, stats_receiver) = mpsc::unbounded_channel();
         let report_stats = report_runtime_stats(workers as usize, stats_receiver, hb_client);
@@ -83,7 +128,27 @@ impl LibFuzzerFuzzTask {
 
         let fuzzers = try_join_all(fuzzers);
 
-        futures::try_join!(resync, new_inputs, new_crashes, fuzzers, report_stats)?;
+        futures::try_join!(resync, new_inputs, new_results, fuzzers, report_stats)?;
+
+        Ok(())
+    }
+
+    // Check that the target can be fuzzed, so that a broken target fails the
+    // task with a clear error, instead of restarting the workers forever.
+    async fn preflight(&self) -> Result<()> {
+        let fuzzer = LibFuzzer::new(
+            &self.config.target_exe,
+            &self.config.target_options,
+            &self.config.target_env,
+            self.config.common.expand(),
+        );
+
+        if let Err(err) = fuzzer.preflight().await {
+            if let Some(err) = err.downcast_ref::<PreflightError>() {
+                event!(preflight_failed; EventData::Type = err.kind());
+            }
+            bail!("target failed preflight check: {}", err);
+        }
 
         Ok(())
     }
@@ -120,6 +185,7 @@ impl LibFuzzerFuzzTask {
         stats_sender: Option<&StatsSender>,
     ) -> Result<()> {
         let crash_dir = tempdir()?;
+        let dict_dir = tempdir()?;
         let run_id = Uuid::new_v4();
 
         info!("starting fuzzer run, run_id = {}", run_id);
@@ -129,11 +195,15 @@ impl LibFuzzerFuzzTask {
             readonly_inputs.iter().for_each(|d| inputs.push(&d.path));
         }
 
-        let fuzzer = LibFuzzer::new(
+        let mut fuzzer = LibFuzzer::new(
             &self.config.target_exe,
             &self.config.target_options,
             &self.config.target_env,
+            self.config.common.expand(),
         );
+        if let Some(dict) = self.prepare_dictionary(dict_dir.path()).await? {
+            fuzzer = fuzzer.dict(dict);
+        }
         let mut running = fuzzer.fuzz(crash_dir.path(), local_inputs, &inputs)?;
 
         let sys_info = task::spawn(report_fuzzer_sys_info(worker_id, run_id, running.id()));
@@ -146,10 +216,15 @@ impl LibFuzzerFuzzTask {
         let stderr = BufReader::new(stderr);
 
         let mut libfuzzer_output = Vec::new();
+        let mut new_functions = 0;
         let mut lines = stderr.lines();
         while let Some(line) = lines.next_line().await? {
             if let Some(stats_sender) = stats_sender {
-                if let Err(err) = try_report_iter_update(stats_sender, worker_id, run_id, &line) {
+                if let Ok(Some(_)) = LibFuzzerNewFunc::parse(&line) {
+                    new_functions += 1;
+                } else if let Err(err) =
+                    try_report_iter_update(stats_sender, worker_id, run_id, new_functions, &line)
+                {
                     error!("could not parse fuzzing interation update: {}", err);
                 }
             }
@@ -159,6 +234,13 @@ impl LibFuzzerFuzzTask {
         let (exit_status, _) = tokio::join!(running, sys_info);
         let exit_status: ExitStatus = exit_status?.into();
 
+        if let Some(dictionaries) = &self.config.dictionaries {
+            let recommended = Dictionary::parse_recommended(&libfuzzer_output.join("\n"));
+            if let Err(err) = self.share_dictionary(dictionaries, &recommended).await {
+                warn!("unable to share recommended dictionary: {}", err);
+            }
+        }
+
         let files = list_files(crash_dir.path()).await?;
 
         // ignore libfuzzer exiting cleanly without crashing, which could happen via
@@ -173,7 +255,8 @@ impl LibFuzzerFuzzTask {
 
         for file in &files {
             if let Some(filename) = file.file_name() {
-                let dest = self.config.crashes.path.join(filename);
+                let artifact = ArtifactType::from_file_name(&filename.to_string_lossy());
+                let dest = self.artifact_dir(artifact).path.join(filename);
                 rename(file, dest).await?;
             }
         }
@@ -181,9 +264,31 @@ impl LibFuzzerFuzzTask {
         Ok(())
     }
 
+    // Route artifacts that are not crashes to their own containers, if set, so
+    // that reporting tasks don't spend their time on them.
+    fn artifact_dir(&self, artifact: Option<ArtifactType>) -> &SyncedDir {
+        let dir = match artifact {
+            Some(ArtifactType::Timeout) | Some(ArtifactType::SlowUnit) => {
+                self.config.hangs.as_ref()
+            }
+            Some(ArtifactType::Oom) => self.config.ooms.as_ref(),
+            Some(ArtifactType::Leak) => self.config.leaks.as_ref(),
+            Some(ArtifactType::Crash) | None => None,
+        };
+
+        dir.unwrap_or(&self.config.crashes)
+    }
+
     async fn init_directories(&self) -> Result<()> {
         self.config.inputs.init().await?;
         self.config.crashes.init().await?;
+        let dirs = [&self.config.hangs, &self.config.ooms, &self.config.leaks];
+        for dir in dirs.iter().copied().flatten() {
+            dir.init().await?;
+        }
+        if let Some(dictionaries) = &self.config.dictionaries {
+            dictionaries.init_pull().await?;
+        }
         if let Some(readonly_inputs) = &self.config.readonly_inputs {
             for dir in readonly_inputs {
                 dir.init().await?;
@@ -198,14 +303,102 @@ impl LibFuzzerFuzzTask {
             let inputs = inputs.clone();
             dirs.extend(inputs);
         }
+        if let Some(dictionaries) = &self.config.dictionaries {
+            dirs.push(dictionaries.clone());
+        }
         continuous_sync(&dirs, Pull, self.config.ensemble_sync_delay).await
     }
+
+    // Merge the task's dictionary with the shared ones into a single file in
+    // `dir`, since libFuzzer only accepts one `-dict`.
+    async fn prepare_dictionary(&self, dir: &Path) -> Result<Option<PathBuf>> {
+        let mut dict = Dictionary::new();
+
+        if let Some(target_dict) = &self.config.target_dict {
+            let mut expand = self.config.common.expand();
+            expand.target_exe(&self.config.target_exe);
+            let path = expand.evaluate_value(target_dict)?;
+            dict = Dictionary::load(&path)
+                .await
+                .map_err(|err| format_err!("unable to load target_dict: {}", err))?;
+        }
+
+        if let Some(dictionaries) = &self.config.dictionaries {
+            for path in list_files(&dictionaries.path).await? {
+                match Dictionary::load(&path).await {
+                    Ok(shared) => {
+                        dict.merge(&shared);
+                    }
+                    Err(err) => warn!("ignoring shared dictionary: {}", err),
+                }
+            }
+        }
+
+        if dict.is_empty() {
+            return Ok(None);
+        }
+
+        let path = dir.join("fuzz.dict");
+        dict.save(&path).await?;
+        Ok(Some(path))
+    }
+
+    // Add recommended entries to the dictionary this task shares from this
+    // machine, and upload it if any are new.
+    async fn share_dictionary(
+        &self,
+        dictionaries: &SyncedDir,
+        recommended: &Dictionary,
+    ) -> Result<()> {
+        if recommended.is_empty() {
+            return Ok(());
+        }
+
+        let name = format!(
+            "{}-{}.dict",
+            self.config.common.task_id,
+            get_machine_id().await?
+        );
+        let path = dictionaries.path.join(&name);
+
+        let mut shared = self.recommended.lock().await;
+
+        // Keep the entries shared before the agent restarted.
+        if shared.is_empty() {
+            if let Ok(previous) = Dictionary::load(&path).await {
+                shared.merge(&previous);
+            }
+        }
+
+        let added = shared.merge(recommended);
+        if added == 0 {
+            return Ok(());
+        }
+
+        verbose!("adding {} recommended dictionary entries", added);
+        shared.save(&path).await?;
+
+        BlobClient::new()
+            .put_file(dictionaries.url.blob(name).url(), &path)
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+async fn monitor_optional_results(dir: &Option<SyncedDir>, event: Event) -> Result<()> {
+    match dir {
+        Some(dir) => dir.monitor_results(event).await,
+        None => Ok(()),
+    }
 }
 
 fn try_report_iter_update(
     stats_sender: &StatsSender,
     worker_id: u64,
     run_id: Uuid,
+    new_functions: u64,
     line: &str,
 ) -> Result<()> {
     if let Some(line) = LibFuzzerLine::parse(line)? {
@@ -214,6 +407,13 @@ fn try_report_iter_update(
             run_id,
             count: line.iters(),
             execs_sec: line.execs_sec(),
+            coverage: line.coverage(),
+            features: line.features(),
+            corpus_units: line.corpus_units(),
+            corpus_size: line.corpus_size(),
+            max_len: line.max_len(),
+            rss_mb: line.rss_mb(),
+            new_functions,
         })?;
     }
 
@@ -255,17 +455,38 @@ pub struct RuntimeStats {
     run_id: Uuid,
     count: u64,
     execs_sec: f64,
+    coverage: Option<u64>,
+    features: Option<u64>,
+    corpus_units: Option<u64>,
+    corpus_size: Option<u64>,
+    max_len: Option<u64>,
+    rss_mb: Option<u64>,
+
+    // Functions first covered in this run, per `NEW_FUNC` lines.
+    new_functions: u64,
 }
 
 impl RuntimeStats {
     pub fn report(&self) {
-        event!(
-            runtime_stats;
-            EventData::WorkerId = self.worker_id,
-            EventData::RunId = self.run_id,
-            EventData::Count = self.count,
-            EventData::ExecsSecond = self.execs_sec
-        );
+        let mut stats = vec![
+            EventData::WorkerId(self.worker_id),
+            EventData::RunId(self.run_id),
+            EventData::Count(self.count),
+            EventData::ExecsSecond(self.execs_sec),
+            EventData::NewFunctions(self.new_functions),
+        ];
+
+        let optional = vec![
+            self.coverage.map(EventData::CoverageEdges),
+            self.features.map(EventData::CoverageFeatures),
+            self.corpus_units.map(EventData::CorpusUnits),
+            self.corpus_size.map(EventData::CorpusSize),
+            self.max_len.map(EventData::MaxInputLength),
+            self.rss_mb.map(EventData::RssMb),
+        ];
+        stats.extend(optional.into_iter().flatten());
+
+        track_event(runtime_stats, stats);
     }
 }
 
@@ -1,6 +1,8 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+pub mod afl_fuzz;
 pub mod generator;
+pub mod honggfuzz_fuzz;
 pub mod libfuzzer_fuzz;
 pub mod supervisor;
@@ -4,9 +4,11 @@
 #![allow(clippy::too_many_arguments)]
 use crate::tasks::{
     config::{CommonConfig, ContainerType},
-    heartbeat::*,
-    stats::common::{monitor_stats, StatsFormat},
-    utils::CheckNotify,
+    stats::{
+        common::{monitor_stats, StatsFormat},
+        generic::StatsFields,
+    },
+    utils::{heartbeat_process, monitor_process},
 };
 use anyhow::{Error, Result};
 use onefuzz::{
@@ -21,7 +23,6 @@ use std::{
     collections::HashMap,
     path::{Path, PathBuf},
     process::Stdio,
-    time::Duration,
 };
 use tokio::{
     process::{Child, Command},
@@ -42,13 +43,12 @@ pub struct SupervisorConfig {
     pub wait_for_files: Option<ContainerType>,
     pub stats_file: Option<String>,
     pub stats_format: Option<StatsFormat>,
+    pub stats_fields: Option<StatsFields>,
     pub ensemble_sync_delay: Option<u64>,
     #[serde(flatten)]
     pub common: CommonConfig,
 }
 
-const HEARTBEAT_PERIOD: Duration = Duration::from_secs(60);
-
 pub async fn spawn(config: SupervisorConfig) -> Result<(), Error> {
     let runtime_dir = OwnedDir::new(config.common.task_id.to_string());
     runtime_dir.create_if_missing().await?;
@@ -56,7 +56,9 @@ pub async fn spawn(config: SupervisorConfig) -> Result<(), Error> {
     config.tools.init_pull().await?;
     set_executable(&config.tools.path).await?;
 
-    let supervisor_path = Expand::new()
+    let supervisor_path = config
+        .common
+        .expand()
         .tools_dir(&config.tools.path)
         .evaluate_value(&config.supervisor_exe)?;
 
@@ -91,7 +93,11 @@ pub async fn spawn(config: SupervisorConfig) -> Result<(), Error> {
 
     let continuous_sync_task = inputs.continuous_sync(Pull, config.ensemble_sync_delay);
 
+    let mut expand = config.common.expand();
+    expand.crashes_account(&config.crashes.url.account());
+
     let process = start_supervisor(
+        expand,
         &runtime_dir.path(),
         &supervisor_path,
         &config.target_exe,
@@ -105,14 +111,16 @@ pub async fn spawn(config: SupervisorConfig) -> Result<(), Error> {
     .await?;
 
     let stopped = Notify::new();
-    let monitor_process = monitor_process(process, &stopped);
+    let monitor_process = monitor_process(process, "supervisor", &stopped);
     let hb = config.common.init_heartbeat().await?;
 
     let heartbeat_process = heartbeat_process(&stopped, hb);
 
     let monitor_path = if let Some(stats_file) = &config.stats_file {
         Some(
-            Expand::new()
+            config
+                .common
+                .expand()
                 .runtime_dir(runtime_dir.path())
                 .evaluate_value(stats_file)?,
         )
@@ -121,7 +129,11 @@ pub async fn spawn(config: SupervisorConfig) -> Result<(), Error> {
         None
     };
 
-    let monitor_stats = monitor_stats(monitor_path, config.stats_format);
+    let monitor_stats = monitor_stats(
+        monitor_path,
+        config.stats_format,
+        config.stats_fields.clone(),
+    );
 
     futures::try_join!(
         heartbeat_process,
@@ -134,43 +146,15 @@ pub async fn spawn(config: SupervisorConfig) -> Result<(), Error> {
     Ok(())
 }
 
-async fn heartbeat_process(
-    stopped: &Notify,
-    heartbeat_client: Option<TaskHeartbeatClient>,
-) -> Result<()> {
-    while !stopped.is_notified(HEARTBEAT_PERIOD).await {
-        heartbeat_client.alive();
-    }
-    Ok(())
-}
-
-async fn monitor_process(process: tokio::process::Child, stopped: &Notify) -> Result<()> {
-    verbose!("waiting for child output...");
-    let output: std::process::Output = process.wait_with_output().await?;
-    verbose!("child exited with {:?}", output.status);
-
-    if output.status.success() {
-        verbose!("child status is success, notifying");
-        stopped.notify();
-        Ok(())
-    } else {
-        let err_text = String::from_utf8_lossy(&output.stderr);
-        let output_text = String::from_utf8_lossy(&output.stdout);
-        let message = format!("{} {}", err_text, output_text);
-        error!("{}", message);
-        stopped.notify();
-        Err(Error::msg(message))
-    }
-}
-
-async fn start_supervisor(
+async fn start_supervisor<'a>(
+    mut expand: Expand<'a>,
     runtime_dir: impl AsRef<Path>,
     supervisor_path: impl AsRef<Path>,
     target_exe: impl AsRef<Path>,
     fault_dir: impl AsRef<Path>,
     inputs_dir: impl AsRef<Path>,
-    target_options: &[String],
-    supervisor_options: &[String],
+    target_options: &'a [String],
+    supervisor_options: &'a [String],
     supervisor_env: &HashMap<String, String>,
     supervisor_input_marker: &Option<String>,
 ) -> Result<Child> {
@@ -182,7 +166,6 @@ async fn start_supervisor(
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
-    let mut expand = Expand::new();
     expand
         .supervisor_exe(supervisor_path)
         .supervisor_options(supervisor_options)
@@ -283,6 +266,7 @@ mod tests {
 
         tokio::fs::write(seed_file_name, "xyz").await.unwrap();
         let process = start_supervisor(
+            Expand::new(),
             runtime_dir,
             PathBuf::from(afl_fuzz_exe),
             PathBuf::from(afl_test_binary),
@@ -297,7 +281,7 @@ mod tests {
         .unwrap();
 
         let notify = Notify::new();
-        let _fuzzing_monitor = monitor_process(process, &notify);
+        let _fuzzing_monitor = monitor_process(process, "supervisor", &notify);
         let stat_output = fault_dir.join("fuzzer_stats");
         let start = Instant::now();
         loop {
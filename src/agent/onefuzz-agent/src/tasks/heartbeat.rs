@@ -13,6 +13,7 @@ use uuid::Uuid;
 pub enum HeartbeatData {
     TaskAlive,
     MachineAlive,
+    MergeProgress { processed: u64, total: u64 },
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
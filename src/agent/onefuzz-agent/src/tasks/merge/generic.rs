@@ -4,8 +4,7 @@
 use crate::tasks::{config::CommonConfig, heartbeat::HeartbeatSender, utils};
 use anyhow::Result;
 use onefuzz::{
-    expand::Expand, fs::set_executable, http::ResponseExt, jitter::delay_with_jitter,
-    syncdir::SyncedDir,
+    fs::set_executable, http::ResponseExt, jitter::delay_with_jitter, syncdir::SyncedDir,
 };
 use reqwest::Url;
 use reqwest_retry::SendRetry;
@@ -129,7 +128,7 @@ async fn try_delete_blob(input_url: Url) -> Result<()> {
 }
 
 async fn merge(config: &Config, output_dir: impl AsRef<Path>) -> Result<()> {
-    let mut supervisor_args = Expand::new();
+    let mut supervisor_args = config.common.expand();
 
     supervisor_args
         .input_marker(&config.supervisor_input_marker)
@@ -144,7 +143,9 @@ async fn merge(config: &Config, output_dir: impl AsRef<Path>) -> Result<()> {
         supervisor_args.target_options(&config.target_options);
     }
 
-    let supervisor_path = Expand::new()
+    let supervisor_path = config
+        .common
+        .expand()
         .tools_dir(&config.tools.path)
         .evaluate_value(&config.supervisor_exe)?;
 
@@ -4,9 +4,11 @@
 use crate::tasks::{config::CommonConfig, heartbeat::*, utils};
 use anyhow::Result;
 use onefuzz::{
+    blob::BlobClient,
     http::ResponseExt,
     jitter::delay_with_jitter,
-    libfuzzer::{LibFuzzer, LibFuzzerMergeOutput},
+    libfuzzer::{LibFuzzer, LibFuzzerMergeOutput, LibFuzzerMergeProgress},
+    sha256,
     syncdir::SyncedDir,
 };
 use reqwest::Url;
@@ -18,6 +20,14 @@ use std::{
     sync::Arc,
 };
 use storage_queue::{QueueClient, EMPTY_QUEUE_DELAY};
+use tokio::time::{self, Duration};
+
+// Prefix of the names of merge control files, in the task working directory or
+// the `merge_control` container.
+const MERGE_CONTROL_FILE: &str = "merge_control_file";
+
+// Period of reporting the progress of a merge.
+const MERGE_PROGRESS_PERIOD: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Deserialize)]
 struct QueueMessage {
@@ -35,6 +45,12 @@ pub struct Config {
     pub inputs: SyncedDir,
     pub unique_inputs: SyncedDir,
 
+    /// Container to keep the merge control file in, so that a merge can resume
+    /// on another VM, if this one is preempted. Otherwise, it's kept in the task
+    /// working directory.
+    #[serde(default)]
+    pub merge_control: Option<SyncedDir>,
+
     #[serde(flatten)]
     pub common: CommonConfig,
 }
@@ -42,9 +58,12 @@ pub struct Config {
 pub async fn spawn(config: Arc<Config>) -> Result<()> {
     let hb_client = config.common.init_heartbeat().await?;
     config.unique_inputs.init().await?;
+    if let Some(merge_control) = &config.merge_control {
+        merge_control.init().await?;
+    }
     loop {
         hb_client.alive();
-        if let Err(error) = process_message(config.clone()).await {
+        if let Err(error) = process_message(config.clone(), &hb_client).await {
             error!(
                 "failed to process latest message from notification queue: {}",
                 error
@@ -53,7 +72,10 @@ pub async fn spawn(config: Arc<Config>) -> Result<()> {
     }
 }
 
-async fn process_message(config: Arc<Config>) -> Result<()> {
+async fn process_message(
+    config: Arc<Config>,
+    hb_client: &Option<TaskHeartbeatClient>,
+) -> Result<()> {
     let tmp_dir = "./tmp";
 
     verbose!("tmp dir reset");
@@ -76,15 +98,11 @@ async fn process_message(config: Arc<Config>) -> Result<()> {
         info!("downloaded input to {}", input_path.display());
 
         info!("Merging corpus");
-        match merge(
-            &config.target_exe,
-            &config.target_options,
-            &config.target_env,
-            &config.unique_inputs.path,
-            &tmp_dir,
-        )
-        .await
-        {
+        let control_file = control_file_path(&config, &input_url);
+        if let Err(e) = restore_control_file(&config, &control_file).await {
+            warn!("Failed to restore merge control file: {}", e);
+        }
+        match merge(&config, &tmp_dir, &control_file, hb_client).await {
             Ok(result) if result.added_files_count > 0 => {
                 info!("Added {} new files to the corpus", result.added_files_count);
                 config.unique_inputs.sync_push().await?;
@@ -93,6 +111,11 @@ async fn process_message(config: Arc<Config>) -> Result<()> {
             Err(e) => error!("Merge failed : {}", e),
         }
 
+        // The merge is done with, so the next one should not try to resume it.
+        if let Err(e) = remove_control_file(&config, &control_file).await {
+            warn!("Failed to remove merge control file: {}", e);
+        }
+
         verbose!("will delete popped message with id = {}", msg.id());
 
         queue.delete(msg).await?;
@@ -128,13 +151,116 @@ async fn try_delete_blob(input_url: Url) -> Result<()> {
 }
 
 async fn merge(
-    target_exe: &Path,
-    target_options: &[String],
-    target_env: &HashMap<String, String>,
-    corpus_dir: &Path,
+    config: &Config,
     candidate_dir: impl AsRef<Path>,
+    control_file: &Path,
+    hb_client: &Option<TaskHeartbeatClient>,
 ) -> Result<LibFuzzerMergeOutput> {
-    let merger = LibFuzzer::new(target_exe, target_options, target_env);
+    let merger = LibFuzzer::new(
+        &config.target_exe,
+        &config.target_options,
+        &config.target_env,
+        config.common.expand(),
+    );
     let candidates = vec![candidate_dir];
-    merger.merge(&corpus_dir, &candidates).await
+
+    tokio::select! {
+        result = merger.merge(&config.unique_inputs.path, &candidates, Some(control_file)) => result,
+        _ = report_merge_progress(config, control_file, hb_client) => unreachable!(),
+    }
+}
+
+// Path of the control file of the merge of `input_url`.
+//
+// The name is unique to the task and input, so that tasks and VMs sharing a
+// `merge_control` container only resume their own merges, such as when the
+// message of a preempted VM is redelivered.
+fn control_file_path(config: &Config, input_url: &Url) -> PathBuf {
+    let name = format!(
+        "{}-{}-{}",
+        MERGE_CONTROL_FILE,
+        config.common.task_id,
+        sha256::digest(input_url.path())
+    );
+
+    match &config.merge_control {
+        Some(merge_control) => merge_control.path.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+// URL of `control_file` in the `merge_control` container, if any.
+fn control_file_url(config: &Config, control_file: &Path) -> Option<Url> {
+    let merge_control = config.merge_control.as_ref()?;
+    let name = control_file.file_name()?.to_string_lossy();
+    Some(merge_control.url.blob(name).url())
+}
+
+// Download the control file of an earlier, interrupted merge of the same input.
+async fn restore_control_file(config: &Config, control_file: &Path) -> Result<()> {
+    if let Some(url) = control_file_url(config, control_file) {
+        if let Some(response) = BlobClient::new().get_if_exists(&url).await? {
+            let data = response.bytes().await?;
+            tokio::fs::write(control_file, &data).await?;
+            info!("resuming merge from {}", control_file.display());
+        }
+    }
+
+    Ok(())
+}
+
+async fn save_control_file(config: &Config, control_file: &Path) -> Result<()> {
+    if let Some(url) = control_file_url(config, control_file) {
+        if tokio::fs::metadata(control_file).await.is_ok() {
+            BlobClient::new()
+                .put_file(url, control_file)
+                .await?
+                .error_for_status()?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn remove_control_file(config: &Config, control_file: &Path) -> Result<()> {
+    if tokio::fs::metadata(control_file).await.is_ok() {
+        tokio::fs::remove_file(control_file).await?;
+    }
+
+    if let Some(url) = control_file_url(config, control_file) {
+        BlobClient::new().delete(url).await?;
+    }
+
+    Ok(())
+}
+
+// Periodically report the progress of a running merge, and save the control
+// file to the `merge_control` container, if any. Never returns.
+async fn report_merge_progress(
+    config: &Config,
+    control_file: &Path,
+    hb_client: &Option<TaskHeartbeatClient>,
+) {
+    loop {
+        time::delay_for(MERGE_PROGRESS_PERIOD).await;
+
+        match LibFuzzerMergeProgress::read(control_file).await {
+            Ok(Some(progress)) => {
+                verbose!("merged {} of {} inputs", progress.processed, progress.total);
+                let data = HeartbeatData::MergeProgress {
+                    processed: progress.processed,
+                    total: progress.total,
+                };
+                if let Err(e) = hb_client.send(data) {
+                    warn!("Failed to report merge progress: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => verbose!("unable to read merge control file: {}", e),
+        }
+
+        if let Err(e) = save_control_file(config, control_file).await {
+            warn!("Failed to save merge control file: {}", e);
+        }
+    }
 }
@@ -0,0 +1,41 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::tasks::report::crash_report::{link_minimized_input, InputBlob};
+use anyhow::Result;
+use onefuzz::{blob::BlobClient, sha256, syncdir::SyncedDir};
+use std::path::Path;
+
+/// Upload a minimized input to `minimized_crashes`, with the name of the
+/// original input, and link it from the report of the original input.
+pub async fn upload_minimized(
+    minimized_crashes: &SyncedDir,
+    reports: &Option<SyncedDir>,
+    input: &Path,
+    minimized: &Path,
+) -> Result<()> {
+    let name = input
+        .file_name()
+        .ok_or_else(|| format_err!("invalid input path: {}", input.display()))?
+        .to_string_lossy();
+
+    let blob_url = minimized_crashes.url.blob(&name);
+    BlobClient::new()
+        .put_file(blob_url.url(), minimized)
+        .await?
+        .error_for_status()?;
+
+    if let Some(reports) = reports {
+        let input_sha256 = sha256::digest_file(input).await?;
+        let linked =
+            link_minimized_input(&reports.url, &input_sha256, InputBlob::from(blob_url)).await?;
+        if !linked {
+            verbose!(
+                "no report to link minimized input from: {}",
+                input.display()
+            );
+        }
+    }
+
+    Ok(())
+}
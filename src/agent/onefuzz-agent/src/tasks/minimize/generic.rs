@@ -0,0 +1,227 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::common::upload_minimized;
+use crate::tasks::{
+    config::CommonConfig,
+    generic::input_poller::{CallbackImpl, InputPoller, Processor},
+    heartbeat::*,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use onefuzz::{
+    dedup::{DedupConfig, StackHasher},
+    input_tester::{TestResult, Tester},
+    minimize::DeltaDebugger,
+    sha256,
+    syncdir::SyncedDir,
+};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use storage_queue::Message;
+use tempfile::tempdir;
+use tokio::fs;
+
+fn default_bool_true() -> bool {
+    true
+}
+
+fn default_max_tests() -> u64 {
+    1000
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub target_exe: PathBuf,
+
+    #[serde(default)]
+    pub target_options: Vec<String>,
+
+    #[serde(default)]
+    pub target_env: HashMap<String, String>,
+
+    pub input_queue: Option<Url>,
+    pub crashes: Option<SyncedDir>,
+    pub minimized_crashes: SyncedDir,
+    pub reports: Option<SyncedDir>,
+
+    pub target_timeout: Option<u64>,
+
+    #[serde(default)]
+    pub check_asan_log: bool,
+    #[serde(default = "default_bool_true")]
+    pub check_debugger: bool,
+    #[serde(default)]
+    pub check_retry_count: u64,
+
+    /// Maximum number of candidates to test when minimizing each input.
+    #[serde(default = "default_max_tests")]
+    pub minimize_max_tests: u64,
+
+    #[serde(default)]
+    pub dedup: DedupConfig,
+
+    #[serde(flatten)]
+    pub common: CommonConfig,
+}
+
+pub struct MinimizeTask<'a> {
+    config: &'a Config,
+    poller: InputPoller<Message>,
+}
+
+impl<'a> MinimizeTask<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        let working_dir = config.common.task_id.to_string();
+        let poller = InputPoller::new(working_dir);
+
+        Self { config, poller }
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        info!("Starting generic crash minimization task");
+        let heartbeat_client = self.config.common.init_heartbeat().await?;
+        let mut processor = GenericMinimizer::new(self.config, heartbeat_client)?;
+
+        if let Some(crashes) = &self.config.crashes {
+            self.poller.batch_process(&mut processor, crashes).await?;
+        }
+
+        if let Some(queue) = &self.config.input_queue {
+            let callback = CallbackImpl::new(queue.clone(), processor);
+            self.poller.run(callback).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Minimizes crashing inputs by delta debugging, keeping candidates that crash
+/// with the same call stack hash as the original input.
+pub struct GenericMinimizer<'a> {
+    config: &'a Config,
+    tester: Tester<'a>,
+    stack_hasher: StackHasher,
+    heartbeat_client: Option<TaskHeartbeatClient>,
+}
+
+impl<'a> GenericMinimizer<'a> {
+    pub fn new(config: &'a Config, heartbeat_client: Option<TaskHeartbeatClient>) -> Result<Self> {
+        let mut expand = config.common.expand();
+        if let Some(reports) = &config.reports {
+            expand.reports_dir(&reports.path);
+        }
+
+        let tester = Tester::new(
+            &config.target_exe,
+            &config.target_options,
+            &config.target_env,
+            &config.target_timeout,
+            config.check_asan_log,
+            false,
+            config.check_debugger,
+            config.check_retry_count,
+            expand,
+        );
+
+        let stack_hasher = StackHasher::new(&config.dedup)?;
+
+        Ok(Self {
+            config,
+            tester,
+            stack_hasher,
+            heartbeat_client,
+        })
+    }
+
+    pub async fn minimize(&self, input: &Path) -> Result<()> {
+        self.heartbeat_client.alive();
+
+        let expected = match self.signature(&self.tester.test_input(input).await?) {
+            Some(signature) => signature,
+            None => {
+                info!(
+                    "not minimizing input that does not crash: {}",
+                    input.display()
+                );
+                return Ok(());
+            }
+        };
+
+        // Keep the file name, in case the target depends on its extension.
+        let candidate_dir = tempdir()?;
+        let file_name = input
+            .file_name()
+            .ok_or_else(|| format_err!("invalid input path: {}", input.display()))?;
+        let candidate_path = candidate_dir.path().join(file_name);
+
+        let original = fs::read(input).await?;
+        let mut dd = DeltaDebugger::new(original.clone());
+        let mut tests = 0;
+
+        while let Some(candidate) = dd.next_candidate() {
+            if tests >= self.config.minimize_max_tests {
+                verbose!(
+                    "stopped minimizing after {} tests: {}",
+                    tests,
+                    input.display()
+                );
+                break;
+            }
+            tests += 1;
+
+            fs::write(&candidate_path, candidate).await?;
+            let result = self.tester.test_input(&candidate_path).await?;
+            dd.report(self.signature(&result).as_ref() == Some(&expected));
+            self.heartbeat_client.alive();
+        }
+
+        if dd.input().len() == original.len() {
+            info!("unable to minimize crash: {}", input.display());
+            return Ok(());
+        }
+
+        info!(
+            "minimized crash from {} to {} bytes: {}",
+            original.len(),
+            dd.input().len(),
+            input.display()
+        );
+        fs::write(&candidate_path, dd.input()).await?;
+
+        upload_minimized(
+            &self.config.minimized_crashes,
+            &self.config.reports,
+            input,
+            &candidate_path,
+        )
+        .await
+    }
+
+    // The hash that deduplicates the report of a crash, which minimizing must
+    // preserve, or `None` if the input did not crash.
+    fn signature(&self, result: &TestResult) -> Option<String> {
+        if let Some(asan_log) = &result.asan_log {
+            let frames = asan_log.call_stack_frames();
+            let digest = self.stack_hasher.digest(frames);
+            return Some(digest.unwrap_or_else(|| asan_log.call_stack_sha256()));
+        }
+
+        if let Some(crash) = &result.crash {
+            let digest = self.stack_hasher.digest(&crash.call_stack_frames);
+            return Some(digest.unwrap_or_else(|| sha256::digest_iter(&crash.call_stack)));
+        }
+
+        None
+    }
+}
+
+#[async_trait]
+impl<'a> Processor for GenericMinimizer<'a> {
+    async fn process(&mut self, _url: Url, input: &Path) -> Result<()> {
+        self.minimize(input).await
+    }
+}
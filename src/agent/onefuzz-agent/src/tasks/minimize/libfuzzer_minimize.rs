@@ -0,0 +1,131 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::common::upload_minimized;
+use crate::tasks::{
+    config::CommonConfig,
+    generic::input_poller::{CallbackImpl, InputPoller, Processor},
+    heartbeat::*,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use onefuzz::{libfuzzer::LibFuzzer, syncdir::SyncedDir};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use storage_queue::Message;
+use tempfile::tempdir;
+
+fn default_minimize_timeout() -> u64 {
+    5 * 60
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub target_exe: PathBuf,
+    pub target_env: HashMap<String, String>,
+    pub target_options: Vec<String>,
+    pub input_queue: Option<Url>,
+    pub crashes: Option<SyncedDir>,
+    pub minimized_crashes: SyncedDir,
+    pub reports: Option<SyncedDir>,
+
+    /// Time limit for minimizing each input, in seconds.
+    #[serde(default = "default_minimize_timeout")]
+    pub minimize_timeout: u64,
+
+    #[serde(flatten)]
+    pub common: CommonConfig,
+}
+
+pub struct MinimizeTask {
+    config: Arc<Config>,
+    poller: InputPoller<Message>,
+}
+
+impl MinimizeTask {
+    pub fn new(config: impl Into<Arc<Config>>) -> Self {
+        let config = config.into();
+
+        let working_dir = config.common.task_id.to_string();
+        let poller = InputPoller::new(working_dir);
+
+        Self { config, poller }
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        info!("Starting libFuzzer crash minimization task");
+        let mut processor = LibFuzzerMinimizer::new(self.config.clone()).await?;
+
+        if let Some(crashes) = &self.config.crashes {
+            self.poller.batch_process(&mut processor, crashes).await?;
+        }
+
+        if let Some(queue) = &self.config.input_queue {
+            let callback = CallbackImpl::new(queue.clone(), processor);
+            self.poller.run(callback).await?;
+        }
+        Ok(())
+    }
+}
+
+pub struct LibFuzzerMinimizer {
+    config: Arc<Config>,
+    heartbeat_client: Option<TaskHeartbeatClient>,
+}
+
+impl LibFuzzerMinimizer {
+    pub async fn new(config: Arc<Config>) -> Result<Self> {
+        let heartbeat_client = config.common.init_heartbeat().await?;
+
+        Ok(Self {
+            config,
+            heartbeat_client,
+        })
+    }
+
+    pub async fn minimize(&self, input: &Path) -> Result<()> {
+        self.heartbeat_client.alive();
+        let mut expand = self.config.common.expand();
+        if let Some(reports) = &self.config.reports {
+            expand.reports_dir(&reports.path);
+        }
+
+        let fuzzer = LibFuzzer::new(
+            &self.config.target_exe,
+            &self.config.target_options,
+            &self.config.target_env,
+            expand,
+        );
+
+        let output_dir = tempdir()?;
+        let output = output_dir.path().join("minimized");
+
+        let minimized = fuzzer
+            .minimize_crash(input, &output, self.config.minimize_timeout)
+            .await?;
+        if !minimized {
+            info!("unable to minimize crash: {}", input.display());
+            return Ok(());
+        }
+
+        upload_minimized(
+            &self.config.minimized_crashes,
+            &self.config.reports,
+            input,
+            &output,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl Processor for LibFuzzerMinimizer {
+    async fn process(&mut self, _url: Url, input: &Path) -> Result<()> {
+        self.minimize(input).await
+    }
+}
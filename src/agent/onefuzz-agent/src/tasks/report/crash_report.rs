@@ -3,13 +3,18 @@
 
 use anyhow::Result;
 use onefuzz::{
-    asan::AsanLog,
+    asan::{AsanLog, MemoryAccess, MemoryRegion, ShadowBytes},
     blob::{BlobClient, BlobContainerUrl, BlobUrl},
+    dedup::StackHasher,
+    exploitable::Exploitability,
+    input_tester::Crash,
+    sha256,
+    stack::StackFrame,
     syncdir::SyncedDir,
     telemetry::Event::{new_report, new_unable_to_reproduce, new_unique_report},
 };
 
-use reqwest::StatusCode;
+use reqwest::{header, StatusCode};
 use reqwest_retry::SendRetry;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -29,10 +34,40 @@ pub struct CrashReport {
 
     pub call_stack: Vec<String>,
 
+    #[serde(default)]
+    pub call_stack_frames: Vec<StackFrame>,
+
     pub call_stack_sha256: String,
 
+    /// Hash of the call stack, reduced per the task's `dedup` options.
+    ///
+    /// If set, used instead of `call_stack_sha256` to deduplicate reports.
+    pub dedup_sha256: Option<String>,
+
     pub asan_log: Option<String>,
 
+    pub memory_access: Option<MemoryAccess>,
+
+    pub memory_region: Option<MemoryRegion>,
+
+    #[serde(default)]
+    pub free_call_stack: Vec<StackFrame>,
+
+    #[serde(default)]
+    pub allocation_call_stack: Vec<StackFrame>,
+
+    pub shadow_bytes: Option<ShadowBytes>,
+
+    pub exploitability: Option<Exploitability>,
+
+    /// Core file of the crashing process, uploaded to the task's `cores`
+    /// container. Load it with `gdb <executable> <core>`.
+    pub core_dump: Option<InputBlob>,
+
+    /// Smaller input with the same crash, uploaded to the `minimized_crashes`
+    /// container by a minimize task.
+    pub minimized_input: Option<InputBlob>,
+
     pub task_id: Uuid,
 
     pub job_id: Uuid,
@@ -57,7 +92,7 @@ pub enum CrashTestResult {
 
 // Conditionally upload a report, if it would not be a duplicate.
 //
-// Use SHA-256 of call stack as dedupe key.
+// Use SHA-256 of call stack as dedupe key, or the reduced stack hash, if set.
 async fn upload_deduped(report: &CrashReport, container: &BlobContainerUrl) -> Result<()> {
     let blob = BlobClient::new();
     let deduped_name = report.unique_blob_name();
@@ -91,6 +126,37 @@ async fn upload_no_repro(report: &NoCrash, container: &BlobContainerUrl) -> Resu
     Ok(())
 }
 
+/// Link a minimized input from the report of the original input in `reports`.
+///
+/// Returns `false` if there is no such report, such as when the original input
+/// has not been reported yet.
+pub async fn link_minimized_input(
+    reports: &BlobContainerUrl,
+    input_sha256: &str,
+    minimized_input: InputBlob,
+) -> Result<bool> {
+    let url = reports.blob(format!("{}.json", input_sha256)).url();
+    let blob = BlobClient::new();
+
+    let response = match blob.get_if_exists(&url).await? {
+        Some(response) => response,
+        None => return Ok(false),
+    };
+
+    let etag = response.headers().get(header::ETAG).cloned();
+    let mut report: CrashReport = response.json().await?;
+    report.minimized_input = Some(minimized_input);
+
+    // Don't overwrite a report that changed since we read it.
+    let mut request = blob.put(url).json(&report);
+    if let Some(etag) = etag {
+        request = request.header(header::IF_MATCH, etag);
+    }
+    request.send_retry_default().await?.error_for_status()?;
+
+    Ok(true)
+}
+
 impl CrashTestResult {
     pub async fn upload(
         &self,
@@ -140,6 +206,7 @@ impl CrashReport {
         executable: impl Into<PathBuf>,
         input_blob: InputBlob,
         input_sha256: String,
+        stack_hasher: &StackHasher,
     ) -> Self {
         Self {
             input_sha256,
@@ -148,8 +215,56 @@ impl CrashReport {
             crash_type: asan_log.fault_type().into(),
             crash_site: asan_log.summary().into(),
             call_stack: asan_log.call_stack().to_vec(),
+            call_stack_frames: asan_log.call_stack_frames().to_vec(),
             call_stack_sha256: asan_log.call_stack_sha256(),
+            dedup_sha256: stack_hasher.digest(asan_log.call_stack_frames()),
             asan_log: Some(asan_log.text().to_string()),
+            memory_access: asan_log.memory_access().cloned(),
+            memory_region: asan_log.memory_region().cloned(),
+            free_call_stack: asan_log.free_call_stack().to_vec(),
+            allocation_call_stack: asan_log.allocation_call_stack().to_vec(),
+            shadow_bytes: asan_log.shadow_bytes().cloned(),
+            exploitability: None,
+            core_dump: None,
+            minimized_input: None,
+            task_id,
+            job_id,
+        }
+    }
+
+    /// Report a crash that was not detected by a sanitizer, such as one caught by
+    /// a debugger, a Rust or Go panic, or an uncaught JVM exception.
+    pub fn from_crash(
+        crash: Crash,
+        task_id: Uuid,
+        job_id: Uuid,
+        executable: impl Into<PathBuf>,
+        input_blob: InputBlob,
+        input_sha256: String,
+        stack_hasher: &StackHasher,
+    ) -> Self {
+        let call_stack_sha256 = sha256::digest_iter(&crash.call_stack);
+        let dedup_sha256 = stack_hasher.digest(&crash.call_stack_frames);
+
+        Self {
+            input_sha256,
+            input_blob,
+            executable: executable.into(),
+            crash_type: crash.crash_type,
+            crash_site: crash.crash_site,
+            call_stack: crash.call_stack,
+            call_stack_frames: crash.call_stack_frames,
+            call_stack_sha256,
+            dedup_sha256,
+            asan_log: None,
+            memory_access: None,
+            memory_region: None,
+            free_call_stack: vec![],
+            allocation_call_stack: vec![],
+            shadow_bytes: None,
+            exploitability: crash.exploitability,
+            core_dump: None,
+            minimized_input: None,
             task_id,
             job_id,
         }
@@ -159,8 +274,16 @@ impl CrashReport {
         format!("{}.json", self.input_sha256)
     }
 
+    pub fn core_dump_blob_name(&self) -> String {
+        format!("{}.core", self.input_sha256)
+    }
+
     pub fn unique_blob_name(&self) -> String {
-        format!("{}.json", self.call_stack_sha256)
+        let key = self
+            .dedup_sha256
+            .as_ref()
+            .unwrap_or(&self.call_stack_sha256);
+        format!("{}.json", key)
     }
 }
 
@@ -9,7 +9,13 @@ use crate::tasks::{
 };
 use anyhow::Result;
 use async_trait::async_trait;
-use onefuzz::{blob::BlobUrl, input_tester::Tester, sha256, syncdir::SyncedDir};
+use onefuzz::{
+    blob::{BlobClient, BlobUrl},
+    dedup::{DedupConfig, StackHasher},
+    input_tester::Tester,
+    sha256,
+    syncdir::SyncedDir,
+};
 use reqwest::Url;
 use serde::Deserialize;
 use std::{
@@ -37,6 +43,11 @@ pub struct Config {
     pub unique_reports: SyncedDir,
     pub no_repro: Option<SyncedDir>,
 
+    /// Container for core files of reproduced crashes, which are linked from the
+    /// crash reports. Requires `check_debugger`, and is only supported on Linux.
+    #[serde(default)]
+    pub cores: Option<SyncedDir>,
+
     pub target_timeout: Option<u64>,
 
     #[serde(default)]
@@ -46,6 +57,9 @@ pub struct Config {
     #[serde(default)]
     pub check_retry_count: u64,
 
+    #[serde(default)]
+    pub dedup: DedupConfig,
+
     #[serde(flatten)]
     pub common: CommonConfig,
 }
@@ -66,7 +80,12 @@ impl<'a> ReportTask<'a> {
     pub async fn run(&mut self) -> Result<()> {
         info!("Starting generic crash report task");
         let heartbeat_client = self.config.common.init_heartbeat().await?;
-        let mut processor = GenericReportProcessor::new(&self.config, heartbeat_client);
+
+        if let Some(cores) = &self.config.cores {
+            cores.init().await?;
+        }
+
+        let mut processor = GenericReportProcessor::new(&self.config, heartbeat_client)?;
 
         if let Some(crashes) = &self.config.crashes {
             self.poller.batch_process(&mut processor, &crashes).await?;
@@ -83,12 +102,18 @@ impl<'a> ReportTask<'a> {
 pub struct GenericReportProcessor<'a> {
     config: &'a Config,
     tester: Tester<'a>,
+    stack_hasher: StackHasher,
     heartbeat_client: Option<TaskHeartbeatClient>,
 }
 
 impl<'a> GenericReportProcessor<'a> {
-    pub fn new(config: &'a Config, heartbeat_client: Option<TaskHeartbeatClient>) -> Self {
-        let tester = Tester::new(
+    pub fn new(config: &'a Config, heartbeat_client: Option<TaskHeartbeatClient>) -> Result<Self> {
+        let mut expand = config.common.expand();
+        if let Some(reports) = &config.reports {
+            expand.reports_dir(&reports.path);
+        }
+
+        let mut tester = Tester::new(
             &config.target_exe,
             &config.target_options,
             &config.target_env,
@@ -97,13 +122,21 @@ impl<'a> GenericReportProcessor<'a> {
             false,
             config.check_debugger,
             config.check_retry_count,
+            expand,
         );
 
-        Self {
+        if let Some(cores) = &config.cores {
+            tester = tester.core_dump_dir(&cores.path);
+        }
+
+        let stack_hasher = StackHasher::new(&config.dedup)?;
+
+        Ok(Self {
             config,
             tester,
+            stack_hasher,
             heartbeat_client,
-        }
+        })
     }
 
     pub async fn test_input(&self, input_url: Url, input: &Path) -> Result<CrashTestResult> {
@@ -113,34 +146,34 @@ impl<'a> GenericReportProcessor<'a> {
         let job_id = self.config.common.job_id;
         let input_blob = InputBlob::from(BlobUrl::new(input_url)?);
 
-        let test_report = self.tester.test_input(input).await?;
+        let mut test_report = self.tester.test_input(input).await?;
+
+        // A sanitizer report may also have been caught by the debugger, with a core.
+        let core_dump = test_report
+            .crash
+            .as_mut()
+            .and_then(|crash| crash.core_dump.take());
 
-        if let Some(asan_log) = test_report.asan_log {
-            let crash_report = CrashReport::new(
+        let mut crash_report = if let Some(asan_log) = test_report.asan_log {
+            CrashReport::new(
                 asan_log,
                 task_id,
                 job_id,
                 &self.config.target_exe,
                 input_blob,
                 input_sha256,
-            );
-            Ok(CrashTestResult::CrashReport(crash_report))
+                &self.stack_hasher,
+            )
         } else if let Some(crash) = test_report.crash {
-            let call_stack_sha256 = sha256::digest_iter(&crash.call_stack);
-            let crash_report = CrashReport {
-                input_blob,
-                input_sha256,
-                executable: PathBuf::from(&self.config.target_exe),
-                call_stack: crash.call_stack,
-                crash_type: crash.crash_type,
-                crash_site: crash.crash_site,
-                call_stack_sha256,
-                asan_log: None,
+            CrashReport::from_crash(
+                crash,
                 task_id,
                 job_id,
-            };
-
-            Ok(CrashTestResult::CrashReport(crash_report))
+                &self.config.target_exe,
+                input_blob,
+                input_sha256,
+                &self.stack_hasher,
+            )
         } else {
             let no_repro = NoCrash {
                 input_blob,
@@ -152,7 +185,47 @@ impl<'a> GenericReportProcessor<'a> {
                 error: test_report.error.map(|e| format!("{}", e)),
             };
 
-            Ok(CrashTestResult::NoRepro(no_repro))
+            return Ok(CrashTestResult::NoRepro(no_repro));
+        };
+
+        if let (Some(cores), Some(core_dump)) = (&self.config.cores, core_dump) {
+            crash_report.core_dump = self
+                .upload_core_dump(cores, &core_dump, crash_report.core_dump_blob_name())
+                .await;
+        }
+
+        Ok(CrashTestResult::CrashReport(crash_report))
+    }
+
+    // Upload a core file next to the report, and remove the local copy.
+    //
+    // A missing core should not lose the report, so failures are only logged.
+    async fn upload_core_dump(
+        &self,
+        cores: &SyncedDir,
+        path: &Path,
+        name: String,
+    ) -> Option<InputBlob> {
+        let blob_url = cores.url.blob(name);
+        let result: Result<()> = async {
+            BlobClient::new()
+                .put_file(blob_url.url(), path)
+                .await?
+                .error_for_status()?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(err) = tokio::fs::remove_file(path).await {
+            warn!("unable to remove core file {}: {}", path.display(), err);
+        }
+
+        match result {
+            Ok(_) => Some(InputBlob::from(blob_url)),
+            Err(err) => {
+                warn!("unable to upload core file {}: {}", path.display(), err);
+                None
+            }
         }
     }
 }
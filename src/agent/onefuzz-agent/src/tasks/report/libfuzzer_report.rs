@@ -5,7 +5,13 @@ use super::crash_report::*;
 use crate::tasks::{config::CommonConfig, generic::input_poller::*, heartbeat::*};
 use anyhow::Result;
 use async_trait::async_trait;
-use onefuzz::{blob::BlobUrl, libfuzzer::LibFuzzer, sha256, syncdir::SyncedDir};
+use onefuzz::{
+    blob::BlobUrl,
+    dedup::{DedupConfig, StackHasher},
+    libfuzzer::LibFuzzer,
+    sha256,
+    syncdir::SyncedDir,
+};
 use reqwest::Url;
 use serde::Deserialize;
 use std::{
@@ -30,6 +36,9 @@ pub struct Config {
     #[serde(default)]
     pub check_retry_count: u64,
 
+    #[serde(default)]
+    pub dedup: DedupConfig,
+
     #[serde(flatten)]
     pub common: CommonConfig,
 }
@@ -67,25 +76,34 @@ impl ReportTask {
 
 pub struct AsanProcessor {
     config: Arc<Config>,
+    stack_hasher: StackHasher,
     heartbeat_client: Option<TaskHeartbeatClient>,
 }
 
 impl AsanProcessor {
     pub async fn new(config: Arc<Config>) -> Result<Self> {
+        let stack_hasher = StackHasher::new(&config.dedup)?;
         let heartbeat_client = config.common.init_heartbeat().await?;
 
         Ok(Self {
             config,
+            stack_hasher,
             heartbeat_client,
         })
     }
 
     pub async fn test_input(&self, input_url: Url, input: &Path) -> Result<CrashTestResult> {
         self.heartbeat_client.alive();
+        let mut expand = self.config.common.expand();
+        if let Some(reports) = &self.config.reports {
+            expand.reports_dir(&reports.path);
+        }
+
         let fuzzer = LibFuzzer::new(
             &self.config.target_exe,
             &self.config.target_options,
             &self.config.target_env,
+            expand,
         );
 
         let task_id = self.config.common.task_id;
@@ -101,31 +119,40 @@ impl AsanProcessor {
             )
             .await?;
 
-        match test_report.asan_log {
-            Some(asan_log) => {
-                let crash_report = CrashReport::new(
-                    asan_log,
-                    task_id,
-                    job_id,
-                    &self.config.target_exe,
-                    input_blob,
-                    input_sha256,
-                );
-                Ok(CrashTestResult::CrashReport(crash_report))
-            }
-            None => {
-                let no_repro = NoCrash {
-                    input_blob,
-                    input_sha256,
-                    executable: PathBuf::from(&self.config.target_exe),
-                    task_id,
-                    job_id,
-                    tries: 1 + self.config.check_retry_count,
-                    error: test_report.error.map(|e| format!("{}", e)),
-                };
-
-                Ok(CrashTestResult::NoRepro(no_repro))
-            }
+        if let Some(asan_log) = test_report.asan_log {
+            let crash_report = CrashReport::new(
+                asan_log,
+                task_id,
+                job_id,
+                &self.config.target_exe,
+                input_blob,
+                input_sha256,
+                &self.stack_hasher,
+            );
+            Ok(CrashTestResult::CrashReport(crash_report))
+        } else if let Some(crash) = test_report.crash {
+            let crash_report = CrashReport::from_crash(
+                crash,
+                task_id,
+                job_id,
+                &self.config.target_exe,
+                input_blob,
+                input_sha256,
+                &self.stack_hasher,
+            );
+            Ok(CrashTestResult::CrashReport(crash_report))
+        } else {
+            let no_repro = NoCrash {
+                input_blob,
+                input_sha256,
+                executable: PathBuf::from(&self.config.target_exe),
+                task_id,
+                job_id,
+                tries: 1 + self.config.check_retry_count,
+                error: test_report.error.map(|e| format!("{}", e)),
+            };
+
+            Ok(CrashTestResult::NoRepro(no_repro))
         }
     }
 }
@@ -4,18 +4,24 @@
 use anyhow::{Error, Result};
 use onefuzz::telemetry::EventData;
 use std::path::Path;
-use tokio::io::AsyncBufReadExt;
 
 pub async fn read_stats(output_path: impl AsRef<Path>) -> Result<Vec<EventData>, Error> {
-    let f = tokio::fs::File::open(output_path).await?;
+    let text = tokio::fs::read_to_string(output_path).await?;
+    Ok(parse_stats(&text))
+}
+
+fn parse_stats(text: &str) -> Vec<EventData> {
     let mut stats = Vec::new();
 
-    let reader = tokio::io::BufReader::new(f);
-    let mut lines = reader.lines();
-    while let Ok(Some(line)) = lines.next_line().await {
-        let mut name_value = line.split(':');
-        let name = name_value.next().unwrap().trim();
-        let value = name_value.next().unwrap().trim();
+    for line in text.lines() {
+        let mut name_value = line.splitn(2, ':');
+        let (name, value) = match (name_value.next(), name_value.next()) {
+            (Some(name), Some(value)) => (name.trim(), value.trim()),
+            _ => {
+                verbose!("ignoring stats line without a value: {:?}", line);
+                continue;
+            }
+        };
 
         match name {
             "target_mode" => {
@@ -91,7 +97,8 @@ pub async fn read_stats(output_path: impl AsRef<Path>) -> Result<Vec<EventData>,
             }
         }
     }
-    Ok(stats)
+
+    stats
 }
 
 #[cfg(test)]
@@ -106,4 +113,16 @@ mod tests {
         assert!(results.contains(&EventData::ExecsSecond(2666.67)));
         assert!(results.contains(&EventData::Mode("default".to_string())));
     }
+
+    #[test]
+    fn test_stats_parse_malformed() {
+        let text = "\nexecs_done : 10\nnot a stat\ncommand_line : afl-fuzz -i in -- ./fuzz:1";
+        assert_eq!(
+            parse_stats(text),
+            vec![
+                EventData::Count(10),
+                EventData::CommandLine("afl-fuzz -i in -- ./fuzz:1".to_string()),
+            ]
+        );
+    }
 }
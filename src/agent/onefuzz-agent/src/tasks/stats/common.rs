@@ -1,7 +1,11 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use super::afl;
+use super::{
+    afl,
+    generic::{self, StatsFields},
+    honggfuzz,
+};
 use anyhow::{Error, Result};
 use onefuzz::{
     jitter::delay_with_jitter,
@@ -13,14 +17,44 @@ pub const STATS_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
 #[derive(Debug, Deserialize, Clone)]
 pub enum StatsFormat {
     AFL,
+    Honggfuzz,
+
+    /// The stats libFuzzer logs with `-print_final_stats=1`.
+    LibFuzzer,
+
+    /// `name: value` lines, read per the configured stats fields.
+    KeyValue,
+
+    /// A JSON object, read per the configured stats fields.
+    Json,
 }
 
-pub async fn monitor_stats(path: Option<String>, format: Option<StatsFormat>) -> Result<(), Error> {
+/// Report the stats of a stats file as runtime stats.
+///
+/// The `fields` name the stats of the `KeyValue` and `Json` formats, and add to
+/// those of the `LibFuzzer` format.
+pub async fn monitor_stats(
+    path: Option<String>,
+    format: Option<StatsFormat>,
+    fields: Option<StatsFields>,
+) -> Result<(), Error> {
     if let Some(path) = path {
         if let Some(format) = format {
+            let mut fields = fields.unwrap_or_default();
+            if let StatsFormat::LibFuzzer = format {
+                for (name, field) in generic::libfuzzer_fields() {
+                    fields.entry(name).or_insert(field);
+                }
+            }
+
             loop {
                 let stats = match format {
                     StatsFormat::AFL => afl::read_stats(&path).await,
+                    StatsFormat::Honggfuzz => honggfuzz::read_stats(&path).await,
+                    StatsFormat::LibFuzzer | StatsFormat::KeyValue => {
+                        generic::read_key_value_stats(&path, &fields).await
+                    }
+                    StatsFormat::Json => generic::read_json_stats(&path, &fields).await,
                 };
                 if let Ok(stats) = stats {
                     track_event(runtime_stats, stats);
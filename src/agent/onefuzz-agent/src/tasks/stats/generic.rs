@@ -0,0 +1,227 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use anyhow::Result;
+use onefuzz::telemetry::EventData;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, path::Path};
+
+/// Names of the stats in a stats file, mapped to the runtime stats they are
+/// reported as.
+pub type StatsFields = HashMap<String, StatsField>;
+
+/// A runtime stat, named as in telemetry.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsField {
+    Mode,
+    Count,
+    #[serde(rename = "execs_sec")]
+    ExecsSecond,
+    Coverage,
+    CoverageEdges,
+    CoverageFeatures,
+    CoveragePaths,
+    CoveragePathsFavored,
+    CoveragePathsFound,
+    CoveragePathsImported,
+    CorpusUnits,
+    CorpusSize,
+    MaxInputLength,
+    RssMb,
+    NewFunctions,
+    Crashes,
+    UniqueCrashes,
+}
+
+impl StatsField {
+    pub fn parse(self, value: &str) -> Option<EventData> {
+        let value = value.trim();
+        let stat = match self {
+            Self::Mode => EventData::Mode(value.to_string()),
+            Self::Count => EventData::Count(value.parse().ok()?),
+            Self::ExecsSecond => EventData::ExecsSecond(value.parse().ok()?),
+            Self::Coverage => EventData::Coverage(value.trim_end_matches('%').parse().ok()?),
+            Self::CoverageEdges => EventData::CoverageEdges(value.parse().ok()?),
+            Self::CoverageFeatures => EventData::CoverageFeatures(value.parse().ok()?),
+            Self::CoveragePaths => EventData::CoveragePaths(value.parse().ok()?),
+            Self::CoveragePathsFavored => EventData::CoveragePathsFavored(value.parse().ok()?),
+            Self::CoveragePathsFound => EventData::CoveragePathsFound(value.parse().ok()?),
+            Self::CoveragePathsImported => EventData::CoveragePathsImported(value.parse().ok()?),
+            Self::CorpusUnits => EventData::CorpusUnits(value.parse().ok()?),
+            Self::CorpusSize => EventData::CorpusSize(value.parse().ok()?),
+            Self::MaxInputLength => EventData::MaxInputLength(value.parse().ok()?),
+            Self::RssMb => EventData::RssMb(value.parse().ok()?),
+            Self::NewFunctions => EventData::NewFunctions(value.parse().ok()?),
+            Self::Crashes => EventData::Crashes(value.parse().ok()?),
+            Self::UniqueCrashes => EventData::UniqueCrashes(value.parse().ok()?),
+        };
+
+        Some(stat)
+    }
+}
+
+/// Fields of the stats libFuzzer logs on exit with `-print_final_stats=1`.
+pub fn libfuzzer_fields() -> StatsFields {
+    vec![
+        ("stat::number_of_executed_units", StatsField::Count),
+        ("stat::average_exec_per_sec", StatsField::ExecsSecond),
+        ("stat::peak_rss_mb", StatsField::RssMb),
+    ]
+    .into_iter()
+    .map(|(name, field)| (name.to_string(), field))
+    .collect()
+}
+
+/// Read a stats file of `name: value` lines, such as:
+///
+/// ```text
+/// stat::number_of_executed_units: 1048576
+/// stat::average_exec_per_sec:     699050
+/// ```
+pub async fn read_key_value_stats(
+    path: impl AsRef<Path>,
+    fields: &StatsFields,
+) -> Result<Vec<EventData>> {
+    let text = tokio::fs::read_to_string(path).await?;
+    Ok(parse_key_value_stats(&text, fields))
+}
+
+/// Read a JSON stats file, in which the stats of nested objects are named by
+/// their path, as in `stats.execs` for `{"stats": {"execs": 1}}`.
+pub async fn read_json_stats(
+    path: impl AsRef<Path>,
+    fields: &StatsFields,
+) -> Result<Vec<EventData>> {
+    let text = tokio::fs::read_to_string(path).await?;
+    parse_json_stats(&text, fields)
+}
+
+fn parse_key_value_stats(text: &str, fields: &StatsFields) -> Vec<EventData> {
+    let values = text.lines().filter_map(split_key_value);
+    parse_values(values, fields)
+}
+
+fn parse_json_stats(text: &str, fields: &StatsFields) -> Result<Vec<EventData>> {
+    let json: Value = serde_json::from_str(text)?;
+
+    let mut values = vec![];
+    flatten_json("", &json, &mut values);
+
+    let values = values
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()));
+    Ok(parse_values(values, fields))
+}
+
+fn parse_values<'a>(
+    values: impl Iterator<Item = (&'a str, &'a str)>,
+    fields: &StatsFields,
+) -> Vec<EventData> {
+    let mut stats = vec![];
+
+    for (name, value) in values {
+        let field = match fields.get(name) {
+            Some(field) => field,
+            None => continue,
+        };
+
+        match field.parse(value) {
+            Some(stat) => stats.push(stat),
+            None => error!("unable to parse telemetry: {:?} {:?}", name, value),
+        }
+    }
+
+    stats
+}
+
+// Split a line at the first `:` that isn't part of a `::`, since names may be
+// namespaced, as in `stat::peak_rss_mb: 562`.
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let bytes = line.as_bytes();
+    let offset = (0..bytes.len()).find(|&i| {
+        bytes[i] == b':' && bytes.get(i + 1) != Some(&b':') && (i == 0 || bytes[i - 1] != b':')
+    })?;
+
+    Some((line[..offset].trim(), line[offset + 1..].trim()))
+}
+
+fn flatten_json(prefix: &str, value: &Value, values: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(object) => {
+            for (name, value) in object {
+                let path = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten_json(&path, value, values);
+            }
+        }
+        Value::String(value) => values.push((prefix.to_string(), value.clone())),
+        Value::Number(value) => values.push((prefix.to_string(), value.to_string())),
+        Value::Bool(value) => values.push((prefix.to_string(), value.to_string())),
+        Value::Array(_) | Value::Null => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_libfuzzer_stats() {
+        let results = read_key_value_stats("data/libfuzzer-final-stats.txt", &libfuzzer_fields())
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                EventData::Count(1048576),
+                EventData::ExecsSecond(699050.0),
+                EventData::RssMb(562),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_value_stats() {
+        let fields: StatsFields = serde_json::from_str(
+            r#"{"execs": "count", "speed": "execs_sec", "cov": "coverage", "mode": "mode"}"#,
+        )
+        .unwrap();
+
+        let text = "no separator\nexecs : 10\nspeed:bad\ncov: 12.5%\nmode: a:b\nother: 1";
+        assert_eq!(
+            parse_key_value_stats(text, &fields),
+            vec![
+                EventData::Count(10),
+                EventData::Coverage(12.5),
+                EventData::Mode("a:b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_stats() {
+        let fields: StatsFields =
+            serde_json::from_str(r#"{"stats.execs": "count", "crashes": "crashes"}"#).unwrap();
+
+        let text = r#"{"stats": {"execs": 10, "list": [1]}, "crashes": "2", "other": null}"#;
+        let results = parse_json_stats(text, &fields).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.contains(&EventData::Count(10)));
+        assert!(results.contains(&EventData::Crashes(2)));
+
+        assert!(parse_json_stats("not json", &fields).is_err());
+    }
+
+    #[test]
+    fn test_split_key_value() {
+        assert_eq!(split_key_value("a: b"), Some(("a", "b")));
+        assert_eq!(split_key_value("a::b: c:d"), Some(("a::b", "c:d")));
+        assert_eq!(split_key_value("a::b"), None);
+        assert_eq!(split_key_value("a"), None);
+    }
+}
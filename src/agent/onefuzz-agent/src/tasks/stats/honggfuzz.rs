@@ -0,0 +1,89 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use anyhow::Result;
+use onefuzz::telemetry::EventData;
+use std::path::Path;
+
+/// Read the latest statistics from a honggfuzz `--statsfile`.
+///
+/// Honggfuzz appends a line of comma-separated values to the file on every
+/// update, with the names of the columns on a leading comment line:
+///
+/// ```text
+/// # unix_time, last_cov_update, total_exec, exec_per_sec, crashes, unique_crashes, hangs, edge_cov, block_cov
+/// 1607372350, 1607372349, 1066, 1066, 0, 0, 0, 139, 220
+/// ```
+pub async fn read_stats(output_path: impl AsRef<Path>) -> Result<Vec<EventData>> {
+    let text = tokio::fs::read_to_string(output_path).await?;
+    parse_stats(&text)
+}
+
+fn parse_stats(text: &str) -> Result<Vec<EventData>> {
+    let header = text
+        .lines()
+        .find_map(|line| line.trim().strip_prefix('#'))
+        .ok_or_else(|| format_err!("missing stats header"))?;
+
+    let values = text
+        .lines()
+        .map(|line| line.trim())
+        .rfind(|line| !line.is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| format_err!("no stats found"))?;
+
+    let mut stats = Vec::new();
+    for (name, value) in header.split(',').zip(values.split(',')) {
+        let name = name.trim();
+        let value = value.trim();
+
+        let parsed = match name {
+            "total_exec" => value.parse().map(EventData::Count).ok(),
+            "exec_per_sec" => value.parse().map(EventData::ExecsSecond).ok(),
+            "crashes" => value.parse().map(EventData::Crashes).ok(),
+            "unique_crashes" => value.parse().map(EventData::UniqueCrashes).ok(),
+            "edge_cov" => value.parse().map(EventData::CoverageEdges).ok(),
+            // ignored telemetry
+            "unix_time" | "last_cov_update" | "hangs" | "block_cov" => continue,
+            _ => {
+                warn!("unsupported telemetry: {} {}", name, value);
+                continue;
+            }
+        };
+
+        match parsed {
+            Some(stat) => stats.push(stat),
+            None => error!("unable to parse telemetry: {:?} {:?}", name, value),
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stats_parse() {
+        let results = read_stats("data/honggfuzz-stats.txt").await.unwrap();
+        assert_eq!(
+            results,
+            vec![
+                EventData::Count(10938),
+                EventData::ExecsSecond(5126.0),
+                EventData::Crashes(7),
+                EventData::UniqueCrashes(2),
+                EventData::CoverageEdges(185),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_parse_errors() {
+        assert!(parse_stats("").is_err());
+        assert!(parse_stats("# total_exec\n").is_err());
+
+        let results = parse_stats("# total_exec, crashes\nbad, 3").unwrap();
+        assert_eq!(results, vec![EventData::Crashes(3)]);
+    }
+}
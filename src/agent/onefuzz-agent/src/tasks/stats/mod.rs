@@ -3,3 +3,5 @@
 
 pub mod afl;
 pub mod common;
+pub mod generic;
+pub mod honggfuzz;
@@ -1,13 +1,16 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use crate::tasks::heartbeat::{HeartbeatSender, TaskHeartbeatClient};
 use anyhow::Result;
 use async_trait::async_trait;
 use onefuzz::jitter::delay_with_jitter;
 use reqwest::Url;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
-use tokio::{fs, io};
+use tokio::{fs, io, process::Child, sync::Notify};
+
+const HEARTBEAT_PERIOD: Duration = Duration::from_secs(60);
 
 pub async fn download_input(input_url: Url, dst: impl AsRef<Path>) -> Result<PathBuf> {
     let file_name = input_url.path_segments().unwrap().last().unwrap();
@@ -71,6 +74,40 @@ impl CheckNotify for tokio::sync::Notify {
     }
 }
 
+/// Send task heartbeats until `stopped` is notified.
+pub async fn heartbeat_process(
+    stopped: &Notify,
+    heartbeat_client: Option<TaskHeartbeatClient>,
+) -> Result<()> {
+    while !stopped.is_notified(HEARTBEAT_PERIOD).await {
+        heartbeat_client.alive();
+    }
+    Ok(())
+}
+
+/// Wait for the fuzzer process `name` to exit, then notify `stopped`.
+///
+/// It is an error for the process to fail, with its output as the message.
+pub async fn monitor_process(process: Child, name: &str, stopped: &Notify) -> Result<()> {
+    verbose!("waiting for {} to exit", name);
+    let output = process.wait_with_output().await?;
+    verbose!("{} exited with {:?}", name, output.status);
+    stopped.notify();
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let message = format!(
+            "{} failed with {}: {} {}",
+            name, output.status, stderr, stdout
+        );
+        error!("{}", message);
+        bail!("{}", message);
+    }
+}
+
 pub fn parse_key_value(value: String) -> Result<(String, String)> {
     let offset = value
         .find('=')
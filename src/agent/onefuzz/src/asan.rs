@@ -1,12 +1,41 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use crate::sha256;
+use crate::{
+    sha256,
+    stack::{mark_inlined, parse_call_stack as parse_stack_frames, StackFrame},
+};
 use anyhow::Result;
 use regex::Regex;
-use std::{collections::HashMap, hash::BuildHasher, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::BuildHasher,
+    path::Path,
+};
 use tokio::{fs, stream::StreamExt};
 
+lazy_static! {
+    static ref REPORT_HEADER: Regex = Regex::new(
+        r"^(?:==\d+==\s*)?(?:ERROR|WARNING): (\w*Sanitizer|libFuzzer): ((data race|deadly signal|detected memory leaks|[^ ]+).*)$",
+    )
+    .unwrap();
+    static ref UBSAN_RUNTIME_ERROR: Regex = Regex::new(r"^.+: runtime error: .+$").unwrap();
+    static ref LEAK: Regex = Regex::new(r"^(Direct|Indirect) leak of .*$").unwrap();
+    static ref SUMMARY: Regex = Regex::new(r"^SUMMARY: ((\w+): .*)$").unwrap();
+    static ref FAULT_SUMMARY: Regex =
+        Regex::new(r"SUMMARY: ((\w+): (data race|deadly signal|[^ \n]+).*)").unwrap();
+    static ref MEMORY_ACCESS: Regex = Regex::new(
+        r"(?i)\b(READ|WRITE) of size (\d+) at (0x[0-9a-f]+)(?: (?:by )?thread ([^\s:]+))?",
+    )
+    .unwrap();
+    static ref MEMORY_REGION: Regex = Regex::new(
+        r"(0x[0-9a-f]+) is located (\d+) bytes (to the left of|to the right of|inside of) (\d+)-byte region \[(0x[0-9a-f]+),(0x[0-9a-f]+)\)",
+    )
+    .unwrap();
+    static ref SHADOW_FAULTING_BYTE: Regex = Regex::new(r"\[([0-9a-f]{2})\]").unwrap();
+    static ref SHADOW_LEGEND: Regex = Regex::new(r"^\s+(.+?):\s+(.+?)\s*$").unwrap();
+}
+
 #[derive(Clone, Debug)]
 pub struct AsanLog {
     text: String,
@@ -14,12 +43,173 @@ pub struct AsanLog {
     summary: String,
     fault_type: String,
     call_stack: Vec<String>,
+    call_stack_frames: Vec<StackFrame>,
+    memory_access: Option<MemoryAccess>,
+    memory_region: Option<MemoryRegion>,
+    free_call_stack: Vec<StackFrame>,
+    allocation_call_stack: Vec<StackFrame>,
+    shadow_bytes: Option<ShadowBytes>,
+    reports: Vec<SanitizerReport>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SanitizerKind {
+    AddressSanitizer,
+    LeakSanitizer,
+    MemorySanitizer,
+    ThreadSanitizer,
+    UndefinedBehaviorSanitizer,
+    LibFuzzer,
+    Other(String),
+}
+
+impl SanitizerKind {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "AddressSanitizer" => Self::AddressSanitizer,
+            "LeakSanitizer" => Self::LeakSanitizer,
+            "MemorySanitizer" => Self::MemorySanitizer,
+            "ThreadSanitizer" => Self::ThreadSanitizer,
+            "UndefinedBehaviorSanitizer" => Self::UndefinedBehaviorSanitizer,
+            "libFuzzer" => Self::LibFuzzer,
+            _ => Self::Other(name.to_owned()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::AddressSanitizer => "AddressSanitizer",
+            Self::LeakSanitizer => "LeakSanitizer",
+            Self::MemorySanitizer => "MemorySanitizer",
+            Self::ThreadSanitizer => "ThreadSanitizer",
+            Self::UndefinedBehaviorSanitizer => "UndefinedBehaviorSanitizer",
+            Self::LibFuzzer => "libFuzzer",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+/// A single finding in a sanitizer log.
+///
+/// One log may hold many reports, e.g. every leak found by LSan, or each
+/// `runtime error:` printed by UBSan when run with `halt_on_error=0`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SanitizerReport {
+    pub sanitizer: SanitizerKind,
+    pub fault_type: String,
+    pub summary: String,
+
+    /// Every stack printed as part of the report, in order. The first is the
+    /// stack of the fault itself.
+    pub stacks: Vec<SanitizerStack>,
+}
+
+impl SanitizerReport {
+    fn new(
+        sanitizer: SanitizerKind,
+        fault_type: impl Into<String>,
+        summary: impl Into<String>,
+    ) -> Self {
+        Self {
+            sanitizer,
+            fault_type: fault_type.into(),
+            summary: summary.into(),
+            stacks: vec![],
+        }
+    }
+
+    /// Stack of the fault, if any.
+    pub fn call_stack(&self) -> &[StackFrame] {
+        self.stacks
+            .first()
+            .map(|s| s.frames.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SanitizerStack {
+    /// Line that introduced the stack, e.g. `freed by thread T0 here:`.
+    pub description: Option<String>,
+    pub frames: Vec<StackFrame>,
+}
+
+/// The faulting memory access described by a sanitizer report, e.g.
+/// `WRITE of size 4 at 0x602000000050 thread T0`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MemoryAccess {
+    pub kind: AccessKind,
+    pub size: u64,
+    pub address: u64,
+    pub thread: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// Location of the faulting address relative to a heap region, e.g.
+/// `0x602000000050 is located 0 bytes to the right of 16-byte region`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MemoryRegion {
+    pub address: u64,
+    pub offset: u64,
+    pub relation: RegionRelation,
+    pub size: u64,
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegionRelation {
+    LeftOf,
+    RightOf,
+    Inside,
+}
+
+/// Shadow memory dump printed after an ASan report.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ShadowBytes {
+    /// Rows of the dump, as printed.
+    pub rows: Vec<String>,
+
+    /// Shadow byte of the faulting address, bracketed in the dump.
+    pub faulting_byte: Option<u8>,
+
+    /// Legend entry that describes `faulting_byte`, e.g. `Freed heap region`.
+    pub faulting_byte_description: Option<String>,
+
+    /// Description of each kind of shadow byte, mapped to its values.
+    pub legend: BTreeMap<String, String>,
 }
 
 impl AsanLog {
     pub fn parse(text: String) -> Option<Self> {
-        let (summary, sanitizer, fault_type) = parse_summary(&text)?;
+        let reports = parse_reports(&text);
+        let (summary, sanitizer, fault_type) = match parse_summary(&text) {
+            Some(summary) => summary,
+            None => {
+                // Sanitizers such as UBSan may not print a `SUMMARY` line.
+                let report = reports.first()?;
+                (
+                    report.summary.clone(),
+                    report.sanitizer.name().to_owned(),
+                    report.fault_type.clone(),
+                )
+            }
+        };
         let call_stack = parse_call_stack(&text).unwrap_or_else(Vec::default);
+        let call_stack_frames = parse_stack_frames(&call_stack);
+        let memory_access = parse_memory_access(&text);
+        let memory_region = parse_memory_region(&text);
+        let free_call_stack = parse_labeled_call_stack(&text, "freed by thread");
+        let allocation_call_stack =
+            parse_labeled_call_stack(&text, "previously allocated by thread");
+        let shadow_bytes = parse_shadow_bytes(&text);
 
         let log = Self {
             text,
@@ -27,6 +217,13 @@ impl AsanLog {
             summary,
             fault_type,
             call_stack,
+            call_stack_frames,
+            memory_access,
+            memory_region,
+            free_call_stack,
+            allocation_call_stack,
+            shadow_bytes,
+            reports,
         };
 
         Some(log)
@@ -36,6 +233,11 @@ impl AsanLog {
         &self.text
     }
 
+    /// Name of the sanitizer of the first report, as in `AddressSanitizer`.
+    pub fn sanitizer(&self) -> &str {
+        &self.sanitizer
+    }
+
     pub fn summary(&self) -> &str {
         &self.summary
     }
@@ -48,15 +250,42 @@ impl AsanLog {
         &self.call_stack
     }
 
+    pub fn call_stack_frames(&self) -> &[StackFrame] {
+        &self.call_stack_frames
+    }
+
     pub fn call_stack_sha256(&self) -> String {
         sha256::digest_iter(self.call_stack())
     }
+
+    pub fn memory_access(&self) -> Option<&MemoryAccess> {
+        self.memory_access.as_ref()
+    }
+
+    pub fn memory_region(&self) -> Option<&MemoryRegion> {
+        self.memory_region.as_ref()
+    }
+
+    pub fn free_call_stack(&self) -> &[StackFrame] {
+        &self.free_call_stack
+    }
+
+    pub fn allocation_call_stack(&self) -> &[StackFrame] {
+        &self.allocation_call_stack
+    }
+
+    pub fn shadow_bytes(&self) -> Option<&ShadowBytes> {
+        self.shadow_bytes.as_ref()
+    }
+
+    /// All sanitizer reports found in the log, in order.
+    pub fn reports(&self) -> &[SanitizerReport] {
+        &self.reports
+    }
 }
 
 fn parse_summary(text: &str) -> Option<(String, String, String)> {
-    let pattern = r"SUMMARY: ((\w+): (data race|deadly signal|[^ \n]+).*)";
-    let re = Regex::new(pattern).ok()?;
-    let captures = re.captures(text)?;
+    let captures = FAULT_SUMMARY.captures(text)?;
     let summary = captures.get(1)?.as_str().trim();
     let sanitizer = captures.get(2)?.as_str().trim();
     let fault_type = captures.get(3)?.as_str().trim();
@@ -91,6 +320,213 @@ fn parse_call_stack(text: &str) -> Option<Vec<String>> {
     None
 }
 
+fn parse_reports(text: &str) -> Vec<SanitizerReport> {
+    let mut reports = vec![];
+    let mut current: Option<SanitizerReport> = None;
+    let mut description: Option<String> = None;
+    let mut in_stack = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        // Require some location info, so libFuzzer status lines such as `#2 INITED`
+        // are not mistaken for frames.
+        let frame = StackFrame::parse(line).filter(|f| {
+            f.address.is_some() || f.module_path.is_some() || f.source_file_path.is_some()
+        });
+
+        if let Some(frame) = frame {
+            if let Some(report) = &mut current {
+                if !in_stack {
+                    report.stacks.push(SanitizerStack {
+                        description: description.take(),
+                        frames: vec![],
+                    });
+                }
+                if let Some(stack) = report.stacks.last_mut() {
+                    stack.frames.push(frame);
+                }
+            }
+            in_stack = true;
+            continue;
+        }
+
+        if in_stack {
+            if let Some(stack) = current.as_mut().and_then(|r| r.stacks.last_mut()) {
+                mark_inlined(&mut stack.frames);
+            }
+        }
+        in_stack = false;
+
+        if let Some(captures) = REPORT_HEADER.captures(line) {
+            reports.extend(current.take());
+            let sanitizer = &captures[1];
+            let summary = format!("{}: {}", sanitizer, &captures[2]);
+            let report =
+                SanitizerReport::new(SanitizerKind::from_name(sanitizer), &captures[3], summary);
+            current = Some(report);
+            description = None;
+        } else if UBSAN_RUNTIME_ERROR.is_match(line) {
+            reports.extend(current.take());
+            let summary = format!("UndefinedBehaviorSanitizer: {}", line);
+            let report = SanitizerReport::new(
+                SanitizerKind::UndefinedBehaviorSanitizer,
+                "undefined-behavior",
+                summary,
+            );
+            current = Some(report);
+            description = None;
+        } else if let Some(captures) = LEAK.captures(line) {
+            // Each leak in an LSan log is its own report. The first replaces the
+            // `detected memory leaks` header report, which has no stack of its own.
+            let is_leak_header = matches!(
+                &current,
+                Some(r) if r.sanitizer == SanitizerKind::LeakSanitizer && r.stacks.is_empty()
+            );
+            if !is_leak_header {
+                reports.extend(current.take());
+            }
+            let fault_type = format!("{}-leak", captures[1].to_lowercase());
+            let summary = format!("LeakSanitizer: {}", line.trim_end_matches(':'));
+            current = Some(SanitizerReport::new(
+                SanitizerKind::LeakSanitizer,
+                fault_type,
+                summary,
+            ));
+            description = None;
+        } else if let Some(captures) = SUMMARY.captures(line) {
+            if let Some(mut report) = current.take() {
+                // The LSan summary totals every leak, so it does not describe
+                // the last one in particular.
+                if report.sanitizer != SanitizerKind::LeakSanitizer {
+                    report.summary = captures[1].to_owned();
+                }
+                reports.push(report);
+            }
+            description = None;
+        } else if !line.is_empty() && !line.starts_with("===") {
+            description = Some(line.to_owned());
+        }
+    }
+
+    if let Some(stack) = current.as_mut().and_then(|r| r.stacks.last_mut()) {
+        mark_inlined(&mut stack.frames);
+    }
+    reports.extend(current);
+
+    reports
+}
+
+fn parse_hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text.trim_start_matches("0x"), 16).ok()
+}
+
+fn parse_memory_access(text: &str) -> Option<MemoryAccess> {
+    let captures = MEMORY_ACCESS.captures(text)?;
+    let kind = if captures.get(1)?.as_str().eq_ignore_ascii_case("read") {
+        AccessKind::Read
+    } else {
+        AccessKind::Write
+    };
+    let size = captures.get(2)?.as_str().parse().ok()?;
+    let address = parse_hex(captures.get(3)?.as_str())?;
+    let thread = captures.get(4).map(|t| t.as_str().to_owned());
+    Some(MemoryAccess {
+        kind,
+        size,
+        address,
+        thread,
+    })
+}
+
+fn parse_memory_region(text: &str) -> Option<MemoryRegion> {
+    let captures = MEMORY_REGION.captures(text)?;
+    let relation = match captures.get(3)?.as_str() {
+        "to the left of" => RegionRelation::LeftOf,
+        "to the right of" => RegionRelation::RightOf,
+        _ => RegionRelation::Inside,
+    };
+    Some(MemoryRegion {
+        address: parse_hex(captures.get(1)?.as_str())?,
+        offset: captures.get(2)?.as_str().parse().ok()?,
+        relation,
+        size: captures.get(4)?.as_str().parse().ok()?,
+        start: parse_hex(captures.get(5)?.as_str())?,
+        end: parse_hex(captures.get(6)?.as_str())?,
+    })
+}
+
+// Parse the stack that immediately follows a line starting with `label`, such as
+// `freed by thread T0 here:`.
+fn parse_labeled_call_stack(text: &str, label: &str) -> Vec<StackFrame> {
+    let mut lines = text
+        .lines()
+        .skip_while(|line| !line.trim().starts_with(label));
+
+    if lines.next().is_none() {
+        return vec![];
+    }
+
+    let stack: Vec<_> = lines
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with('#'))
+        .collect();
+
+    parse_stack_frames(&stack)
+}
+
+fn parse_shadow_bytes(text: &str) -> Option<ShadowBytes> {
+    let mut lines = text
+        .lines()
+        .skip_while(|line| !line.starts_with("Shadow bytes around the buggy address"));
+    lines.next()?;
+
+    let mut rows = vec![];
+    let mut faulting_byte = None;
+    let mut legend = BTreeMap::new();
+
+    let mut in_legend = false;
+    for line in lines {
+        if line.starts_with("Shadow byte legend") {
+            in_legend = true;
+            continue;
+        }
+
+        if in_legend {
+            match SHADOW_LEGEND.captures(line) {
+                Some(captures) => {
+                    legend.insert(captures[1].to_owned(), captures[2].to_owned());
+                }
+                None => break,
+            }
+        } else {
+            let row = line.trim();
+            if !row.starts_with("0x") && !row.starts_with("=>") {
+                break;
+            }
+            if let Some(captures) = SHADOW_FAULTING_BYTE.captures(row) {
+                faulting_byte = u8::from_str_radix(&captures[1], 16).ok();
+            }
+            rows.push(row.to_owned());
+        }
+    }
+
+    let faulting_byte_description = faulting_byte.and_then(|byte| {
+        let byte = format!("{:02x}", byte);
+        legend
+            .iter()
+            .find(|(_, values)| values.split_whitespace().any(|v| v == byte))
+            .map(|(description, _)| description.to_owned())
+    });
+
+    Some(ShadowBytes {
+        rows,
+        faulting_byte,
+        faulting_byte_description,
+        legend,
+    })
+}
+
 #[cfg(target_os = "windows")]
 pub fn add_asan_log_env<S: BuildHasher>(env: &mut HashMap<String, String, S>, asan_dir: &Path) {
     let asan_path = asan_dir.join("asan-log");
@@ -171,7 +607,8 @@ pub async fn check_asan_path(asan_dir: &Path) -> Result<Option<AsanLog>> {
 
 #[cfg(test)]
 mod tests {
-    use super::AsanLog;
+    use super::*;
+    use crate::stack::StackFrame;
 
     #[test]
     fn test_asan_log_parse() {
@@ -233,6 +670,199 @@ mod tests {
             assert_eq!(log.sanitizer, sanitizer);
             assert_eq!(log.fault_type, fault_type);
             assert_eq!(log.call_stack.len(), call_stack_len);
+            assert_eq!(log.call_stack_frames.len(), call_stack_len);
+        }
+    }
+
+    #[test]
+    fn test_asan_log_frames() {
+        let data = std::fs::read_to_string("data/libfuzzer-asan-log.txt").unwrap();
+        let log = AsanLog::parse(data).unwrap();
+
+        let top = &log.call_stack_frames()[0];
+        assert_eq!(
+            top,
+            &StackFrame {
+                line: "#0 0x527475 in LLVMFuzzerTestOneInput /home/testuser/projects/onefuzz/samples/asan/fuzz.c:45:51".to_owned(),
+                index: Some(0),
+                address: Some(0x527475),
+                function_name: Some("LLVMFuzzerTestOneInput".to_owned()),
+                source_file_path: Some("/home/testuser/projects/onefuzz/samples/asan/fuzz.c".to_owned()),
+                source_file_line: Some(45),
+                source_file_column: Some(51),
+                ..Default::default()
+            }
+        );
+
+        let unsymbolized = &log.call_stack_frames()[1];
+        assert_eq!(
+            unsymbolized.module_path.as_deref(),
+            Some("/home/testuser/projects/onefuzz/samples/asan/fuzz.exe")
+        );
+        assert_eq!(unsymbolized.module_offset, Some(0x42fb3a));
+    }
+
+    #[test]
+    fn test_asan_log_memory_details() {
+        let data = std::fs::read_to_string("data/libfuzzer-asan-log.txt").unwrap();
+        let log = AsanLog::parse(data).unwrap();
+
+        assert_eq!(
+            log.memory_access(),
+            Some(&MemoryAccess {
+                kind: AccessKind::Write,
+                size: 4,
+                address: 0x602000000050,
+                thread: Some("T0".to_owned()),
+            })
+        );
+
+        assert_eq!(
+            log.memory_region(),
+            Some(&MemoryRegion {
+                address: 0x602000000050,
+                offset: 0,
+                relation: RegionRelation::Inside,
+                size: 4,
+                start: 0x602000000050,
+                end: 0x602000000054,
+            })
+        );
+
+        assert_eq!(log.free_call_stack().len(), 7);
+        assert_eq!(
+            log.free_call_stack()[0].function_name.as_deref(),
+            Some("free")
+        );
+        assert_eq!(log.allocation_call_stack().len(), 7);
+        assert_eq!(
+            log.allocation_call_stack()[0].function_name.as_deref(),
+            Some("__interceptor_malloc")
+        );
+
+        let shadow = log.shadow_bytes().unwrap();
+        assert_eq!(shadow.rows.len(), 11);
+        assert_eq!(shadow.faulting_byte, Some(0xfd));
+        assert_eq!(
+            shadow.faulting_byte_description.as_deref(),
+            Some("Freed heap region")
+        );
+        assert_eq!(shadow.legend.len(), 19);
+    }
+
+    #[test]
+    fn test_asan_log_no_memory_details() {
+        let data = std::fs::read_to_string("data/libfuzzer-deadly-signal.txt").unwrap();
+        let log = AsanLog::parse(data).unwrap();
+
+        assert_eq!(log.memory_access(), None);
+        assert_eq!(log.memory_region(), None);
+        assert!(log.free_call_stack().is_empty());
+        assert!(log.allocation_call_stack().is_empty());
+        assert_eq!(log.shadow_bytes(), None);
+    }
+
+    // The sanitizer, fault type and stack lengths of a report.
+    type ReportCase = (SanitizerKind, &'static str, Vec<usize>);
+
+    #[test]
+    fn test_sanitizer_reports() {
+        use SanitizerKind::*;
+
+        let test_cases: Vec<(&str, Vec<ReportCase>)> = vec![
+            (
+                "data/libfuzzer-asan-log.txt",
+                vec![(AddressSanitizer, "heap-use-after-free", vec![7, 7, 7])],
+            ),
+            (
+                "data/libfuzzer-deadly-signal.txt",
+                vec![(LibFuzzer, "deadly signal", vec![14])],
+            ),
+            (
+                "data/tsan-linux-llvm10-data-race.txt",
+                vec![(ThreadSanitizer, "data race", vec![1, 1, 2])],
+            ),
+            (
+                "data/msan-linux-llvm10-uninit.txt",
+                vec![(MemorySanitizer, "use-of-uninitialized-value", vec![7, 7])],
+            ),
+            (
+                "data/lsan-linux-llvm10-leaks.txt",
+                vec![
+                    (LeakSanitizer, "direct-leak", vec![7]),
+                    (LeakSanitizer, "indirect-leak", vec![6]),
+                    (LeakSanitizer, "direct-leak", vec![3]),
+                ],
+            ),
+            (
+                "data/ubsan-linux-llvm10-no-halt.txt",
+                vec![
+                    (UndefinedBehaviorSanitizer, "undefined-behavior", vec![5]),
+                    (UndefinedBehaviorSanitizer, "undefined-behavior", vec![5]),
+                    (UndefinedBehaviorSanitizer, "undefined-behavior", vec![]),
+                ],
+            ),
+        ];
+
+        for (log_path, expected) in test_cases {
+            let data = std::fs::read_to_string(log_path).unwrap();
+            let log = AsanLog::parse(data).unwrap();
+
+            let reports: Vec<_> = log
+                .reports()
+                .iter()
+                .map(|r| {
+                    let stacks: Vec<_> = r.stacks.iter().map(|s| s.frames.len()).collect();
+                    (r.sanitizer.clone(), r.fault_type.as_str(), stacks)
+                })
+                .collect();
+
+            assert_eq!(reports, expected, "{}", log_path);
         }
     }
+
+    #[test]
+    fn test_sanitizer_report_details() {
+        let data = std::fs::read_to_string("data/tsan-linux-llvm10-data-race.txt").unwrap();
+        let log = AsanLog::parse(data).unwrap();
+        let report = &log.reports()[0];
+        assert_eq!(
+            report.summary,
+            "ThreadSanitizer: data race /home/user/fuzz-targets/tiny_race.c:4:10 in Thread1"
+        );
+        assert_eq!(
+            report.stacks[1].description.as_deref(),
+            Some("Previous write of size 4 at 0x000001109278 by main thread:")
+        );
+
+        let data = std::fs::read_to_string("data/msan-linux-llvm10-uninit.txt").unwrap();
+        let log = AsanLog::parse(data).unwrap();
+        let report = &log.reports()[0];
+        assert_eq!(report.call_stack()[0].source_file_line, Some(10));
+        assert_eq!(
+            report.stacks[1].description.as_deref(),
+            Some("Uninitialized value was created by a heap allocation")
+        );
+
+        let data = std::fs::read_to_string("data/lsan-linux-llvm10-leaks.txt").unwrap();
+        let log = AsanLog::parse(data).unwrap();
+        assert_eq!(
+            log.reports()[1].summary,
+            "LeakSanitizer: Indirect leak of 16 byte(s) in 2 object(s) allocated from"
+        );
+    }
+
+    #[test]
+    fn test_ubsan_log_without_summary() {
+        let data = std::fs::read_to_string("data/ubsan-linux-llvm10-no-halt.txt").unwrap();
+        let log = AsanLog::parse(data).unwrap();
+
+        assert_eq!(log.sanitizer(), "UndefinedBehaviorSanitizer");
+        assert_eq!(log.fault_type(), "undefined-behavior");
+        assert_eq!(log.call_stack().len(), 5);
+        assert_eq!(
+            log.summary(),
+            "UndefinedBehaviorSanitizer: /home/user/fuzz-targets/ubsan.c:8:12: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'"
+        );
+    }
 }
@@ -5,7 +5,7 @@ use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use futures::stream::TryStreamExt;
-use reqwest::{Body, RequestBuilder, Response, Url};
+use reqwest::{Body, RequestBuilder, Response, StatusCode, Url};
 use reqwest_retry::SendRetry;
 use serde::Serialize;
 use tokio::{fs, io};
@@ -42,6 +42,18 @@ impl BlobClient {
         Ok(r)
     }
 
+    /// Get a blob, or `None` if it does not exist.
+    pub async fn get_if_exists(&self, url: &Url) -> Result<Option<Response>> {
+        let url = url.clone();
+
+        let r = self.client.get(url).send_retry_default().await?;
+        if r.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(r.error_for_status()?))
+    }
+
     pub async fn get_data(&self, url: &Url) -> Result<Vec<u8>> {
         let r = self.get(url).await?;
         let b = r.bytes().await?;
@@ -58,6 +70,16 @@ impl BlobClient {
         Ok(dst.to_owned())
     }
 
+    /// Delete a blob. Deleting a blob that does not exist is not an error.
+    pub async fn delete(&self, url: Url) -> Result<()> {
+        let r = self.client.delete(url).send_retry_default().await?;
+        if r.status() != StatusCode::NOT_FOUND {
+            r.error_for_status()?;
+        }
+
+        Ok(())
+    }
+
     pub fn put(&self, url: Url) -> RequestBuilder {
         self.client.put(url).header("x-ms-blob-type", "BlockBlob")
     }
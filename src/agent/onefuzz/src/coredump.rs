@@ -0,0 +1,433 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use anyhow::Result;
+use pete::{Pid, Registers, Siginfo, Tracee};
+use proc_maps::MapRange;
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+const PAGE_SIZE: u64 = 4096;
+
+// Mappings larger than this are recorded without their contents. Sanitizers
+// reserve terabytes of shadow memory as private, writable mappings.
+const MAX_DUMPED_MAPPING_SIZE: u64 = 64 << 20;
+
+// Total size of the mapping contents written to a single core file.
+const MAX_DUMPED_SIZE: u64 = 512 << 20;
+
+const ELF_HEADER_SIZE: u64 = 64;
+const PROGRAM_HEADER_SIZE: u64 = 56;
+
+const ET_CORE: u16 = 4;
+const EM_X86_64: u16 = 62;
+
+const PN_XNUM: u16 = 0xffff;
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRPSINFO: u32 = 3;
+const NT_AUXV: u32 = 6;
+const NT_SIGINFO: u32 = 0x5349_4749;
+const NT_FILE: u32 = 0x4649_4c45;
+
+/// Registers and signal of a crashing thread, saved when the signal was delivered.
+#[derive(Clone, Copy)]
+pub struct CrashedThread {
+    pub tid: Pid,
+    pub siginfo: Siginfo,
+    pub registers: Registers,
+}
+
+impl CrashedThread {
+    /// Save the state of `tracee`, which must be stopped at the delivery of the
+    /// crashing signal described by `siginfo`.
+    pub fn new(tracee: &mut Tracee, siginfo: Siginfo) -> Result<Self> {
+        Ok(Self {
+            tid: tracee.pid,
+            siginfo,
+            registers: tracee.registers()?,
+        })
+    }
+}
+
+/// Write an ELF core file for the process `pid`, in which `thread` crashed.
+///
+/// The process must be stopped, but not necessarily at the crash. Stopping it at
+/// its exit lets callers only write cores for crashes the process died of.
+///
+/// The core is trimmed compared to one written by the kernel: it only has the
+/// registers of the crashing thread, and only the contents of writable or
+/// anonymous mappings. Code and read-only data are loaded by the debugger from
+/// the files that back them, so the core can be opened with:
+///
+/// ```text
+/// gdb <target_exe> <core>
+/// ```
+pub fn write_core(pid: Pid, thread: &CrashedThread, path: impl AsRef<Path>) -> Result<()> {
+    let pid = pid.as_raw();
+    let maps = proc_maps::get_process_maps(pid)?;
+    let program_headers = program_header_count(maps.len())?;
+
+    let mut notes = vec![];
+    write_note(
+        &mut notes,
+        NT_PRSTATUS,
+        &prstatus(thread.tid.as_raw(), &thread.siginfo, &thread.registers),
+    );
+    write_note(&mut notes, NT_PRPSINFO, &prpsinfo(pid));
+    write_note(&mut notes, NT_SIGINFO, as_bytes(&thread.siginfo));
+    if let Ok(auxv) = fs::read(format!("/proc/{}/auxv", pid)) {
+        write_note(&mut notes, NT_AUXV, &auxv);
+    }
+    write_note(&mut notes, NT_FILE, &file_mappings(&maps));
+
+    let mem = File::open(format!("/proc/{}/mem", pid))?;
+    let contents = read_mappings(&mem, &maps);
+
+    let headers_size = ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE * u64::from(program_headers);
+    let notes_offset = headers_size;
+    let data_offset = align_up(notes_offset + notes.len() as u64, PAGE_SIZE);
+
+    let mut file = BufWriter::new(File::create(path)?);
+
+    file.write_all(&elf_header(program_headers))?;
+    file.write_all(&program_header(
+        PT_NOTE,
+        0,
+        notes_offset,
+        0,
+        notes.len() as u64,
+        0,
+        1,
+    ))?;
+
+    let mut offset = data_offset;
+    for (map, data) in maps.iter().zip(&contents) {
+        let file_size = data.as_ref().map(|d| d.len() as u64).unwrap_or_default();
+        file.write_all(&program_header(
+            PT_LOAD,
+            segment_flags(map),
+            offset,
+            map.start() as u64,
+            file_size,
+            map.size() as u64,
+            PAGE_SIZE,
+        ))?;
+        offset += file_size;
+    }
+
+    // Pad the notes, so the mapping contents start on a page boundary.
+    let padding = (data_offset - notes_offset) as usize - notes.len();
+    file.write_all(&notes)?;
+    file.write_all(&vec![0; padding])?;
+
+    for data in contents.iter().flatten() {
+        file.write_all(data)?;
+    }
+
+    file.flush()?;
+
+    Ok(())
+}
+
+// Read the contents of the mappings that a debugger can't reload from disk.
+//
+// Mappings that are skipped or can't be read are `None`, and written to the core
+// as segments with no file contents.
+fn read_mappings(mem: &File, maps: &[MapRange]) -> Vec<Option<Vec<u8>>> {
+    let mut budget = MAX_DUMPED_SIZE;
+
+    maps.iter()
+        .map(|map| {
+            let size = map.size() as u64;
+
+            if !should_dump(map) || size > MAX_DUMPED_MAPPING_SIZE || size > budget {
+                return None;
+            }
+
+            let mut data = vec![0; size as usize];
+            if mem.read_exact_at(&mut data, map.start() as u64).is_err() {
+                return None;
+            }
+
+            budget -= size;
+            Some(data)
+        })
+        .collect()
+}
+
+fn should_dump(map: &MapRange) -> bool {
+    let readable = map.flags.starts_with('r');
+    let writable = map.flags.contains('w');
+
+    match map.filename() {
+        // Never readable via `/proc/<pid>/mem`.
+        Some(name) if name == "[vvar]" || name == "[vsyscall]" => false,
+
+        // `[heap]`, `[stack]`, and other pseudo-mappings.
+        Some(name) if name.starts_with('[') => readable,
+
+        Some(_) => readable && writable,
+
+        // Anonymous mapping, which may be JIT code or allocator memory.
+        None => readable,
+    }
+}
+
+fn segment_flags(map: &MapRange) -> u32 {
+    let mut flags = 0;
+
+    if map.flags.contains('r') {
+        flags |= PF_R;
+    }
+    if map.flags.contains('w') {
+        flags |= PF_W;
+    }
+    if map.flags.contains('x') {
+        flags |= PF_X;
+    }
+
+    flags
+}
+
+// One `PT_NOTE` header, and one `PT_LOAD` header per mapping.
+//
+// Counts from `PN_XNUM` up must be stored in an extra section header, which we
+// don't write, so cores of processes with that many mappings are skipped.
+fn program_header_count(mappings: usize) -> Result<u16> {
+    u16::try_from(mappings + 1)
+        .ok()
+        .filter(|&count| count < PN_XNUM)
+        .ok_or_else(|| format_err!("too many mappings for a core file: {}", mappings))
+}
+
+fn elf_header(program_headers: u16) -> Vec<u8> {
+    let mut header = vec![];
+
+    // `e_ident`: magic, 64-bit, little-endian, current version, System V ABI.
+    header.extend_from_slice(b"\x7fELF");
+    header.extend_from_slice(&[2, 1, 1, 0]);
+    header.extend_from_slice(&[0; 8]);
+
+    header.extend_from_slice(&ET_CORE.to_le_bytes());
+    header.extend_from_slice(&EM_X86_64.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    header.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+    header.extend_from_slice(&ELF_HEADER_SIZE.to_le_bytes()); // e_phoff
+    header.extend_from_slice(&0u64.to_le_bytes()); // e_shoff
+    header.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+    header.extend_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&program_headers.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes()); // e_shentsize
+    header.extend_from_slice(&0u16.to_le_bytes()); // e_shnum
+    header.extend_from_slice(&0u16.to_le_bytes()); // e_shstrndx
+
+    header
+}
+
+fn program_header(
+    kind: u32,
+    flags: u32,
+    offset: u64,
+    vaddr: u64,
+    file_size: u64,
+    mem_size: u64,
+    align: u64,
+) -> Vec<u8> {
+    let mut header = vec![];
+
+    header.extend_from_slice(&kind.to_le_bytes());
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(&offset.to_le_bytes());
+    header.extend_from_slice(&vaddr.to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes()); // p_paddr
+    header.extend_from_slice(&file_size.to_le_bytes());
+    header.extend_from_slice(&mem_size.to_le_bytes());
+    header.extend_from_slice(&align.to_le_bytes());
+
+    header
+}
+
+// Append an ELF note owned by "CORE", with the name and descriptor padded to
+// 4-byte alignment.
+fn write_note(notes: &mut Vec<u8>, kind: u32, desc: &[u8]) {
+    const NAME: &[u8] = b"CORE\0";
+
+    notes.extend_from_slice(&(NAME.len() as u32).to_le_bytes());
+    notes.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    notes.extend_from_slice(&kind.to_le_bytes());
+    notes.extend_from_slice(NAME);
+    pad(notes, 4);
+    notes.extend_from_slice(desc);
+    pad(notes, 4);
+}
+
+// `struct elf_prstatus` for x86-64.
+fn prstatus(pid: i32, siginfo: &Siginfo, registers: &Registers) -> Vec<u8> {
+    let mut desc = vec![];
+
+    desc.extend_from_slice(&siginfo.si_signo.to_le_bytes());
+    desc.extend_from_slice(&siginfo.si_code.to_le_bytes());
+    desc.extend_from_slice(&siginfo.si_errno.to_le_bytes());
+    desc.extend_from_slice(&(siginfo.si_signo as i16).to_le_bytes()); // pr_cursig
+    pad(&mut desc, 8);
+    desc.extend_from_slice(&0u64.to_le_bytes()); // pr_sigpend
+    desc.extend_from_slice(&0u64.to_le_bytes()); // pr_sighold
+    desc.extend_from_slice(&pid.to_le_bytes()); // pr_pid
+    desc.extend_from_slice(&[0; 12]); // pr_ppid, pr_pgrp, pr_sid
+    desc.extend_from_slice(&[0; 64]); // pr_utime, pr_stime, pr_cutime, pr_cstime
+
+    // `struct user_regs_struct`, which has the layout of `elf_gregset_t`.
+    desc.extend_from_slice(as_bytes(registers));
+
+    desc.extend_from_slice(&0i32.to_le_bytes()); // pr_fpvalid
+    pad(&mut desc, 8);
+
+    desc
+}
+
+// `struct elf_prpsinfo` for x86-64.
+fn prpsinfo(pid: i32) -> Vec<u8> {
+    let comm = fs::read(format!("/proc/{}/comm", pid)).unwrap_or_default();
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+
+    let mut desc = vec![];
+
+    desc.extend_from_slice(&[0, b't', 0, 0]); // pr_state, pr_sname, pr_zomb, pr_nice
+    pad(&mut desc, 8);
+    desc.extend_from_slice(&0u64.to_le_bytes()); // pr_flag
+    desc.extend_from_slice(&[0; 8]); // pr_uid, pr_gid
+    desc.extend_from_slice(&pid.to_le_bytes()); // pr_pid
+    desc.extend_from_slice(&[0; 12]); // pr_ppid, pr_pgrp, pr_sid
+    desc.extend_from_slice(&fixed_string(trim_end(&comm), 16)); // pr_fname
+
+    // Arguments are separated by NULs in `cmdline`, but by spaces in `pr_psargs`.
+    let args: Vec<u8> = cmdline
+        .iter()
+        .map(|&b| if b == 0 { b' ' } else { b })
+        .collect();
+    desc.extend_from_slice(&fixed_string(trim_end(&args), 80)); // pr_psargs
+
+    desc
+}
+
+// Table of file-backed mappings, which lets a debugger find the shared libraries
+// of the process.
+fn file_mappings(maps: &[MapRange]) -> Vec<u8> {
+    let files: Vec<_> = maps
+        .iter()
+        .filter_map(|map| match map.filename() {
+            Some(name) if name.starts_with('/') => Some((map, name)),
+            _ => None,
+        })
+        .collect();
+
+    let mut desc = vec![];
+
+    desc.extend_from_slice(&(files.len() as u64).to_le_bytes());
+    desc.extend_from_slice(&PAGE_SIZE.to_le_bytes());
+
+    for (map, _) in &files {
+        let start = map.start() as u64;
+        desc.extend_from_slice(&start.to_le_bytes());
+        desc.extend_from_slice(&(start + map.size() as u64).to_le_bytes());
+        desc.extend_from_slice(&(map.offset as u64 / PAGE_SIZE).to_le_bytes());
+    }
+
+    for (_, name) in &files {
+        desc.extend_from_slice(name.as_bytes());
+        desc.push(0);
+    }
+
+    desc
+}
+
+// Trim the trailing newline or spaces of a `/proc` file.
+fn trim_end(data: &[u8]) -> &[u8] {
+    let end = data
+        .iter()
+        .rposition(|&b| b != b'\n' && b != b' ')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &data[..end]
+}
+
+// Copy `data` into a NUL-terminated field of `len` bytes, truncating if needed.
+fn fixed_string(data: &[u8], len: usize) -> Vec<u8> {
+    let mut field = vec![0; len];
+    let n = data.len().min(len - 1);
+    field[..n].copy_from_slice(&data[..n]);
+    field
+}
+
+fn pad(data: &mut Vec<u8>, align: usize) {
+    let len = align_up(data.len() as u64, align as u64) as usize;
+    data.resize(len, 0);
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    (value + align - 1) / align * align
+}
+
+// View a plain C struct, such as `siginfo_t`, as its raw bytes.
+fn as_bytes<T: Copy>(t: &T) -> &[u8] {
+    // Safe because `T` is `Copy`, so it has no drop glue or interior references,
+    // and we only read `size_of::<T>()` bytes of it.
+    unsafe { std::slice::from_raw_parts(t as *const T as *const u8, std::mem::size_of::<T>()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elf_header() {
+        let header = elf_header(3);
+        assert_eq!(header.len() as u64, ELF_HEADER_SIZE);
+        assert_eq!(&header[..4], b"\x7fELF");
+        assert_eq!(&header[56..58], &3u16.to_le_bytes());
+    }
+
+    #[test]
+    fn test_program_header_count() {
+        assert_eq!(program_header_count(2).unwrap(), 3);
+        assert_eq!(program_header_count(0xfffd).unwrap(), 0xfffe);
+        assert!(program_header_count(0xfffe).is_err());
+        assert!(program_header_count(100_000).is_err());
+    }
+
+    #[test]
+    fn test_program_header() {
+        let header = program_header(PT_LOAD, PF_R | PF_W, 0x1000, 0x7f00_0000, 0, 0x2000, 4096);
+        assert_eq!(header.len() as u64, PROGRAM_HEADER_SIZE);
+    }
+
+    #[test]
+    fn test_write_note() {
+        let mut notes = vec![];
+        write_note(&mut notes, NT_AUXV, &[1, 2, 3]);
+
+        // Header, padded name, padded descriptor.
+        assert_eq!(notes.len(), 12 + 8 + 4);
+        assert_eq!(&notes[12..17], b"CORE\0");
+        assert_eq!(&notes[20..24], &[1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_fixed_string() {
+        assert_eq!(fixed_string(b"fuzz", 6), b"fuzz\0\0");
+        assert_eq!(fixed_string(b"fuzz_target", 6), b"fuzz_\0");
+        assert_eq!(trim_end(b"fuzz_target\n"), b"fuzz_target");
+    }
+}
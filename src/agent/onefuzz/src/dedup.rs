@@ -0,0 +1,240 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{sha256, stack::StackFrame};
+use anyhow::Result;
+use regex::Regex;
+
+// Frames from sanitizer runtimes, the libFuzzer driver, allocators and process
+// startup. They appear in many unrelated crashes, and so only add noise to a key.
+const DEFAULT_IGNORE_FRAMES: &[&str] = &[
+    r"^__(asan|lsan|msan|tsan|ubsan|sanitizer)",
+    r"^__interceptor_",
+    r"^fuzzer::",
+    r"^(malloc|calloc|realloc|free)$",
+    r"^operator (new|delete)",
+    r"^(main|_start|__libc_start_main)$",
+    r"libc(\.so|-)",
+    r"libpthread",
+];
+
+/// Options for deriving a crash deduplication key from a call stack.
+///
+/// The default options keep the legacy behavior of hashing every line of the
+/// raw call stack.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DedupConfig {
+    /// Only hash this many of the innermost frames that were not ignored.
+    pub max_frames: Option<usize>,
+
+    /// Hash only the function name of each frame, ignoring source locations and
+    /// module offsets.
+    #[serde(default)]
+    pub function_names_only: bool,
+
+    /// Regexes matched against the function name and module path of each frame.
+    /// Matching frames are left out of the key.
+    #[serde(default)]
+    pub ignore_frames: Vec<String>,
+
+    /// Also ignore frames from sanitizer runtimes, allocators, the libFuzzer
+    /// driver and process startup.
+    #[serde(default)]
+    pub ignore_default_frames: bool,
+}
+
+impl DedupConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Computes deduplication keys for call stacks, per a `DedupConfig`.
+#[derive(Clone, Debug)]
+pub struct StackHasher {
+    enabled: bool,
+    max_frames: Option<usize>,
+    function_names_only: bool,
+    ignore_frames: Vec<Regex>,
+}
+
+impl StackHasher {
+    pub fn new(config: &DedupConfig) -> Result<Self> {
+        let mut patterns: Vec<&str> = config.ignore_frames.iter().map(|p| p.as_str()).collect();
+        if config.ignore_default_frames {
+            patterns.extend(DEFAULT_IGNORE_FRAMES);
+        }
+
+        let mut ignore_frames = vec![];
+        for pattern in patterns {
+            let re = Regex::new(pattern)
+                .map_err(|err| format_err!("invalid ignore_frames regex {:?}: {}", pattern, err))?;
+            ignore_frames.push(re);
+        }
+
+        Ok(Self {
+            enabled: !config.is_default(),
+            max_frames: config.max_frames,
+            function_names_only: config.function_names_only,
+            ignore_frames,
+        })
+    }
+
+    /// Compute the deduplication key of a call stack.
+    ///
+    /// Returns `None` if the hasher uses the default options, or if no frames are
+    /// left once ignored frames are removed. Callers should then fall back to the
+    /// hash of the raw call stack.
+    pub fn digest(&self, frames: &[StackFrame]) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+
+        let max_frames = self.max_frames.unwrap_or(usize::MAX);
+        let keys: Vec<String> = frames
+            .iter()
+            .filter(|frame| !self.is_ignored(frame))
+            .filter_map(|frame| self.frame_key(frame))
+            .take(max_frames)
+            .collect();
+
+        if keys.is_empty() {
+            return None;
+        }
+
+        Some(sha256::digest_iter(&keys))
+    }
+
+    fn is_ignored(&self, frame: &StackFrame) -> bool {
+        let names = frame.function_name.iter().chain(frame.module_path.iter());
+
+        for name in names {
+            if self.ignore_frames.iter().any(|re| re.is_match(name)) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Key for a single frame, free of absolute addresses and build paths.
+    fn frame_key(&self, frame: &StackFrame) -> Option<String> {
+        if let Some(function) = &frame.function_name {
+            if self.function_names_only {
+                return Some(function.to_owned());
+            }
+
+            if let (Some(file), Some(line)) = (&frame.source_file_path, frame.source_file_line) {
+                return Some(format!("{} {}:{}", function, file_name(file), line));
+            }
+
+            if let Some(offset) = frame.function_offset {
+                return Some(format!("{}+0x{:x}", function, offset));
+            }
+
+            return Some(function.to_owned());
+        }
+
+        let module = file_name(frame.module_path.as_ref()?);
+
+        if self.function_names_only {
+            return Some(module.to_owned());
+        }
+
+        match frame.module_offset {
+            Some(offset) => Some(format!("{}+0x{:x}", module, offset)),
+            None => Some(module.to_owned()),
+        }
+    }
+}
+
+// Final component of a Unix or Windows path.
+fn file_name(path: &str) -> &str {
+    path.rsplit(|c: char| c == '/' || c == '\\')
+        .next()
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::parse_call_stack;
+
+    fn frames(lines: &[&str]) -> Vec<StackFrame> {
+        parse_call_stack(lines)
+    }
+
+    #[test]
+    fn test_default_config_is_disabled() {
+        let hasher = StackHasher::new(&DedupConfig::default()).unwrap();
+        let stack = frames(&["#0 0x527475 in crash /src/fuzz.c:45:51"]);
+        assert_eq!(hasher.digest(&stack), None);
+    }
+
+    #[test]
+    fn test_ignores_addresses_and_build_paths() {
+        let config = DedupConfig {
+            max_frames: Some(2),
+            ..Default::default()
+        };
+        let hasher = StackHasher::new(&config).unwrap();
+
+        let a = frames(&[
+            "#0 0x527475 in crash /build/a/fuzz.c:45:51",
+            "#1 0x42fb3a in parse /build/a/fuzz.c:60:3",
+            "#2 0x41ef87 in caller_a /build/a/fuzz.c:80:3",
+        ]);
+        let b = frames(&[
+            "#0 0x7f0000527475 in crash /home/user/b/fuzz.c:45:51",
+            "#1 0x7f000042fb3a in parse /home/user/b/fuzz.c:60:3",
+            "#2 0x7f000041ef87 in caller_b /home/user/b/fuzz.c:99:3",
+        ]);
+
+        assert!(hasher.digest(&a).is_some());
+        assert_eq!(hasher.digest(&a), hasher.digest(&b));
+    }
+
+    #[test]
+    fn test_function_names_only() {
+        let config = DedupConfig {
+            function_names_only: true,
+            ..Default::default()
+        };
+        let hasher = StackHasher::new(&config).unwrap();
+
+        let a = frames(&["#0 0x527475 in crash /src/fuzz.c:45:51"]);
+        let b = frames(&["#0 0x527475 in crash /src/fuzz.c:47:2"]);
+        assert_eq!(hasher.digest(&a), hasher.digest(&b));
+
+        let c = frames(&["#0 0x527475 in other /src/fuzz.c:45:51"]);
+        assert_ne!(hasher.digest(&a), hasher.digest(&c));
+    }
+
+    #[test]
+    fn test_ignore_frames() {
+        let config = DedupConfig {
+            ignore_frames: vec!["^helper$".to_owned()],
+            ignore_default_frames: true,
+            ..Default::default()
+        };
+        let hasher = StackHasher::new(&config).unwrap();
+
+        let a = frames(&[
+            "#0 0x4f72e2 in __asan_memcpy (/src/fuzz.exe+0x4f72e2)",
+            "#1 0x527475 in helper /src/fuzz.c:10:3",
+            "#2 0x527475 in crash /src/fuzz.c:45:51",
+            "#3 0x42fb3a in fuzzer::Fuzzer::ExecuteCallback(unsigned char const*, unsigned long) (/src/fuzz.exe+0x42fb3a)",
+        ]);
+        let b = frames(&["#0 0x527475 in crash /src/fuzz.c:45:51"]);
+        assert_eq!(hasher.digest(&a), hasher.digest(&b));
+    }
+
+    #[test]
+    fn test_invalid_ignore_regex() {
+        let config = DedupConfig {
+            ignore_frames: vec!["(".to_owned()],
+            ..Default::default()
+        };
+        assert!(StackHasher::new(&config).is_err());
+    }
+}
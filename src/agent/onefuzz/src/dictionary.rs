@@ -0,0 +1,293 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use anyhow::Result;
+use std::{collections::BTreeSet, fmt, path::Path};
+use tokio::fs;
+
+// Longest entry accepted by libFuzzer (`Word::kMaxSize`).
+const MAX_ENTRY_SIZE: usize = 64;
+
+const RECOMMENDED_BEGIN: &str = "###### Recommended dictionary. ######";
+const RECOMMENDED_END: &str = "###### End of recommended dictionary. ######";
+
+/// A fuzzing dictionary, in the format shared by libFuzzer and AFL:
+///
+/// ```text
+/// # Lines starting with `#` are comments.
+/// kw1="blah"
+/// "\xF7\xF8"
+/// ```
+///
+/// Entry names are dropped, and entries are deduplicated by value, so that
+/// dictionaries from different sources can be merged.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Dictionary {
+    entries: BTreeSet<Vec<u8>>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut dict = Self::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let entry = parse_entry(line).map_err(|err| {
+                format_err!("invalid dictionary entry on line {}: {}", index + 1, err)
+            })?;
+            dict.insert(entry);
+        }
+
+        Ok(dict)
+    }
+
+    /// Parse the entries of the recommended dictionaries that libFuzzer logs
+    /// on exit, from its output.
+    ///
+    /// Lines of the blocks that can't be parsed are ignored.
+    pub fn parse_recommended(output: &str) -> Self {
+        let mut dict = Self::new();
+        let mut in_block = false;
+
+        for line in output.lines() {
+            let line = line.trim();
+
+            if line == RECOMMENDED_BEGIN {
+                in_block = true;
+                continue;
+            }
+
+            if line == RECOMMENDED_END {
+                in_block = false;
+                continue;
+            }
+
+            if !in_block {
+                continue;
+            }
+
+            // Entries are followed by a count, as in `"foo" # Uses: 12`.
+            let entry = match line.rfind("\" #") {
+                Some(end) => &line[..=end],
+                None => line,
+            };
+
+            match parse_entry(entry) {
+                Ok(entry) => {
+                    dict.insert(entry);
+                }
+                Err(err) => verbose!("ignoring recommended dictionary entry {:?}: {}", line, err),
+            }
+        }
+
+        dict
+    }
+
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let result: Result<Self> = async { Self::parse(&fs::read_to_string(path).await?) }.await;
+        result.map_err(|err| format_err!("{}: {}", path.display(), err))
+    }
+
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_string()).await?;
+        Ok(())
+    }
+
+    /// Add an entry, returning `true` if it was not already present.
+    ///
+    /// Entries that are empty or too long for libFuzzer are ignored.
+    pub fn insert(&mut self, entry: Vec<u8>) -> bool {
+        if entry.is_empty() || entry.len() > MAX_ENTRY_SIZE {
+            return false;
+        }
+
+        self.entries.insert(entry)
+    }
+
+    /// Add the entries of `other`, returning how many were new.
+    pub fn merge(&mut self, other: &Dictionary) -> usize {
+        other
+            .entries
+            .iter()
+            .filter(|entry| self.insert(entry.to_vec()))
+            .count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &[u8]> {
+        self.entries.iter().map(|entry| entry.as_slice())
+    }
+}
+
+impl fmt::Display for Dictionary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            write!(f, "\"")?;
+            for &byte in entry {
+                match byte {
+                    b'\\' | b'"' => write!(f, "\\{}", byte as char)?,
+                    b' '..=b'~' => write!(f, "{}", byte as char)?,
+                    _ => write!(f, "\\x{:02X}", byte)?,
+                }
+            }
+            writeln!(f, "\"")?;
+        }
+
+        Ok(())
+    }
+}
+
+// Parse an entry like `kw1="blah"` or `"\xF7\xF8"`.
+fn parse_entry(line: &str) -> Result<Vec<u8>> {
+    let start = line
+        .find('"')
+        .ok_or_else(|| format_err!("expected a quoted value"))?;
+
+    let name = &line[..start];
+    if !name.is_empty() && !name.ends_with('=') {
+        bail!("expected `=` after the entry name");
+    }
+
+    let value = &line[start + 1..];
+    let value = value
+        .strip_suffix('"')
+        .ok_or_else(|| format_err!("expected a closing quote"))?;
+
+    let mut entry = vec![];
+    let mut bytes = value.bytes();
+
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            entry.push(byte);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(b'\\') => entry.push(b'\\'),
+            Some(b'"') => entry.push(b'"'),
+            Some(b'x') => {
+                let hex = [
+                    bytes.next().unwrap_or_default(),
+                    bytes.next().unwrap_or_default(),
+                ];
+                let hex = std::str::from_utf8(&hex)?;
+                let byte = u8::from_str_radix(hex, 16)
+                    .map_err(|_| format_err!("invalid escape `\\x{}`", hex))?;
+                entry.push(byte);
+            }
+            _ => bail!("invalid escape"),
+        }
+    }
+
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = r#"
+# A comment.
+kw1="blah"
+"\xF7\xF8"
+  "a\"b\\c"
+kw2="blah"
+"#;
+
+        let dict = Dictionary::parse(text).unwrap();
+        let entries: Vec<_> = dict.entries().collect();
+        assert_eq!(
+            entries,
+            vec![b"a\"b\\c".as_ref(), b"blah".as_ref(), b"\xF7\xF8".as_ref()]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = vec![
+            ("blah", "expected a quoted value"),
+            ("kw1 \"blah\"", "expected `=` after the entry name"),
+            ("\"blah", "expected a closing quote"),
+            ("\"blah\" # comment", "expected a closing quote"),
+            ("\"\\n\"", "invalid escape"),
+            ("\"\\xZZ\"", "invalid escape `\\xZZ`"),
+        ];
+
+        for (line, message) in cases {
+            let err = Dictionary::parse(line).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("invalid dictionary entry on line 1: {}", message)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_recommended() {
+        let output = r#"
+#1048576	DONE   cov: 11 ft: 11 corp: 6/21b lim: 4096 exec/s: 699050 rss: 562Mb
+###### Recommended dictionary. ######
+"\x00\x00\x00\x00" # Uses: 12
+"FUZZ" # Uses: 3
+"\x01#\x02" # Uses: 0
+not an entry
+###### End of recommended dictionary. ######
+"outside" # Uses: 1
+"#;
+
+        let dict = Dictionary::parse_recommended(output);
+        let entries: Vec<_> = dict.entries().collect();
+        assert_eq!(
+            entries,
+            vec![
+                b"\x00\x00\x00\x00".as_ref(),
+                b"\x01#\x02".as_ref(),
+                b"FUZZ".as_ref()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_and_display() {
+        let mut dict = Dictionary::parse("\"A\"\n\"\\x41\\x42\"").unwrap();
+        assert_eq!(dict.len(), 2);
+
+        let other = Dictionary::parse("x=\"\\x41\"").unwrap();
+        assert_eq!(dict.merge(&other), 0);
+
+        let other = Dictionary::parse("\"\\\"\\x0a\"\n\"\"").unwrap();
+        assert_eq!(dict.merge(&other), 1);
+        assert_eq!(dict.merge(&other), 0);
+
+        assert_eq!(dict.to_string(), "\"\\\"\\x0A\"\n\"A\"\n\"AB\"\n");
+        assert_eq!(Dictionary::parse(&dict.to_string()).unwrap(), dict);
+    }
+
+    #[test]
+    fn test_insert_limits() {
+        let mut dict = Dictionary::new();
+        assert!(!dict.insert(vec![]));
+        assert!(!dict.insert(vec![0; MAX_ENTRY_SIZE + 1]));
+        assert!(dict.insert(vec![0; MAX_ENTRY_SIZE]));
+        assert!(!dict.insert(vec![0; MAX_ENTRY_SIZE]));
+        assert_eq!(dict.len(), 1);
+    }
+}
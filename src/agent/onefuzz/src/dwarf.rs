@@ -0,0 +1,223 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use addr2line::{
+    gimli::{EndianRcSlice, RunTimeEndian},
+    object::{self, Object, ObjectSegment},
+    Context,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Source location of a code address, from DWARF line info.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A logical frame at a code address, which may have been inlined into the
+/// function that contains the address.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SymbolizedFrame {
+    /// Demangled name of the function, if known.
+    pub function: Option<String>,
+
+    pub location: Option<SourceLocation>,
+}
+
+/// Resolves module-relative code addresses to functions and source locations,
+/// using the DWARF debug info of each module.
+///
+/// Debug info is loaded at most once per module. Modules without debug info are
+/// remembered, and symbolize to nothing.
+#[derive(Default)]
+pub struct DwarfSymbolizer {
+    modules: HashMap<String, Option<ModuleDebugInfo>>,
+}
+
+impl DwarfSymbolizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Symbolize the code at `offset` in the file at `module`.
+    ///
+    /// Returns the frames at the address, from the innermost inlined function to
+    /// the outermost function that contains the address. Returns no frames if the
+    /// module has no debug info for the address.
+    pub fn symbolize(&mut self, module: &str, offset: u64) -> Vec<SymbolizedFrame> {
+        let debug_info = self.modules.entry(module.to_owned()).or_insert_with(|| {
+            match ModuleDebugInfo::load(module) {
+                Ok(debug_info) => Some(debug_info),
+                Err(err) => {
+                    verbose!("no debug info for module {}: {}", module, err);
+                    None
+                }
+            }
+        });
+
+        match debug_info {
+            Some(debug_info) => debug_info.symbolize(offset).unwrap_or_default(),
+            None => vec![],
+        }
+    }
+}
+
+struct ModuleDebugInfo {
+    context: Context<EndianRcSlice<RunTimeEndian>>,
+
+    /// File offset, file size and virtual address of each loadable segment.
+    segments: Vec<(u64, u64, u64)>,
+}
+
+impl ModuleDebugInfo {
+    fn load(path: &str) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        let file = object::File::parse(&data).map_err(|err| format_err!("{}", err))?;
+
+        if !file.has_debug_symbols() {
+            bail!("module has no debug symbols");
+        }
+
+        // The context copies the debug sections it needs, so `data` can be dropped.
+        let context = Context::new(&file)?;
+
+        let segments = file
+            .segments()
+            .map(|segment| {
+                let (offset, size) = segment.file_range();
+                (offset, size, segment.address())
+            })
+            .collect();
+
+        Ok(Self { context, segments })
+    }
+
+    // DWARF describes code by its link-time virtual address, but we only know the
+    // offset into the module file. Map it through the segment that loads it.
+    fn virtual_address(&self, offset: u64) -> Option<u64> {
+        self.segments
+            .iter()
+            .find(|(start, size, _)| (*start..*start + *size).contains(&offset))
+            .map(|(start, _, address)| address + (offset - start))
+    }
+
+    fn symbolize(&self, offset: u64) -> Result<Vec<SymbolizedFrame>> {
+        let address = match self.virtual_address(offset) {
+            Some(address) => address,
+            None => return Ok(vec![]),
+        };
+
+        let mut frames = vec![];
+        let mut iter = self.context.find_frames(address)?;
+
+        while let Some(frame) = iter.next()? {
+            let function = match &frame.function {
+                Some(name) => Some(name.demangle()?.into_owned()),
+                None => None,
+            };
+
+            let location = frame.location.and_then(|location| {
+                Some(SourceLocation {
+                    file: location.file?.to_owned(),
+                    line: location.line?,
+                    column: location.column,
+                })
+            });
+
+            frames.push(SymbolizedFrame { function, location });
+        }
+
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[inline(never)]
+    fn symbolized_function() -> u32 {
+        line!()
+    }
+
+    // Find the file and file offset of a code address in this process.
+    fn module_offset(addr: u64) -> Result<(String, u64)> {
+        let maps = proc_maps::get_process_maps(std::process::id() as i32)?;
+        let map = maps
+            .iter()
+            .find(|m| (m.start() as u64..(m.start() + m.size()) as u64).contains(&addr))
+            .ok_or_else(|| format_err!("no mapping for address 0x{:x}", addr))?;
+        let module = map
+            .filename()
+            .clone()
+            .ok_or_else(|| format_err!("anonymous mapping"))?;
+        let offset = addr - map.start() as u64 + map.offset as u64;
+
+        Ok((module, offset))
+    }
+
+    #[test]
+    fn test_source_location_display() {
+        let mut location = SourceLocation {
+            file: "/src/fuzz.c".into(),
+            line: 45,
+            column: Some(51),
+        };
+        assert_eq!(location.to_string(), "/src/fuzz.c:45:51");
+
+        location.column = None;
+        assert_eq!(location.to_string(), "/src/fuzz.c:45");
+    }
+
+    #[test]
+    fn test_symbolize_missing_module() {
+        let mut symbolizer = DwarfSymbolizer::new();
+        assert!(symbolizer.symbolize("/does/not/exist", 0x1000).is_empty());
+
+        // The failure to load is remembered.
+        assert!(symbolizer.modules["/does/not/exist"].is_none());
+        assert!(symbolizer.symbolize("/does/not/exist", 0x1000).is_empty());
+    }
+
+    #[test]
+    fn test_symbolize_self() -> Result<()> {
+        let body_line = symbolized_function();
+        let target: fn() -> u32 = symbolized_function;
+        let (module, offset) = module_offset(target as usize as u64)?;
+
+        let mut symbolizer = DwarfSymbolizer::new();
+        let frames = symbolizer.symbolize(&module, offset);
+
+        let frame = frames
+            .last()
+            .ok_or_else(|| format_err!("no frames for {}+0x{:x}", module, offset))?;
+        let function = frame.function.as_deref().unwrap_or_default();
+        assert!(function.ends_with("symbolized_function"), "{}", function);
+
+        let location = frame.location.as_ref().expect("no source location");
+        assert!(location.file.ends_with("dwarf.rs"), "{}", location.file);
+        assert!(
+            (body_line - 1..=body_line + 1).contains(&location.line),
+            "{}",
+            location
+        );
+
+        Ok(())
+    }
+}
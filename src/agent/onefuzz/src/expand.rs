@@ -1,17 +1,20 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use crate::template::{Modifier, Placeholder, Segment, Template};
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
+use uuid::Uuid;
 
+#[derive(Clone)]
 pub enum ExpandedValue<'a> {
     Path(String),
     Scalar(String),
     List(&'a [String]),
-    Mapping(Box<dyn Fn(&Expand<'a>, &str) -> Option<ExpandedValue<'a>>>),
+    Mapping(fn(&Expand<'_>, &str) -> Option<String>),
 }
 
 #[derive(PartialEq, Eq, Hash, EnumIter)]
@@ -33,6 +36,12 @@ pub enum PlaceHolder {
     GeneratorOptions,
     SupervisorExe,
     SupervisorOptions,
+    JobId,
+    TaskId,
+    ReportsDir,
+    CrashesAccount,
+    SetupDir,
+    TargetTimeout,
 }
 
 impl PlaceHolder {
@@ -55,13 +64,25 @@ impl PlaceHolder {
             Self::GeneratorOptions => "{generator_options}",
             Self::SupervisorExe => "{supervisor_exe}",
             Self::SupervisorOptions => "{supervisor_options}",
+            Self::JobId => "{job_id}",
+            Self::TaskId => "{task_id}",
+            Self::ReportsDir => "{reports_dir}",
+            Self::CrashesAccount => "{crashes_account}",
+            Self::SetupDir => "{setup_dir}",
+            Self::TargetTimeout => "{target_timeout}",
         }
         .to_string()
     }
 }
 
+/// Values of the placeholders of templates, such as command lines.
+///
+/// Cloning is cheap, so tasks can set their shared values once, and clone them
+/// for each command they run.
+#[derive(Clone)]
 pub struct Expand<'a> {
     values: HashMap<String, ExpandedValue<'a>>,
+    strict: bool,
 }
 
 impl Default for Expand<'_> {
@@ -75,37 +96,24 @@ impl<'a> Expand<'a> {
         let mut values = HashMap::new();
         values.insert(
             PlaceHolder::InputFileNameNoExt.get_string(),
-            ExpandedValue::Mapping(Box::new(Expand::extract_file_name_no_ext)),
+            ExpandedValue::Mapping(extract_file_name_no_ext),
         );
         values.insert(
             PlaceHolder::InputFileName.get_string(),
-            ExpandedValue::Mapping(Box::new(Expand::extract_file_name)),
+            ExpandedValue::Mapping(extract_file_name),
         );
-        Self { values }
-    }
-
-    fn extract_file_name_no_ext(&self, _format_str: &str) -> Option<ExpandedValue<'a>> {
-        match self.values.get(&PlaceHolder::Input.get_string()) {
-            Some(ExpandedValue::Path(fp)) => {
-                let file = PathBuf::from(fp);
-                let stem = file.file_stem()?;
-                let name_as_str = String::from(stem.to_str()?);
-                Some(ExpandedValue::Scalar(name_as_str))
-            }
-            _ => None,
+        Self {
+            values,
+            strict: false,
         }
     }
 
-    fn extract_file_name(&self, _format_str: &str) -> Option<ExpandedValue<'a>> {
-        match self.values.get(&PlaceHolder::Input.get_string()) {
-            Some(ExpandedValue::Path(fp)) => {
-                let file = PathBuf::from(fp);
-                let name = file.file_name()?;
-                let name_as_str = String::from(name.to_str()?);
-                Some(ExpandedValue::Scalar(name_as_str))
-            }
-            _ => None,
-        }
+    /// In strict mode, evaluating a value fails if it contains a placeholder that
+    /// is not known, such as a misspelled `{imput}`. Otherwise, unknown
+    /// placeholders are left as-is.
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
     }
 
     pub fn set_value(&mut self, name: PlaceHolder, value: ExpandedValue<'a>) -> &mut Self {
@@ -215,71 +223,210 @@ impl<'a> Expand<'a> {
         self
     }
 
-    fn replace_value(
-        &self,
-        fmtstr: &str,
-        mut arg: String,
-        ev: &ExpandedValue<'a>,
-    ) -> Result<String> {
+    pub fn job_id(&mut self, arg: &Uuid) -> &mut Self {
+        self.set_value(PlaceHolder::JobId, ExpandedValue::Scalar(arg.to_string()));
+        self
+    }
+
+    pub fn task_id(&mut self, arg: &Uuid) -> &mut Self {
+        self.set_value(PlaceHolder::TaskId, ExpandedValue::Scalar(arg.to_string()));
+        self
+    }
+
+    pub fn reports_dir(&mut self, arg: impl AsRef<Path>) -> &mut Self {
+        let arg = arg.as_ref();
+        let path = String::from(arg.to_string_lossy());
+        self.set_value(PlaceHolder::ReportsDir, ExpandedValue::Path(path));
+        self
+    }
+
+    pub fn crashes_account(&mut self, arg: &str) -> &mut Self {
+        self.set_value(
+            PlaceHolder::CrashesAccount,
+            ExpandedValue::Scalar(String::from(arg)),
+        );
+        self
+    }
+
+    pub fn setup_dir(&mut self, arg: impl AsRef<Path>) -> &mut Self {
+        let arg = arg.as_ref();
+        let path = String::from(arg.to_string_lossy());
+        self.set_value(PlaceHolder::SetupDir, ExpandedValue::Path(path));
+        self
+    }
+
+    /// Set `{target_timeout}`, in seconds. If `None`, the placeholder is unset, so
+    /// templates can give a default, as in `{target_timeout:-5}`.
+    pub fn target_timeout(&mut self, arg: Option<u64>) -> &mut Self {
+        if let Some(timeout) = arg {
+            self.set_value(
+                PlaceHolder::TargetTimeout,
+                ExpandedValue::Scalar(timeout.to_string()),
+            );
+        }
+        self
+    }
+
+    fn resolve(&self, key: &str, ev: &ExpandedValue<'a>) -> Result<Option<String>> {
         match ev {
             ExpandedValue::Path(v) => {
                 let path = String::from(dunce::canonicalize(v)?.to_string_lossy());
-                arg = arg.replace(fmtstr, &path);
-                Ok(arg)
-            }
-            ExpandedValue::Scalar(v) => {
-                arg = arg.replace(fmtstr, &v);
-                Ok(arg)
+                Ok(Some(path))
             }
+            ExpandedValue::Scalar(v) => Ok(Some(v.clone())),
             ExpandedValue::List(value) => {
                 let replaced = self.evaluate(value)?;
-                let replaced = replaced.join(" ");
-                arg = arg.replace(fmtstr, &replaced);
-                Ok(arg)
+                Ok(Some(replaced.join(" ")))
             }
-            ExpandedValue::Mapping(func) => {
-                if let Some(value) = func(self, &fmtstr) {
-                    let arg = self.replace_value(fmtstr, arg, &value)?;
-                    Ok(arg)
-                } else {
-                    Ok(arg)
+            ExpandedValue::Mapping(func) => Ok(func(self, key)),
+        }
+    }
+
+    // Value of the placeholder, or `None` if it is unset.
+    fn lookup(&self, placeholder: &Placeholder) -> Result<Option<String>> {
+        if let Some(name) = placeholder.name.strip_prefix("env:") {
+            return Ok(std::env::var(name).ok());
+        }
+
+        let key = format!("{{{}}}", placeholder.name);
+        match self.values.get(&key) {
+            Some(ev) => self.resolve(&key, ev),
+            None => Ok(None),
+        }
+    }
+
+    fn is_known(&self, placeholder: &Placeholder) -> bool {
+        let key = format!("{{{}}}", placeholder.name);
+        placeholder.name.starts_with("env:") || PlaceHolder::iter().any(|p| p.get_string() == key)
+    }
+
+    fn render(&self, template: &Template) -> Result<String> {
+        let mut result = String::new();
+
+        for segment in template.segments() {
+            match segment {
+                Segment::Text(text) => result.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    result.push_str(&self.render_placeholder(placeholder)?)
                 }
             }
         }
+
+        Ok(result)
     }
 
-    pub fn evaluate_value<T: AsRef<str>>(&self, arg: T) -> Result<String> {
-        let mut arg = arg.as_ref().to_owned();
-
-        for placeholder in PlaceHolder::iter() {
-            let fmtstr = &placeholder.get_string();
-            match (
-                arg.contains(fmtstr),
-                self.values.get(&placeholder.get_string()),
-            ) {
-                (true, Some(ev)) => arg = self.replace_value(fmtstr, arg, ev)?,
-                (true, None) => bail!("missing argument {}", fmtstr),
-                (false, _) => (),
+    fn render_placeholder(&self, placeholder: &Placeholder) -> Result<String> {
+        if !self.is_known(placeholder) {
+            if self.strict {
+                bail!(
+                    "unknown placeholder {} at offset {}",
+                    placeholder.source,
+                    placeholder.offset
+                );
+            }
+
+            // Not ours, so pass it through to the target as-is.
+            return Ok(placeholder.source.clone());
+        }
+
+        match (&placeholder.modifier, self.lookup(placeholder)?) {
+            (Modifier::Required, Some(value)) => Ok(value),
+            (Modifier::Required, None) => {
+                if let Some(name) = placeholder.name.strip_prefix("env:") {
+                    bail!("missing environment variable {}", name);
+                }
+                if let Some(ExpandedValue::Mapping(_)) = self.values.get(&placeholder.source) {
+                    // Mappings without a value to map are left as-is.
+                    return Ok(placeholder.source.clone());
+                }
+                bail!("missing argument {}", placeholder.source)
             }
+            (Modifier::Optional, value) => Ok(value.unwrap_or_default()),
+            (Modifier::Default(default), value) => match value {
+                Some(value) if !value.is_empty() => Ok(value),
+                _ => self.render(default),
+            },
+            (Modifier::Alternate(alternate), value) => match value {
+                Some(value) if !value.is_empty() => self.render(alternate),
+                _ => Ok(String::new()),
+            },
         }
-        Ok(arg)
     }
 
+    // Parse and render `arg`, returning both the template and its value.
+    //
+    // Outside of strict mode, braces that aren't part of a placeholder are
+    // passed through, so existing arguments such as JSON values keep working.
+    fn expand_template(&self, arg: &str) -> Result<(Template, String)> {
+        let template = if self.strict {
+            Template::parse(arg)
+                .map_err(|err| format_err!("invalid template '{}': {}", arg, err))?
+        } else {
+            Template::parse_lenient(arg)
+        };
+
+        let value = self
+            .render(&template)
+            .map_err(|err| format_err!("unable to expand '{}': {}", arg, err))?;
+
+        Ok((template, value))
+    }
+
+    /// Evaluate a single template, such as the value of an environment variable.
+    pub fn evaluate_value<T: AsRef<str>>(&self, arg: T) -> Result<String> {
+        let (_, value) = self.expand_template(arg.as_ref())?;
+        Ok(value)
+    }
+
+    /// Evaluate a list of templates, such as a command line.
+    ///
+    /// Arguments with an optional (`?`) or alternate (`:+`) placeholder are
+    /// dropped if they evaluate to the empty string.
     pub fn evaluate<T: AsRef<str>>(&self, args: &[T]) -> Result<Vec<String>> {
         let mut result = Vec::new();
         for arg in args {
-            let arg = self.evaluate_value(arg)?;
-            result.push(arg);
+            let (template, value) = self.expand_template(arg.as_ref())?;
+
+            if value.is_empty() && template.is_conditional() {
+                continue;
+            }
+
+            result.push(value);
         }
         Ok(result)
     }
 }
 
+// Mappings are free functions, rather than methods, so they are not tied to the
+// lifetime of any one `Expand`.
+fn extract_file_name_no_ext(expand: &Expand<'_>, _format_str: &str) -> Option<String> {
+    match expand.values.get(&PlaceHolder::Input.get_string()) {
+        Some(ExpandedValue::Path(fp)) => {
+            let file = PathBuf::from(fp);
+            let stem = file.file_stem()?;
+            Some(String::from(stem.to_str()?))
+        }
+        _ => None,
+    }
+}
+
+fn extract_file_name(expand: &Expand<'_>, _format_str: &str) -> Option<String> {
+    match expand.values.get(&PlaceHolder::Input.get_string()) {
+        Some(ExpandedValue::Path(fp)) => {
+            let file = PathBuf::from(fp);
+            let name = file.file_name()?;
+            Some(String::from(name.to_str()?))
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Expand;
     use anyhow::Result;
-    use std::path::Path;
+    use std::{collections::HashMap, path::Path};
+    use uuid::Uuid;
 
     #[test]
     fn test_expand() -> Result<()> {
@@ -350,4 +497,173 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_expand_ids() -> Result<()> {
+        let job_id = Uuid::parse_str("00000000-0000-0000-0000-000000000001")?;
+        let task_id = Uuid::parse_str("00000000-0000-0000-0000-000000000002")?;
+
+        let result = Expand::new()
+            .job_id(&job_id)
+            .task_id(&task_id)
+            .crashes_account("fuzzaccount")
+            .evaluate_value("{job_id}/{task_id}@{crashes_account}")?;
+
+        assert_eq!(
+            result,
+            "00000000-0000-0000-0000-000000000001/00000000-0000-0000-0000-000000000002@fuzzaccount"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_env() -> Result<()> {
+        std::env::set_var("ONEFUZZ_TEST_EXPAND_ENV", "value");
+
+        let result = Expand::new().evaluate_value("-x={env:ONEFUZZ_TEST_EXPAND_ENV}")?;
+        assert_eq!(result, "-x=value");
+
+        assert!(Expand::new()
+            .evaluate_value("{env:ONEFUZZ_TEST_EXPAND_ENV_UNSET}")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_strict() -> Result<()> {
+        // Unknown placeholders are left as-is by default.
+        let result = Expand::new().evaluate_value("{imput}")?;
+        assert_eq!(result, "{imput}");
+
+        assert!(Expand::new()
+            .strict(true)
+            .evaluate_value("{imput}")
+            .is_err());
+        assert!(Expand::new()
+            .strict(true)
+            .evaluate(&["-runs=10", "{input_file}"])
+            .is_err());
+
+        let result = Expand::new()
+            .strict(true)
+            .input_marker("@@")
+            .evaluate_value("{input}")?;
+        assert_eq!(result, "@@");
+
+        // Known placeholders without values fail in either mode.
+        assert!(Expand::new()
+            .strict(true)
+            .evaluate_value("{task_id}")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_escapes() -> Result<()> {
+        let result = Expand::new()
+            .strict(true)
+            .evaluate_value("-dict={{\"a\": 1}}")?;
+        assert_eq!(result, "-dict={\"a\": 1}");
+
+        let err = Expand::new()
+            .strict(true)
+            .evaluate_value("-x={input")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid template '-x={input': unterminated placeholder at offset 3"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_literal_braces() -> Result<()> {
+        let corpus = Path::new("data");
+        let env: HashMap<String, String> = vec![
+            (
+                "OPTIONS",
+                r#"{"corpus": "{input_corpus}", "retries": {"max": 3}}"#,
+            ),
+            ("SESSION", "{6f3c2a1e-9b1d-4f7a-8c2e-5d4b3a2f1e0d}"),
+            ("ESCAPED", "{{input_corpus}} }} a}b {"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect();
+
+        let mut expand = Expand::new();
+        expand.input_corpus(corpus);
+
+        let corpus_path = dunce::canonicalize(corpus)?;
+        assert_eq!(
+            expand.evaluate_value(&env["OPTIONS"])?,
+            format!(
+                r#"{{"corpus": "{}", "retries": {{"max": 3}}}}"#,
+                corpus_path.display()
+            )
+        );
+        assert_eq!(
+            expand.evaluate_value(&env["SESSION"])?,
+            "{6f3c2a1e-9b1d-4f7a-8c2e-5d4b3a2f1e0d}"
+        );
+        assert_eq!(
+            expand.evaluate_value(&env["ESCAPED"])?,
+            format!("{{{}}} }}}} a}}b {{", corpus_path.display())
+        );
+
+        // Strict mode requires escapes.
+        assert!(expand.strict(true).evaluate_value(&env["OPTIONS"]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_defaults() -> Result<()> {
+        let result = Expand::new().evaluate_value("-timeout={target_timeout:-5}")?;
+        assert_eq!(result, "-timeout=5");
+
+        let result = Expand::new()
+            .target_timeout(Some(30))
+            .evaluate_value("-timeout={target_timeout:-5}")?;
+        assert_eq!(result, "-timeout=30");
+
+        // Defaults are templates too.
+        let job_id = Uuid::parse_str("00000000-0000-0000-0000-000000000001")?;
+        let result = Expand::new()
+            .job_id(&job_id)
+            .evaluate_value("{task_id:-{job_id}}")?;
+        assert_eq!(result, "00000000-0000-0000-0000-000000000001");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_conditional_arguments() -> Result<()> {
+        let empty: Vec<String> = vec![];
+        let options = vec!["-max_len=100".to_owned()];
+        let args = vec![
+            "-a",
+            "{target_options:+--options={target_options}}",
+            "{task_id?}",
+            "-b",
+        ];
+
+        let result = Expand::new().target_options(&empty).evaluate(&args)?;
+        assert_eq!(result, vec!["-a", "-b"]);
+
+        let result = Expand::new().target_options(&options).evaluate(&args)?;
+        assert_eq!(result, vec!["-a", "--options=-max_len=100", "-b"]);
+
+        // Without a conditional, an empty value is still an argument.
+        let result = Expand::new()
+            .target_options(&empty)
+            .evaluate(&["{target_options}"])?;
+        assert_eq!(result, vec![""]);
+
+        Ok(())
+    }
 }
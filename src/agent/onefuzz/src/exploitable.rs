@@ -0,0 +1,539 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Heuristic exploitability classification of native crashes, in the style of
+//! CERT triage tools and `!exploitable`.
+//!
+//! The classifier only looks at plain data, so it can be tested and reported on
+//! any platform. On Linux, the inputs are gathered by `triage::Crash`.
+
+use iced_x86::{Code, Decoder, DecoderOptions, InstructionInfoFactory, OpAccess};
+use std::fmt;
+
+// Faults below this address are treated as null pointer dereferences.
+const NULL_PAGE_LIMIT: u64 = 64 * 1024;
+
+// Faults this close below the stack pointer are treated as stack exhaustion.
+const STACK_EXHAUSTION_LIMIT: u64 = 64 * 1024;
+
+// Lowest address of the upper half of the x86-64 canonical address space.
+const CANONICAL_UPPER_HALF: u64 = 0xffff_8000_0000_0000;
+
+// Highest address of the lower half of the x86-64 canonical address space.
+const CANONICAL_LOWER_HALF: u64 = 0x0000_7fff_ffff_ffff;
+
+/// `si_code` values for `SIGSEGV`. See `sigaction(2)`.
+pub const SEGV_MAPERR: i32 = 1;
+pub const SEGV_ACCERR: i32 = 2;
+
+/// `si_code` of signals sent by the kernel, such as a `SIGSEGV` for a general
+/// protection fault. These have no fault address.
+pub const SI_KERNEL: i32 = 0x80;
+
+// Functions that abort after detecting corruption of the stack or heap.
+const STACK_CORRUPTION_FUNCTIONS: &[&str] = &["__stack_chk_fail", "__fortify_fail"];
+const HEAP_CORRUPTION_FUNCTIONS: &[&str] = &["malloc_printerr", "__malloc_assert"];
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Exploitable,
+    ProbablyExploitable,
+    ProbablyNotExploitable,
+    Unknown,
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Rating::Exploitable => "exploitable",
+            Rating::ProbablyExploitable => "probably exploitable",
+            Rating::ProbablyNotExploitable => "probably not exploitable",
+            Rating::Unknown => "unknown",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// Exploitability rating of a crash, with the reasons that led to it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Exploitability {
+    pub rating: Rating,
+    pub reasons: Vec<String>,
+}
+
+/// A mapped region of the crashing process's address space.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MappedRegion {
+    pub start: u64,
+    pub end: u64,
+    pub readable: bool,
+    pub writable: bool,
+    pub executable: bool,
+    pub name: Option<String>,
+}
+
+impl MappedRegion {
+    pub fn contains(&self, addr: u64) -> bool {
+        (self.start..self.end).contains(&addr)
+    }
+}
+
+/// State of a crashed thread at the time its crashing signal was delivered.
+#[derive(Clone, Debug, Default)]
+pub struct CrashContext {
+    /// Name of the crashing signal, such as `SIGSEGV`.
+    pub signal: String,
+
+    /// `si_code` of the signal.
+    pub si_code: i32,
+
+    /// Address of the faulting memory access, for `SIGSEGV`.
+    pub fault_address: Option<u64>,
+
+    /// Program counter of the crashing thread.
+    pub pc: Option<u64>,
+
+    /// Stack pointer of the crashing thread.
+    pub sp: Option<u64>,
+
+    /// Memory mappings of the crashing process.
+    pub regions: Vec<MappedRegion>,
+
+    /// Bytes of memory at `pc`. May be empty if unreadable.
+    pub instruction: Vec<u8>,
+
+    /// Functions of the crashing thread's stack, from inner to outer.
+    pub functions: Vec<String>,
+}
+
+impl CrashContext {
+    fn find_region(&self, addr: u64) -> Option<&MappedRegion> {
+        self.regions.iter().find(|r| r.contains(addr))
+    }
+}
+
+/// Rate the exploitability of a crash.
+pub fn classify(crash: &CrashContext) -> Exploitability {
+    let mut reasons = vec![];
+    let rating = rate(crash, &mut reasons);
+
+    Exploitability { rating, reasons }
+}
+
+fn rate(crash: &CrashContext, reasons: &mut Vec<String>) -> Rating {
+    if let Some(rating) = rate_pc(crash, reasons) {
+        return rating;
+    }
+
+    match crash.signal.as_str() {
+        "SIGSEGV" => rate_segv(crash, reasons),
+        "SIGILL" => {
+            if is_ud2(&crash.instruction) {
+                reasons.push("illegal instruction is `ud2`, a compiler-inserted trap".into());
+                Rating::ProbablyNotExploitable
+            } else {
+                reasons.push("illegal instruction, possibly executing data".into());
+                Rating::ProbablyExploitable
+            }
+        }
+        "SIGABRT" => rate_abort(crash, reasons),
+        "SIGFPE" => {
+            reasons.push("arithmetic exception, such as division by zero".into());
+            Rating::ProbablyNotExploitable
+        }
+        "SIGBUS" => {
+            reasons
+                .push("bus error, such as a misaligned access or a truncated mapped file".into());
+            Rating::ProbablyNotExploitable
+        }
+        "SIGTRAP" => {
+            reasons.push("trace or breakpoint trap".into());
+            Rating::Unknown
+        }
+        signal => {
+            reasons.push(format!("no rules for signal {}", signal));
+            Rating::Unknown
+        }
+    }
+}
+
+// Check for a corrupted program counter, which takes precedence over the signal.
+fn rate_pc(crash: &CrashContext, reasons: &mut Vec<String>) -> Option<Rating> {
+    let pc = crash.pc?;
+
+    if crash.regions.is_empty() {
+        return None;
+    }
+
+    match crash.find_region(pc) {
+        Some(region) if region.executable => None,
+        Some(_) => {
+            reasons.push(format!(
+                "program counter 0x{:x} is in non-executable memory",
+                pc
+            ));
+            Some(Rating::Exploitable)
+        }
+        None if pc < NULL_PAGE_LIMIT => {
+            reasons.push(format!(
+                "program counter 0x{:x} is near null, likely a call through a null function pointer",
+                pc
+            ));
+            Some(Rating::ProbablyNotExploitable)
+        }
+        None => {
+            reasons.push(format!("program counter 0x{:x} is not mapped", pc));
+            Some(Rating::Exploitable)
+        }
+    }
+}
+
+fn rate_segv(crash: &CrashContext, reasons: &mut Vec<String>) -> Rating {
+    if crash.si_code == SI_KERNEL {
+        reasons.push(
+            "general protection fault, likely a corrupt or attacker-controlled non-canonical pointer"
+                .into(),
+        );
+        return Rating::ProbablyExploitable;
+    }
+
+    let addr = match crash.fault_address {
+        Some(addr) => addr,
+        None => {
+            reasons.push("segmentation fault with unknown fault address".into());
+            return Rating::Unknown;
+        }
+    };
+
+    if let Some(sp) = crash.sp {
+        if addr < sp && sp - addr <= STACK_EXHAUSTION_LIMIT {
+            reasons.push(format!(
+                "fault address 0x{:x} is near the stack pointer 0x{:x}, likely stack exhaustion",
+                addr, sp
+            ));
+            return Rating::ProbablyNotExploitable;
+        }
+    }
+
+    if addr < NULL_PAGE_LIMIT {
+        reasons.push(format!(
+            "fault address 0x{:x} is near null, likely a null pointer dereference",
+            addr
+        ));
+        return Rating::ProbablyNotExploitable;
+    }
+
+    if addr > CANONICAL_LOWER_HALF && addr < CANONICAL_UPPER_HALF {
+        reasons.push(format!(
+            "fault address 0x{:x} is non-canonical, likely a corrupt or attacker-controlled pointer",
+            addr
+        ));
+        return Rating::ProbablyExploitable;
+    }
+
+    match (crash.si_code, crash.find_region(addr)) {
+        (SEGV_ACCERR, Some(region)) => {
+            let name = region.name.as_deref().unwrap_or("<anonymous>");
+            reasons.push(format!(
+                "fault address 0x{:x} violates the permissions of its mapping ({})",
+                addr, name
+            ));
+        }
+        _ => {
+            reasons.push(format!("fault address 0x{:x} is not mapped", addr));
+        }
+    }
+
+    match access_kind(&crash.instruction) {
+        Some(Access::Write) => {
+            reasons.push("faulting instruction writes to memory".into());
+            Rating::Exploitable
+        }
+        Some(Access::Read) => {
+            reasons.push("faulting instruction reads from memory".into());
+            Rating::Unknown
+        }
+        None => {
+            reasons.push("unable to determine the kind of memory access".into());
+            Rating::Unknown
+        }
+    }
+}
+
+fn rate_abort(crash: &CrashContext, reasons: &mut Vec<String>) -> Rating {
+    let called = |names: &[&str]| {
+        crash
+            .functions
+            .iter()
+            .find(|f| names.iter().any(|name| f.starts_with(name)))
+            .cloned()
+    };
+
+    if let Some(function) = called(STACK_CORRUPTION_FUNCTIONS) {
+        reasons.push(format!("stack corruption detected by `{}`", function));
+        return Rating::Exploitable;
+    }
+
+    if let Some(function) = called(HEAP_CORRUPTION_FUNCTIONS) {
+        reasons.push(format!("heap corruption detected by `{}`", function));
+        return Rating::ProbablyExploitable;
+    }
+
+    reasons.push("abort, likely a failed assertion".into());
+    Rating::ProbablyNotExploitable
+}
+
+fn is_ud2(instruction: &[u8]) -> bool {
+    instruction.starts_with(&[0x0f, 0x0b])
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Access {
+    Read,
+    Write,
+}
+
+// Classify the memory access of an x86-64 instruction. An instruction that both
+// reads and writes memory, such as `movs` or `add [rax], 1`, counts as a write.
+fn access_kind(instruction: &[u8]) -> Option<Access> {
+    if instruction.is_empty() {
+        return None;
+    }
+
+    let mut decoder = Decoder::new(64, instruction, DecoderOptions::NONE);
+    let instruction = decoder.decode();
+
+    if instruction.code() == Code::INVALID {
+        return None;
+    }
+
+    let mut factory = InstructionInfoFactory::new();
+    let info = factory.info(&instruction);
+
+    let mut kind = None;
+    for memory in info.used_memory() {
+        match memory.access() {
+            OpAccess::Write
+            | OpAccess::CondWrite
+            | OpAccess::ReadWrite
+            | OpAccess::ReadCondWrite => return Some(Access::Write),
+            OpAccess::Read | OpAccess::CondRead => kind = Some(Access::Read),
+            OpAccess::None | OpAccess::NoMemAccess => {}
+        }
+    }
+
+    kind
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions() -> Vec<MappedRegion> {
+        vec![
+            MappedRegion {
+                start: 0x55_0000_0000,
+                end: 0x55_0001_0000,
+                readable: true,
+                executable: true,
+                name: Some("/src/fuzz.exe".into()),
+                ..Default::default()
+            },
+            MappedRegion {
+                start: 0x55_0001_0000,
+                end: 0x55_0002_0000,
+                readable: true,
+                name: Some("/src/fuzz.exe".into()),
+                ..Default::default()
+            },
+            MappedRegion {
+                start: 0x7ffc_0000_0000,
+                end: 0x7ffc_0002_0000,
+                readable: true,
+                writable: true,
+                name: Some("[stack]".into()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn segv(fault_address: u64, instruction: &[u8]) -> CrashContext {
+        CrashContext {
+            signal: "SIGSEGV".into(),
+            si_code: SEGV_MAPERR,
+            fault_address: Some(fault_address),
+            pc: Some(0x55_0000_1234),
+            sp: Some(0x7ffc_0001_0000),
+            regions: regions(),
+            instruction: instruction.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_segv_write() {
+        // mov qword ptr [rax], rcx
+        let crash = segv(0x6000_0000_0000, &[0x48, 0x89, 0x08]);
+        assert_eq!(classify(&crash).rating, Rating::Exploitable);
+
+        // rep stosb
+        let crash = segv(0x6000_0000_0000, &[0xf3, 0xaa]);
+        assert_eq!(classify(&crash).rating, Rating::Exploitable);
+    }
+
+    #[test]
+    fn test_segv_read() {
+        // mov rcx, qword ptr [rax]
+        let crash = segv(0x6000_0000_0000, &[0x48, 0x8b, 0x08]);
+        assert_eq!(classify(&crash).rating, Rating::Unknown);
+
+        // movzx eax, byte ptr [rdi]
+        let crash = segv(0x6000_0000_0000, &[0x0f, 0xb6, 0x07]);
+        assert_eq!(classify(&crash).rating, Rating::Unknown);
+    }
+
+    #[test]
+    fn test_segv_near_null() {
+        let crash = segv(0x10, &[0x48, 0x89, 0x08]);
+        let exploitability = classify(&crash);
+        assert_eq!(exploitability.rating, Rating::ProbablyNotExploitable);
+        assert!(exploitability.reasons[0].contains("null pointer"));
+    }
+
+    #[test]
+    fn test_segv_stack_exhaustion() {
+        // push rbp
+        let mut crash = segv(0x7ffb_ffff_fff8, &[0x55]);
+        crash.sp = Some(0x7ffc_0000_0000);
+        assert_eq!(classify(&crash).rating, Rating::ProbablyNotExploitable);
+    }
+
+    #[test]
+    fn test_segv_above_stack_pointer() {
+        // mov qword ptr [rsp+0x10], rax, past the end of the stack mapping.
+        let mut crash = segv(0x7ffc_0002_0008, &[0x48, 0x89, 0x44, 0x24, 0x10]);
+        crash.sp = Some(0x7ffc_0001_fff8);
+        assert_eq!(classify(&crash).rating, Rating::Exploitable);
+    }
+
+    #[test]
+    fn test_access_kind() {
+        // add dword ptr [rax], 1
+        assert_eq!(access_kind(&[0x83, 0x00, 0x01]), Some(Access::Write));
+
+        // vmovdqu ymmword ptr [rdi], ymm0
+        assert_eq!(access_kind(&[0xc5, 0xfe, 0x7f, 0x07]), Some(Access::Write));
+
+        // cmp byte ptr [rsi], 0
+        assert_eq!(access_kind(&[0x80, 0x3e, 0x00]), Some(Access::Read));
+
+        // lea rax, [rbx+8]
+        assert_eq!(access_kind(&[0x48, 0x8d, 0x43, 0x08]), None);
+
+        assert_eq!(access_kind(&[]), None);
+    }
+
+    #[test]
+    fn test_segv_non_canonical() {
+        let crash = segv(0x4141_4141_4141_4141, &[0x48, 0x8b, 0x08]);
+        assert_eq!(classify(&crash).rating, Rating::ProbablyExploitable);
+
+        // The kernel reports no fault address for general protection faults.
+        let mut crash = segv(0, &[0x48, 0x8b, 0x08]);
+        crash.si_code = SI_KERNEL;
+        assert_eq!(classify(&crash).rating, Rating::ProbablyExploitable);
+    }
+
+    #[test]
+    fn test_segv_access_violation() {
+        // Write to the read-only data of the executable.
+        let mut crash = segv(0x55_0001_0010, &[0xc6, 0x00, 0x41]);
+        crash.si_code = SEGV_ACCERR;
+        let exploitability = classify(&crash);
+        assert_eq!(exploitability.rating, Rating::Exploitable);
+        assert!(exploitability.reasons[0].contains("/src/fuzz.exe"));
+    }
+
+    #[test]
+    fn test_bad_pc() {
+        let mut crash = segv(0x4141_4141_4141, &[]);
+        crash.pc = Some(0x4141_4141_4141);
+        assert_eq!(classify(&crash).rating, Rating::Exploitable);
+
+        // Jump into the stack.
+        let mut crash = segv(0x7ffc_0001_0100, &[]);
+        crash.pc = Some(0x7ffc_0001_0100);
+        crash.si_code = SEGV_ACCERR;
+        assert_eq!(classify(&crash).rating, Rating::Exploitable);
+
+        // Call through a null function pointer.
+        let mut crash = segv(0, &[]);
+        crash.pc = Some(0);
+        assert_eq!(classify(&crash).rating, Rating::ProbablyNotExploitable);
+    }
+
+    #[test]
+    fn test_sigill() {
+        let crash = CrashContext {
+            signal: "SIGILL".into(),
+            instruction: vec![0x0f, 0x0b],
+            ..Default::default()
+        };
+        assert_eq!(classify(&crash).rating, Rating::ProbablyNotExploitable);
+
+        let crash = CrashContext {
+            signal: "SIGILL".into(),
+            instruction: vec![0xff, 0xff],
+            ..Default::default()
+        };
+        assert_eq!(classify(&crash).rating, Rating::ProbablyExploitable);
+    }
+
+    #[test]
+    fn test_sigabrt() {
+        let functions = |names: &[&str]| names.iter().map(|&n| n.to_owned()).collect();
+
+        let crash = CrashContext {
+            signal: "SIGABRT".into(),
+            functions: functions(&["raise", "abort", "__assert_fail", "parse"]),
+            ..Default::default()
+        };
+        assert_eq!(classify(&crash).rating, Rating::ProbablyNotExploitable);
+
+        let crash = CrashContext {
+            signal: "SIGABRT".into(),
+            functions: functions(&[
+                "raise",
+                "abort",
+                "__libc_message",
+                "malloc_printerr",
+                "_int_free",
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(classify(&crash).rating, Rating::ProbablyExploitable);
+
+        let crash = CrashContext {
+            signal: "SIGABRT".into(),
+            functions: functions(&[
+                "raise",
+                "abort",
+                "__libc_message",
+                "__fortify_fail",
+                "__stack_chk_fail",
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(classify(&crash).rating, Rating::Exploitable);
+    }
+
+    #[test]
+    fn test_unknown_signal() {
+        let crash = CrashContext {
+            signal: "SIGTRAP".into(),
+            ..Default::default()
+        };
+        assert_eq!(classify(&crash).rating, Rating::Unknown);
+    }
+}
@@ -0,0 +1,540 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use anyhow::Result;
+use rand::{prelude::*, rngs::StdRng};
+use regex::Regex;
+use std::{collections::BTreeMap, path::Path};
+use tokio::fs;
+
+const START: &str = "<start>";
+
+lazy_static! {
+    static ref JSON_NONTERMINAL: Regex = Regex::new(r"<[^<> ]+>").unwrap();
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Symbol {
+    Terminal(Vec<u8>),
+    NonTerminal(String),
+}
+
+#[derive(Clone, Debug)]
+struct Expansion {
+    symbols: Vec<Symbol>,
+
+    // Least depth of a derivation of the expansion into terminals.
+    cost: usize,
+}
+
+/// A context-free grammar, from which inputs are generated starting at the
+/// `<start>` rule.
+///
+/// Grammars are read either as BNF, with quoted terminals:
+///
+/// ```text
+/// # Lines starting with `#` are comments.
+/// <start> ::= <number> | <number> "+" <start>
+/// <number> ::= <digit> | <digit> <number>
+/// <digit> ::= "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9"
+/// ```
+///
+/// or as JSON, mapping each rule to its expansions, in which text that names a
+/// rule, like `<digit>`, refers to the rule, and other text is a terminal:
+///
+/// ```text
+/// {
+///   "<start>": ["<number>", "<number>+<start>"],
+///   "<number>": ["<digit>", "<digit><number>"],
+///   "<digit>": ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Grammar {
+    rules: BTreeMap<String, Vec<Expansion>>,
+}
+
+impl Grammar {
+    /// Load a grammar, as JSON if the file has a `.json` extension, and as
+    /// BNF otherwise.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let result: Result<Self> = async {
+            let text = fs::read_to_string(path).await?;
+            if path.extension().map_or(false, |ext| ext == "json") {
+                Self::parse_json(&text)
+            } else {
+                Self::parse_bnf(&text)
+            }
+        }
+        .await;
+
+        result.map_err(|err| format_err!("{}: {}", path.display(), err))
+    }
+
+    pub fn parse_bnf(text: &str) -> Result<Self> {
+        let mut rules = BTreeMap::new();
+        let mut current = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            parse_bnf_line(line, &mut current, &mut rules)
+                .map_err(|err| format_err!("invalid grammar on line {}: {}", index + 1, err))?;
+        }
+
+        Self::new(rules)
+    }
+
+    pub fn parse_json(text: &str) -> Result<Self> {
+        let json: BTreeMap<String, Vec<String>> = serde_json::from_str(text)?;
+
+        let mut rules = BTreeMap::new();
+        for (name, expansions) in &json {
+            if !is_nonterminal(name) {
+                bail!("invalid rule name `{}`", name);
+            }
+
+            let expansions = expansions
+                .iter()
+                .map(|expansion| parse_json_expansion(expansion, &json))
+                .collect();
+            rules.insert(name.clone(), expansions);
+        }
+
+        Self::new(rules)
+    }
+
+    fn new(rules: BTreeMap<String, Vec<Vec<Symbol>>>) -> Result<Self> {
+        if !rules.contains_key(START) {
+            bail!("missing `{}` rule", START);
+        }
+
+        for expansions in rules.values() {
+            for symbol in expansions.iter().flatten() {
+                if let Symbol::NonTerminal(name) = symbol {
+                    if !rules.contains_key(name) {
+                        bail!("undefined rule `{}`", name);
+                    }
+                }
+            }
+        }
+
+        let costs = rule_costs(&rules);
+        if let Some((name, _)) = costs.iter().find(|(_, cost)| **cost == usize::MAX) {
+            bail!("rule `{}` never derives a finite input", name);
+        }
+
+        let rules = rules
+            .into_iter()
+            .map(|(name, expansions)| {
+                let expansions = expansions
+                    .into_iter()
+                    .map(|symbols| {
+                        let cost = expansion_cost(&symbols, &costs);
+                        Expansion { symbols, cost }
+                    })
+                    .collect();
+                (name, expansions)
+            })
+            .collect();
+
+        Ok(Self { rules })
+    }
+}
+
+/// Generates inputs from a grammar.
+///
+/// Once a derivation is `max_depth` rules deep, only the expansions that end
+/// it the soonest are used. A generator with a given seed produces the same
+/// inputs.
+pub struct GrammarGenerator {
+    grammar: Grammar,
+    rng: StdRng,
+    max_depth: usize,
+}
+
+impl GrammarGenerator {
+    pub fn new(grammar: Grammar, seed: u64, max_depth: usize) -> Self {
+        Self {
+            grammar,
+            rng: StdRng::seed_from_u64(seed),
+            max_depth,
+        }
+    }
+
+    pub fn generate(&mut self) -> Vec<u8> {
+        let mut output = vec![];
+        expand(&self.grammar, &mut self.rng, self.max_depth, &mut output);
+        output
+    }
+}
+
+// Derive an input from the `<start>` rule.
+//
+// Derivations are expanded with an explicit stack rather than by recursion, so
+// deep grammars or a large `max_depth` can't overflow the stack of the agent.
+fn expand(grammar: &Grammar, rng: &mut StdRng, max_depth: usize, output: &mut Vec<u8>) {
+    let start = Symbol::NonTerminal(START.to_owned());
+
+    // Symbols left to derive, the next one last, with the depth of their rule.
+    let mut pending = vec![(&start, 0)];
+
+    while let Some((symbol, depth)) = pending.pop() {
+        let name = match symbol {
+            Symbol::Terminal(bytes) => {
+                output.extend_from_slice(bytes);
+                continue;
+            }
+            Symbol::NonTerminal(name) => name,
+        };
+
+        let expansions = &grammar.rules[name];
+
+        let expansion = if depth < max_depth {
+            expansions.choose(rng)
+        } else {
+            let least = expansions.iter().map(|e| e.cost).min().unwrap_or_default();
+            let cheapest: Vec<_> = expansions.iter().filter(|e| e.cost == least).collect();
+            cheapest.choose(rng).copied()
+        };
+
+        if let Some(expansion) = expansion {
+            let symbols = expansion.symbols.iter().rev();
+            pending.extend(symbols.map(|symbol| (symbol, depth + 1)));
+        }
+    }
+}
+
+// Least depth of a derivation of each rule, or `usize::MAX` if it has none.
+fn rule_costs(rules: &BTreeMap<String, Vec<Vec<Symbol>>>) -> BTreeMap<String, usize> {
+    let mut costs: BTreeMap<String, usize> = rules
+        .keys()
+        .map(|name| (name.clone(), usize::MAX))
+        .collect();
+
+    loop {
+        let mut changed = false;
+        for (name, expansions) in rules {
+            let cost = expansions
+                .iter()
+                .map(|symbols| expansion_cost(symbols, &costs))
+                .min()
+                .unwrap_or(usize::MAX);
+
+            if cost < costs[name] {
+                costs.insert(name.clone(), cost);
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return costs;
+        }
+    }
+}
+
+fn expansion_cost(symbols: &[Symbol], costs: &BTreeMap<String, usize>) -> usize {
+    symbols
+        .iter()
+        .filter_map(|symbol| match symbol {
+            Symbol::NonTerminal(name) => Some(costs[name]),
+            Symbol::Terminal(_) => None,
+        })
+        .max()
+        .unwrap_or(0)
+        .saturating_add(1)
+}
+
+fn is_nonterminal(name: &str) -> bool {
+    name.len() > 2
+        && name.starts_with('<')
+        && name.ends_with('>')
+        && !name[1..name.len() - 1].contains(|c: char| c == '<' || c == '>' || c.is_whitespace())
+}
+
+// Parse a line of a BNF grammar, which either defines a rule, or continues the
+// definition of the current one.
+fn parse_bnf_line(
+    line: &str,
+    current: &mut Option<String>,
+    rules: &mut BTreeMap<String, Vec<Vec<Symbol>>>,
+) -> Result<()> {
+    let definition = line.find("::=").and_then(|offset| {
+        let name = line[..offset].trim();
+        if is_nonterminal(name) {
+            Some((name, &line[offset + 3..]))
+        } else {
+            None
+        }
+    });
+
+    let (name, body, continuation) = match (definition, current.as_ref()) {
+        (Some((name, body)), _) => (name.to_string(), body, false),
+        (None, Some(name)) => (name.clone(), line, true),
+        (None, None) => bail!("expected a rule definition"),
+    };
+
+    let mut alternatives = parse_bnf_alternatives(body)?.into_iter();
+    let expansions = rules.entry(name.clone()).or_default();
+
+    // A continuation extends the last alternative, unless it starts with `|`,
+    // in which case its first alternative is empty.
+    if continuation {
+        if let (Some(last), Some(first)) = (expansions.last_mut(), alternatives.next()) {
+            last.extend(first);
+        }
+    }
+    expansions.extend(alternatives);
+
+    *current = Some(name);
+    Ok(())
+}
+
+// Parse the alternatives of a BNF rule, like `<a> "b" | "c"`.
+fn parse_bnf_alternatives(body: &str) -> Result<Vec<Vec<Symbol>>> {
+    let mut alternatives = vec![vec![]];
+    let mut chars = body.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '|' => alternatives.push(vec![]),
+            '<' => {
+                let mut name = String::from("<");
+                loop {
+                    match chars.next() {
+                        Some('>') => break,
+                        Some(c) => name.push(c),
+                        None => bail!("expected `>` after `{}`", name),
+                    }
+                }
+                name.push('>');
+
+                if !is_nonterminal(&name) {
+                    bail!("invalid rule name `{}`", name);
+                }
+                alternatives
+                    .last_mut()
+                    .unwrap()
+                    .push(Symbol::NonTerminal(name));
+            }
+            '"' | '\'' => {
+                let quote = c;
+                let mut bytes = vec![];
+                loop {
+                    match chars.next() {
+                        Some(c) if c == quote => break,
+                        Some('\\') => bytes.push(parse_escape(&mut chars)?),
+                        Some(c) => {
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                        }
+                        None => bail!("expected a closing quote"),
+                    }
+                }
+                alternatives
+                    .last_mut()
+                    .unwrap()
+                    .push(Symbol::Terminal(bytes));
+            }
+            c => bail!("unexpected `{}`", c),
+        }
+    }
+
+    Ok(alternatives)
+}
+
+fn parse_escape(chars: &mut impl Iterator<Item = char>) -> Result<u8> {
+    let byte = match chars.next() {
+        Some('n') => b'\n',
+        Some('r') => b'\r',
+        Some('t') => b'\t',
+        Some('\\') => b'\\',
+        Some('"') => b'"',
+        Some('\'') => b'\'',
+        Some('x') => {
+            let hex: String = chars.take(2).collect();
+            u8::from_str_radix(&hex, 16).map_err(|_| format_err!("invalid escape `\\x{}`", hex))?
+        }
+        _ => bail!("invalid escape"),
+    };
+
+    Ok(byte)
+}
+
+// Split a JSON expansion, like `<digit>+<number>`, into the rules it refers to
+// and the text between them.
+fn parse_json_expansion(expansion: &str, rules: &BTreeMap<String, Vec<String>>) -> Vec<Symbol> {
+    let mut symbols = vec![];
+    let mut text = String::new();
+    let mut last = 0;
+
+    for m in JSON_NONTERMINAL.find_iter(expansion) {
+        if !rules.contains_key(m.as_str()) {
+            continue;
+        }
+
+        text.push_str(&expansion[last..m.start()]);
+        if !text.is_empty() {
+            symbols.push(Symbol::Terminal(text.into_bytes()));
+            text = String::new();
+        }
+        symbols.push(Symbol::NonTerminal(m.as_str().to_string()));
+        last = m.end();
+    }
+
+    text.push_str(&expansion[last..]);
+    if !text.is_empty() {
+        symbols.push(Symbol::Terminal(text.into_bytes()));
+    }
+
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BNF: &str = r#"
+# Sums of numbers.
+<start> ::= <number> | <number> "+" <start>
+<number> ::= <digit>
+    | <digit> <number>
+<digit> ::= "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9"
+"#;
+
+    const JSON: &str = r#"{
+        "<start>": ["<number>", "<number>+<start>"],
+        "<number>": ["<digit>", "<digit><number>"],
+        "<digit>": ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+    }"#;
+
+    fn is_sum(input: &[u8]) -> bool {
+        let input = std::str::from_utf8(input).unwrap();
+        input
+            .split('+')
+            .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    }
+
+    #[test]
+    fn test_generate_bnf() {
+        let grammar = Grammar::parse_bnf(BNF).unwrap();
+        let mut generator = GrammarGenerator::new(grammar, 0, 8);
+        for _ in 0..100 {
+            assert!(is_sum(&generator.generate()));
+        }
+    }
+
+    #[test]
+    fn test_generate_json() {
+        let grammar = Grammar::parse_json(JSON).unwrap();
+        let mut generator = GrammarGenerator::new(grammar, 0, 8);
+        for _ in 0..100 {
+            assert!(is_sum(&generator.generate()));
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let grammar = Grammar::parse_bnf(BNF).unwrap();
+        let mut a = GrammarGenerator::new(grammar.clone(), 42, 8);
+        let mut b = GrammarGenerator::new(grammar, 42, 8);
+        for _ in 0..100 {
+            assert_eq!(a.generate(), b.generate());
+        }
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let grammar = Grammar::parse_bnf(BNF).unwrap();
+
+        // At depth 0, only the cheapest expansions, a single digit, are used.
+        let mut generator = GrammarGenerator::new(grammar, 0, 0);
+        for _ in 0..100 {
+            let input = generator.generate();
+            assert_eq!(input.len(), 1);
+            assert!(input[0].is_ascii_digit());
+        }
+    }
+
+    #[test]
+    fn test_deep_grammar() {
+        // A chain of rules far deeper than the stack could hold frames for.
+        let depth = 100_000;
+        let mut bnf = format!("<start> ::= <r{:06}>\n<r000000> ::= \"x\"\n", depth - 1);
+        for i in 1..depth {
+            bnf.push_str(&format!("<r{:06}> ::= <r{:06}>\n", i, i - 1));
+        }
+
+        let grammar = Grammar::parse_bnf(&bnf).unwrap();
+        let mut generator = GrammarGenerator::new(grammar, 0, usize::MAX);
+        assert_eq!(generator.generate(), b"x");
+    }
+
+    #[test]
+    fn test_json_literal_brackets() {
+        let grammar = Grammar::parse_json(r#"{"<start>": ["<b><x></b>"], "<x>": ["x"]}"#).unwrap();
+        let mut generator = GrammarGenerator::new(grammar, 0, 8);
+        assert_eq!(generator.generate(), b"<b>x</b>");
+    }
+
+    #[test]
+    fn test_bnf_terminals() {
+        let grammar = Grammar::parse_bnf(r#"<start> ::= "a\"b" 'c\'d' "\x00\n" """#).unwrap();
+        let mut generator = GrammarGenerator::new(grammar, 0, 8);
+        assert_eq!(generator.generate(), b"a\"bc'd\x00\n");
+    }
+
+    #[test]
+    fn test_bnf_continuation() {
+        let grammar = Grammar::parse_bnf("<start> ::= \"a\"\n  \"b\"\n  | \"c\"").unwrap();
+        let start = &grammar.rules[START];
+        assert_eq!(start.len(), 2);
+        assert_eq!(
+            start[0].symbols,
+            vec![
+                Symbol::Terminal(b"a".to_vec()),
+                Symbol::Terminal(b"b".to_vec())
+            ]
+        );
+        assert_eq!(start[1].symbols, vec![Symbol::Terminal(b"c".to_vec())]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = vec![
+            ("<a> ::= \"a\"", "missing `<start>` rule"),
+            ("<start> ::= <a>", "undefined rule `<a>`"),
+            (
+                "<start> ::= <start> \"a\"",
+                "rule `<start>` never derives a finite input",
+            ),
+            (
+                "\"a\"",
+                "invalid grammar on line 1: expected a rule definition",
+            ),
+            (
+                "start ::= \"a\"",
+                "invalid grammar on line 1: expected a rule definition",
+            ),
+            (
+                "<start> ::= \"a",
+                "invalid grammar on line 1: expected a closing quote",
+            ),
+            ("<start> ::= a", "invalid grammar on line 1: unexpected `a`"),
+            (
+                "<start> ::= \"\\q\"",
+                "invalid grammar on line 1: invalid escape",
+            ),
+        ];
+
+        for (text, message) in cases {
+            let err = Grammar::parse_bnf(text).unwrap_err();
+            assert_eq!(err.to_string(), message, "{}", text);
+        }
+    }
+}
@@ -6,10 +6,18 @@
 use crate::{
     asan::{add_asan_log_env, check_asan_path, check_asan_string, AsanLog},
     expand::Expand,
+    exploitable::Exploitability,
+    jvm::JvmLog,
+    panic::PanicLog,
     process::run_cmd,
+    stack::StackFrame,
 };
 use anyhow::{Error, Result};
-use std::{collections::HashMap, path::Path, time::Duration};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use tempfile::tempdir;
 
 const DEFAULT_TIMEOUT_SECS: u64 = 5;
@@ -24,13 +32,55 @@ pub struct Tester<'a> {
     check_asan_stderr: bool,
     check_debugger: bool,
     check_retry_count: u64,
+    expand: Expand<'a>,
+
+    // Only used by the Linux debugger.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    core_dump_dir: Option<&'a Path>,
 }
 
 #[derive(Debug)]
 pub struct Crash {
     pub call_stack: Vec<String>,
+
+    /// Structured frames of the call stack. May include inlined frames and source
+    /// locations that are left out of `call_stack`, so its hash stays stable.
+    pub call_stack_frames: Vec<StackFrame>,
+
     pub crash_type: String,
     pub crash_site: String,
+
+    /// Set if the crash was triaged in a debugger that rates exploitability.
+    pub exploitability: Option<Exploitability>,
+
+    /// Core file of the crashing process, if one was requested and written.
+    pub core_dump: Option<PathBuf>,
+}
+
+impl From<PanicLog> for Crash {
+    fn from(log: PanicLog) -> Self {
+        Self {
+            call_stack: log.call_stack().to_vec(),
+            call_stack_frames: log.call_stack_frames(),
+            crash_type: log.crash_type().to_owned(),
+            crash_site: log.crash_site().to_owned(),
+            exploitability: None,
+            core_dump: None,
+        }
+    }
+}
+
+impl From<JvmLog> for Crash {
+    fn from(log: JvmLog) -> Self {
+        Self {
+            call_stack: log.call_stack().to_vec(),
+            call_stack_frames: log.call_stack_frames(),
+            crash_type: log.crash_type().to_owned(),
+            crash_site: log.crash_site(),
+            exploitability: None,
+            core_dump: None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -50,6 +100,7 @@ impl<'a> Tester<'a> {
         check_asan_stderr: bool,
         check_debugger: bool,
         check_retry_count: u64,
+        expand: Expand<'a>,
     ) -> Self {
         let timeout = Duration::from_secs(timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
         Self {
@@ -61,9 +112,20 @@ impl<'a> Tester<'a> {
             check_asan_stderr,
             check_debugger,
             check_retry_count,
+            expand,
+            core_dump_dir: None,
         }
     }
 
+    /// Write a core file to `dir` for a crash caught by the debugger, if the
+    /// target did not recover from it.
+    ///
+    /// Only supported by the Linux debugger, so this requires `check_debugger`.
+    pub fn core_dump_dir(mut self, dir: &'a Path) -> Self {
+        self.core_dump_dir = Some(dir);
+        self
+    }
+
     #[cfg(target_os = "windows")]
     async fn test_input_debugger(
         &self,
@@ -96,9 +158,12 @@ impl<'a> Tester<'a> {
             let crash_type = exception.description.to_string();
 
             Some(Crash {
+                call_stack_frames: crate::stack::parse_call_stack(&call_stack),
                 call_stack,
                 crash_type,
                 crash_site,
+                exploitability: None,
+                core_dump: None,
             })
         } else {
             bail!("{}", report.exit_status);
@@ -113,9 +178,13 @@ impl<'a> Tester<'a> {
         mut argv: Vec<String>,
         env: HashMap<String, String>,
     ) -> Result<Option<Crash>> {
+        use crate::triage::{ExitStatus, TriageCommand};
+
         argv.insert(0, self.exe_path.display().to_string());
 
         let (sender, receiver) = std::sync::mpsc::channel();
+        let deadline = self.timeout;
+        let core_dump_dir = self.core_dump_dir.map(|dir| dir.to_owned());
 
         // Create two async tasks: one off-thread task for the blocking triage run,
         // and one task that will kill the triage target if we time out.
@@ -123,7 +192,11 @@ impl<'a> Tester<'a> {
             // Spawn a triage run, but stop it before execing.
             //
             // This calls a blocking `wait()` internally, on the forked child.
-            let triage = crate::triage::TriageCommand::new(argv, env)?;
+            let mut triage = TriageCommand::new(argv, env)?;
+            triage.deadline(deadline);
+            if let Some(dir) = core_dump_dir {
+                triage.core_dump_dir(dir);
+            }
 
             // Share the new child ID with main thread.
             sender.send(triage.pid())?;
@@ -134,9 +207,19 @@ impl<'a> Tester<'a> {
 
         // Save the new process ID of the spawned triage target, so we can try to kill
         // the (possibly hung) target out-of-band, if we time out.
-        let target_pid = receiver.recv()?;
+        //
+        // If the sender was dropped, the triage run failed to start. Report why.
+        let target_pid = match receiver.recv() {
+            Ok(pid) => pid,
+            Err(_) => {
+                triage.await??;
+                bail!("triage run exited before spawning target");
+            }
+        };
 
-        let timeout = tokio::time::timeout(self.timeout, triage).await;
+        // The triage run enforces its own deadline, and reports hangs. Only kill the
+        // target from here if that somehow fails.
+        let timeout = tokio::time::timeout(self.timeout * 2, triage).await;
         let crash = if timeout.is_err() {
             use nix::sys::signal::{kill, Signal};
             // Yes. Try to kill the target process, if hung.
@@ -145,7 +228,17 @@ impl<'a> Tester<'a> {
         } else {
             let report = timeout???;
 
-            if let Some(crash) = report.crashes.last() {
+            if report.hung() {
+                bail!("process timed out");
+            }
+
+            // Runtimes like the JVM and Go raise and handle `SIGSEGV` as part of
+            // normal operation. Only report a crash if the target did not recover,
+            // so it either died by the signal, or exited with an error (as it does
+            // when a sanitizer handles the signal).
+            let recovered = matches!(report.exit_status, ExitStatus::Exited(0));
+
+            if let (Some(crash), false) = (report.crashes.last(), recovered) {
                 let crash_thread = crash
                     .threads
                     .get(&crash.tid.as_raw())
@@ -154,13 +247,23 @@ impl<'a> Tester<'a> {
                 let call_stack: Vec<_> = crash_thread
                     .callstack
                     .iter()
+                    .filter(|frame| !frame.inlined)
                     .enumerate()
                     .map(|(idx, frame)| format!("#{} {}", idx, frame))
                     .collect();
 
+                let call_stack_frames = crash_thread
+                    .callstack
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, frame)| frame.to_stack_frame(idx))
+                    .collect();
+
                 let crash_type = crash.signal.to_string();
 
-                let crash_site = if let Some(frame) = crash_thread.callstack.get(0) {
+                let crash_site = if let Some(frame) =
+                    crash_thread.callstack.iter().find(|frame| !frame.inlined)
+                {
                     frame.to_string()
                 } else {
                     CRASH_SITE_UNAVAILABLE.to_owned()
@@ -168,11 +271,14 @@ impl<'a> Tester<'a> {
 
                 Some(Crash {
                     call_stack,
+                    call_stack_frames,
                     crash_type,
                     crash_site,
+                    exploitability: Some(crash.exploitability.clone()),
+                    core_dump: crash.core_dump.clone(),
                 })
             } else {
-                None
+                bail!("{}", report.exit_status);
             }
         };
 
@@ -187,11 +293,12 @@ impl<'a> Tester<'a> {
         };
 
         let (argv, env) = {
-            let mut expand = Expand::new();
+            let mut expand = self.expand.clone();
             expand
                 .input_path(input_file)
                 .target_exe(&self.exe_path)
-                .target_options(&self.arguments);
+                .target_options(&self.arguments)
+                .target_timeout(Some(self.timeout.as_secs()));
 
             let argv = expand.evaluate(&self.arguments)?;
             let mut env: HashMap<String, String> = HashMap::new();
@@ -235,8 +342,19 @@ impl<'a> Tester<'a> {
             };
 
             if asan_log.is_none() && self.check_asan_stderr {
-                if let Some(output) = output {
-                    asan_log = check_asan_string(output.stderr).await?;
+                if let Some(output) = &output {
+                    asan_log = check_asan_string(output.stderr.clone()).await?;
+                }
+            }
+
+            // Rust and Go targets report fatal errors by panicking, and JVM targets
+            // (including Jazzer harnesses) by throwing. Both write a message and
+            // (maybe) a stack trace to stderr before exiting.
+            if crash.is_none() && asan_log.is_none() {
+                if let Some(output) = &output {
+                    if !output.exit_status.success {
+                        crash = check_crash_string(&output.stderr);
+                    }
                 }
             }
 
@@ -257,3 +375,15 @@ impl<'a> Tester<'a> {
         Ok(test_result.crash.is_some() || test_result.asan_log.is_some())
     }
 }
+
+fn check_crash_string(data: &str) -> Option<Crash> {
+    if let Some(log) = PanicLog::parse(data.to_owned()) {
+        return Some(log.into());
+    }
+
+    if let Some(log) = JvmLog::parse(data.to_owned()) {
+        return Some(log.into());
+    }
+
+    None
+}
@@ -0,0 +1,321 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{
+    sha256,
+    stack::{parse_call_stack, StackFrame},
+};
+use regex::Regex;
+
+lazy_static! {
+    // Jazzer: `== Java Exception: java.lang.IllegalStateException: message`
+    // JVM: `Exception in thread "main" java.lang.IllegalStateException: message`
+    static ref EXCEPTION_HEADER: Regex = Regex::new(
+        r#"^(?:== Java Exception: |Exception in thread "[^"]*" )([\w$.]+)(?:: (.*))?$"#
+    )
+    .unwrap();
+    static ref CAUSED_BY: Regex = Regex::new(r"^Caused by: ([\w$.]+)(?:: (.*))?$").unwrap();
+    static ref FRAME: Regex = Regex::new(r"^\s+at (\S+)\((.*)\)$").unwrap();
+    static ref FRAMES_OMITTED: Regex = Regex::new(r"^\s+\.\.\. (\d+) more$").unwrap();
+}
+
+/// A single exception of a JVM stack trace.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JavaException {
+    pub class_name: String,
+    pub message: Option<String>,
+
+    /// Frames as printed, such as `com.example.Parser.parse(Parser.java:12)`.
+    pub frames: Vec<String>,
+
+    /// Count of outer frames shared with the enclosing exception, which the JVM
+    /// elides as `... N more`.
+    pub omitted_frames: usize,
+}
+
+/// Uncaught exception reported by a JVM program or a Jazzer fuzz target.
+///
+/// The `Caused by:` chain is kept in `exceptions`, from the outermost exception
+/// to the root cause. The crash type, site and call stack all describe the root
+/// cause, which is where the faulting code actually threw.
+#[derive(Clone, Debug)]
+pub struct JvmLog {
+    text: String,
+    exceptions: Vec<JavaException>,
+    call_stack: Vec<String>,
+}
+
+impl JvmLog {
+    pub fn parse(text: String) -> Option<Self> {
+        let exceptions = parse_exceptions(&text)?;
+        let call_stack = root_cause_call_stack(&exceptions);
+
+        Some(Self {
+            text,
+            exceptions,
+            call_stack,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn exceptions(&self) -> &[JavaException] {
+        &self.exceptions
+    }
+
+    pub fn root_cause(&self) -> &JavaException {
+        // Never empty, checked in `parse()`.
+        &self.exceptions[self.exceptions.len() - 1]
+    }
+
+    pub fn crash_type(&self) -> &str {
+        &self.root_cause().class_name
+    }
+
+    pub fn crash_site(&self) -> String {
+        let root_cause = self.root_cause();
+        match &root_cause.message {
+            Some(message) => format!("{}: {}", root_cause.class_name, message),
+            None => root_cause.class_name.clone(),
+        }
+    }
+
+    pub fn call_stack(&self) -> &[String] {
+        &self.call_stack
+    }
+
+    pub fn call_stack_frames(&self) -> Vec<StackFrame> {
+        parse_call_stack(&self.call_stack)
+    }
+
+    pub fn call_stack_sha256(&self) -> String {
+        sha256::digest_iter(self.call_stack())
+    }
+}
+
+fn parse_exceptions(text: &str) -> Option<Vec<JavaException>> {
+    let mut lines = text
+        .lines()
+        .skip_while(|line| !EXCEPTION_HEADER.is_match(line));
+
+    let header = EXCEPTION_HEADER.captures(lines.next()?)?;
+    let mut exceptions = vec![new_exception(&header)];
+
+    // Set while reading a `Suppressed:` block, whose frames and causes are
+    // indented, and do not belong to the current exception.
+    let mut suppressed = false;
+
+    for line in lines {
+        if let Some(captures) = CAUSED_BY.captures(line) {
+            exceptions.push(new_exception(&captures));
+            suppressed = false;
+            continue;
+        }
+
+        if line.trim_start().starts_with("Suppressed: ") {
+            suppressed = true;
+            continue;
+        }
+
+        let current = exceptions.last_mut()?;
+
+        if let Some(captures) = FRAME.captures(line) {
+            if !suppressed {
+                current
+                    .frames
+                    .push(format!("{}({})", &captures[1], &captures[2]));
+            }
+            continue;
+        }
+
+        if let Some(captures) = FRAMES_OMITTED.captures(line) {
+            if !suppressed {
+                current.omitted_frames = captures[1].parse().ok()?;
+            }
+            continue;
+        }
+
+        if suppressed && line.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        // Exception messages may span several lines before the first frame.
+        if current.frames.is_empty() && !line.trim().is_empty() {
+            continue;
+        }
+
+        break;
+    }
+
+    Some(exceptions)
+}
+
+fn new_exception(captures: &regex::Captures) -> JavaException {
+    JavaException {
+        class_name: captures[1].to_owned(),
+        message: captures.get(2).map(|m| m.as_str().to_owned()),
+        frames: vec![],
+        omitted_frames: 0,
+    }
+}
+
+// Complete the stack of the root cause with the outer frames that the JVM elided
+// as `... N more`, and format it like a sanitizer call stack:
+//
+//     #0 com.example.Parser.parse Parser.java:12
+fn root_cause_call_stack(exceptions: &[JavaException]) -> Vec<String> {
+    let mut frames: Vec<&str> = vec![];
+
+    for exception in exceptions {
+        let start = frames.len().saturating_sub(exception.omitted_frames);
+        let shared = frames.split_off(start);
+        frames = exception.frames.iter().map(|f| f.as_str()).collect();
+        frames.extend(shared);
+    }
+
+    frames
+        .into_iter()
+        .enumerate()
+        .map(|(index, frame)| format_frame(index, frame))
+        .collect()
+}
+
+fn format_frame(index: usize, frame: &str) -> String {
+    let (method, source) = match frame.rfind('(') {
+        Some(paren) => (&frame[..paren], &frame[paren + 1..frame.len() - 1]),
+        None => (frame, ""),
+    };
+
+    // Drop the class loader and module prefixes of JDK 9+ frames, such as
+    // `app//` or `java.base/`, which vary with the runtime.
+    let method = method.rsplit('/').next().unwrap_or(method);
+
+    // `Parser.java:12`, but not `Native Method` or `Unknown Source`.
+    let has_line = source
+        .rsplit(':')
+        .next()
+        .map(|line| line != source && line.parse::<u64>().is_ok())
+        .unwrap_or(false);
+
+    if has_line {
+        format!("#{} {} {}", index, method, source)
+    } else {
+        format!("#{} {}", index, method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jazzer_exception() {
+        let data = std::fs::read_to_string("data/jazzer-exception.txt").unwrap();
+        let log = JvmLog::parse(data).unwrap();
+
+        assert_eq!(log.exceptions().len(), 1);
+        assert_eq!(log.crash_type(), "java.lang.ArrayIndexOutOfBoundsException");
+        assert_eq!(
+            log.crash_site(),
+            "java.lang.ArrayIndexOutOfBoundsException: Index 5 out of bounds for length 3"
+        );
+        assert_eq!(log.call_stack().len(), 4);
+        assert_eq!(
+            log.call_stack()[0],
+            "#0 com.example.Parser.readField Parser.java:41"
+        );
+
+        let frames = log.call_stack_frames();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(
+            frames[0].function_name.as_deref(),
+            Some("com.example.Parser.readField")
+        );
+        assert_eq!(frames[0].source_file_path.as_deref(), Some("Parser.java"));
+        assert_eq!(frames[0].source_file_line, Some(41));
+    }
+
+    #[test]
+    fn test_jazzer_caused_by() {
+        let data = std::fs::read_to_string("data/jazzer-caused-by.txt").unwrap();
+        let log = JvmLog::parse(data).unwrap();
+
+        let class_names: Vec<_> = log
+            .exceptions()
+            .iter()
+            .map(|e| e.class_name.as_str())
+            .collect();
+        assert_eq!(
+            class_names,
+            vec![
+                "java.lang.RuntimeException",
+                "java.io.UncheckedIOException",
+                "java.lang.NumberFormatException",
+            ]
+        );
+        assert_eq!(log.exceptions()[1].omitted_frames, 2);
+        assert_eq!(log.exceptions()[2].omitted_frames, 4);
+
+        assert_eq!(log.crash_type(), "java.lang.NumberFormatException");
+        assert_eq!(
+            log.crash_site(),
+            "java.lang.NumberFormatException: For input string: \"x\""
+        );
+
+        // Root cause frames, then the elided outer frames.
+        let expected = vec![
+            "#0 java.lang.NumberFormatException.forInputString NumberFormatException.java:67",
+            "#1 java.lang.Integer.parseInt Integer.java:652",
+            "#2 java.lang.Integer.parseInt Integer.java:770",
+            "#3 com.example.Config.readPort Config.java:30",
+            "#4 com.example.Config.load Config.java:22",
+            "#5 com.example.Config.parse Config.java:15",
+            "#6 com.example.ConfigFuzzer.fuzzerTestOneInput ConfigFuzzer.java:9",
+            "#7 com.code_intelligence.jazzer.driver.FuzzTargetRunner.runOne FuzzTargetRunner.java:227",
+        ];
+        assert_eq!(log.call_stack(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_jvm_uncaught_exception() {
+        let data = std::fs::read_to_string("data/jvm-uncaught-exception.txt").unwrap();
+        let log = JvmLog::parse(data).unwrap();
+
+        assert_eq!(log.crash_type(), "java.lang.NullPointerException");
+        assert_eq!(log.crash_site(), "java.lang.NullPointerException");
+        assert_eq!(
+            log.call_stack(),
+            &[
+                "#0 com.example.Tree.insert Tree.java:88".to_owned(),
+                "#1 com.example.Main.main Main.java:7".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_frame() {
+        assert_eq!(
+            format_frame(0, "java.base/java.util.ArrayList.get(ArrayList.java:427)"),
+            "#0 java.util.ArrayList.get ArrayList.java:427"
+        );
+        assert_eq!(
+            format_frame(
+                1,
+                "jdk.internal.reflect.NativeMethodAccessorImpl.invoke0(Native Method)"
+            ),
+            "#1 jdk.internal.reflect.NativeMethodAccessorImpl.invoke0"
+        );
+        assert_eq!(
+            format_frame(2, "com.example.Gen.lambda$run$0(Unknown Source)"),
+            "#2 com.example.Gen.lambda$run$0"
+        );
+    }
+
+    #[test]
+    fn test_not_a_jvm_log() {
+        let data = std::fs::read_to_string("data/libfuzzer-asan-log.txt").unwrap();
+        assert!(JvmLog::parse(data).is_none());
+    }
+}
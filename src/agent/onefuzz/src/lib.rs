@@ -16,21 +16,35 @@ pub mod telemetry;
 pub mod asan;
 pub mod az_copy;
 pub mod blob;
+pub mod dedup;
+pub mod dictionary;
 pub mod expand;
+pub mod exploitable;
 pub mod fs;
+pub mod grammar;
 pub mod heartbeat;
 pub mod http;
 pub mod input_tester;
 pub mod jitter;
+pub mod jvm;
 pub mod libfuzzer;
 pub mod machine_id;
+pub mod minimize;
 pub mod monitor;
+pub mod mutator;
+pub mod panic;
 pub mod process;
 pub mod sha256;
+pub mod stack;
 pub mod syncdir;
 pub mod system;
+pub mod template;
 pub mod utils;
 
+#[cfg(target_os = "linux")]
+pub mod coredump;
+#[cfg(target_os = "linux")]
+pub mod dwarf;
 #[cfg(target_os = "linux")]
 pub mod triage;
 pub mod uploader;
@@ -4,27 +4,120 @@
 use crate::{
     expand::Expand,
     input_tester::{TestResult, Tester},
+    process::{ExitStatus, Output},
 };
 use anyhow::Result;
 use std::{
     collections::HashMap,
     ffi::OsString,
+    fmt,
     path::{Path, PathBuf},
     process::Stdio,
+    time::Duration,
 };
+use tempfile::tempdir;
 use tokio::process::{Child, Command};
 
 const DEFAULT_MAX_TOTAL_SECONDS: i32 = 10 * 60;
 
+// Time allowed for each of the runs of the preflight check.
+const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Printed by libFuzzer for `-help=1`.
+const HELP_MARKER: &str = "Flags: (strictly in form -flag=value)";
+
+// `STATUS_DLL_NOT_FOUND`, the exit code of a process missing a DLL on Windows.
+const STATUS_DLL_NOT_FOUND: i32 = 0xC000_0135_u32 as i32;
+
 pub struct LibFuzzerMergeOutput {
     pub added_files_count: i32,
     pub added_feature_count: i32,
 }
 
+/// Kind of a file libFuzzer writes to its `-artifact_prefix`, by file name
+/// prefix.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArtifactType {
+    Crash,
+    Oom,
+    Timeout,
+    Leak,
+    SlowUnit,
+}
+
+impl ArtifactType {
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let artifact = if name.starts_with("crash-") {
+            Self::Crash
+        } else if name.starts_with("oom-") {
+            Self::Oom
+        } else if name.starts_with("timeout-") {
+            Self::Timeout
+        } else if name.starts_with("leak-") {
+            Self::Leak
+        } else if name.starts_with("slow-unit-") {
+            Self::SlowUnit
+        } else {
+            return None;
+        };
+
+        Some(artifact)
+    }
+}
+
+/// Progress of a merge, per its `-merge_control_file`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LibFuzzerMergeProgress {
+    /// Inputs whose features have been collected.
+    pub processed: u64,
+
+    /// Inputs in the merge, including those of the initial corpus.
+    pub total: u64,
+}
+
+impl LibFuzzerMergeProgress {
+    /// Parse a merge control file, which starts with the number of inputs and
+    /// the number of them in the initial corpus, followed by one path per
+    /// input, then records the inputs as they are processed:
+    ///
+    /// ```text
+    /// 3
+    /// 1
+    /// corpus/a
+    /// tmp/b
+    /// tmp/c
+    /// STARTED 0 10
+    /// FT 0 1 2 3
+    /// COV 0 4 5
+    /// STARTED 1 20
+    /// ```
+    ///
+    /// Returns `None` if the file is incomplete, such as when libFuzzer is
+    /// still writing its header.
+    pub fn parse(control: &str) -> Option<Self> {
+        let mut lines = control.lines();
+        let total = lines.next()?.trim().parse().ok()?;
+
+        // Older versions of libFuzzer record `DONE` instead of `FT`.
+        let processed = lines
+            .filter(|line| line.starts_with("FT ") || line.starts_with("DONE "))
+            .count() as u64;
+
+        Some(Self { processed, total })
+    }
+
+    pub async fn read(control_file: impl AsRef<Path>) -> Result<Option<Self>> {
+        let control = tokio::fs::read_to_string(control_file).await?;
+        Ok(Self::parse(&control))
+    }
+}
+
 pub struct LibFuzzer<'a> {
     exe: PathBuf,
     options: &'a [String],
     env: &'a HashMap<String, String>,
+    dict: Option<PathBuf>,
+    expand: Expand<'a>,
 }
 
 impl<'a> LibFuzzer<'a> {
@@ -32,14 +125,24 @@ impl<'a> LibFuzzer<'a> {
         exe: impl Into<PathBuf>,
         options: &'a [String],
         env: &'a HashMap<String, String>,
+        expand: Expand<'a>,
     ) -> Self {
         Self {
             exe: exe.into(),
             options,
             env,
+            dict: None,
+            expand,
         }
     }
 
+    /// Fuzz with the dictionary at `path`, unless the options already set one
+    /// with `-dict`.
+    pub fn dict(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dict = Some(path.into());
+        self
+    }
+
     pub fn fuzz(
         &self,
         fault_dir: impl AsRef<Path>,
@@ -49,7 +152,7 @@ impl<'a> LibFuzzer<'a> {
         let corpus_dir = corpus_dir.as_ref();
         let fault_dir = fault_dir.as_ref();
 
-        let mut expand = Expand::new();
+        let mut expand = self.expand.clone();
         expand
             .target_exe(&self.exe)
             .target_options(&self.options)
@@ -81,6 +184,12 @@ impl<'a> LibFuzzer<'a> {
             cmd.arg(format!("-max_total_time={}", DEFAULT_MAX_TOTAL_SECONDS));
         }
 
+        if let Some(dict) = &self.dict {
+            if !self.options.iter().any(|o| o.starts_with("-dict=")) {
+                cmd.arg(format!("-dict={}", dict.display()));
+            }
+        }
+
         // When writing a new faulting input, the libFuzzer runtime _exactly_
         // prepends the value of `-artifact_prefix` to the new file name. To
         // specify that a new file `crash-<digest>` should be written to a
@@ -116,17 +225,177 @@ impl<'a> LibFuzzer<'a> {
         options.push("{input}".to_string());
 
         let tester = Tester::new(
-            &self.exe, &options, &self.env, &timeout, false, true, false, retry,
+            &self.exe,
+            &options,
+            self.env,
+            &timeout,
+            false,
+            true,
+            false,
+            retry,
+            self.expand.clone(),
         );
         tester.test_input(test_input.as_ref()).await
     }
 
+    /// Minimize a crashing input with `-minimize_crash=1`, for at most
+    /// `max_total_time` seconds, writing the smallest crashing input found to
+    /// `output`.
+    ///
+    /// Returns `false` if libFuzzer found no smaller input with the same crash.
+    pub async fn minimize_crash(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+        max_total_time: u64,
+    ) -> Result<bool> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+
+        let mut expand = self.expand.clone();
+        expand
+            .target_exe(&self.exe)
+            .target_options(self.options)
+            .input_path(input);
+
+        let mut cmd = Command::new(&self.exe);
+        cmd.kill_on_drop(true)
+            .env_remove("RUST_LOG")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .arg("-minimize_crash=1")
+            .arg(format!("-max_total_time={}", max_total_time))
+            .arg(format!("-exact_artifact_path={}", output.display()));
+
+        // Set the environment.
+        for (k, v) in self.env {
+            cmd.env(k, expand.evaluate_value(v)?);
+        }
+
+        // Pass custom option arguments.
+        for o in expand.evaluate(self.options)? {
+            cmd.arg(o);
+        }
+
+        cmd.arg(input);
+
+        let result = cmd.spawn()?.wait_with_output().await?;
+        if !result.status.success() {
+            let status: ExitStatus = result.status.into();
+            bail!(
+                "libfuzzer failed to minimize crash.  status:{} stderr:{:?}",
+                serde_json::to_string(&status)?,
+                String::from_utf8_lossy(&result.stderr)
+            );
+        }
+
+        Ok(tokio::fs::metadata(output).await.is_ok())
+    }
+
+    /// Check that the target can be fuzzed, before starting any workers.
+    ///
+    /// The target is run with `-help=1`, to check that it starts and is a
+    /// libFuzzer target, then with `-runs=0` on an empty corpus, to check that
+    /// it can run the empty input. A failed check is returned as a
+    /// `PreflightError`.
+    pub async fn preflight(&self) -> Result<()> {
+        let corpus_dir = tempdir()?;
+
+        let help = self.preflight_run(&["-help=1".into()]).await?;
+        if let Err(err) = check_help(&help) {
+            return Err(self.resolve_missing_libraries(err).await.into());
+        }
+
+        let mut expand = self.expand.clone();
+        expand
+            .target_exe(&self.exe)
+            .target_options(self.options)
+            .input_corpus(corpus_dir.path());
+
+        let mut args = expand.evaluate(self.options)?;
+        args.push("-runs=0".into());
+        args.push(corpus_dir.path().display().to_string());
+
+        let run = self.preflight_run(&args).await?;
+        check_run(&run)?;
+
+        Ok(())
+    }
+
+    async fn preflight_run(&self, args: &[String]) -> Result<Output> {
+        let mut expand = self.expand.clone();
+        expand.target_exe(&self.exe);
+
+        let mut cmd = Command::new(&self.exe);
+        cmd.kill_on_drop(true)
+            .env_remove("RUST_LOG")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .args(args);
+
+        for (k, v) in self.env {
+            cmd.env(k, expand.evaluate_value(v)?);
+        }
+
+        let child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(PreflightError::MissingTarget(self.exe.display().to_string()).into());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        match tokio::time::timeout(PREFLIGHT_TIMEOUT, child.wait_with_output()).await {
+            Ok(output) => Ok(output?.into()),
+            Err(_) => Err(PreflightError::Timeout(args.join(" ")).into()),
+        }
+    }
+
+    // The loader only reports the first missing library, so on Linux, ask
+    // `ldd` for all of them.
+    async fn resolve_missing_libraries(&self, err: PreflightError) -> PreflightError {
+        if !cfg!(target_os = "linux") || !matches!(err, PreflightError::MissingLibraries(_)) {
+            return err;
+        }
+
+        let mut expand = self.expand.clone();
+        expand.target_exe(&self.exe);
+
+        let mut cmd = Command::new("ldd");
+        cmd.arg(&self.exe).stdin(Stdio::null());
+        for (k, v) in self.env {
+            if let Ok(v) = expand.evaluate_value(v) {
+                cmd.env(k, v);
+            }
+        }
+
+        match cmd.output().await {
+            Ok(output) => {
+                let missing = parse_ldd_missing(&String::from_utf8_lossy(&output.stdout));
+                if missing.is_empty() {
+                    err
+                } else {
+                    PreflightError::MissingLibraries(missing)
+                }
+            }
+            Err(_) => err,
+        }
+    }
+
+    /// Merge `corpus_dirs` into `corpus_dir`.
+    ///
+    /// With a `control_file`, libFuzzer records its progress there, and resumes
+    /// from it if it is run again with the same inputs, such as after the VM was
+    /// preempted.
     pub async fn merge(
         &self,
         corpus_dir: impl AsRef<Path>,
         corpus_dirs: &[impl AsRef<Path>],
+        control_file: Option<&Path>,
     ) -> Result<LibFuzzerMergeOutput> {
-        let mut expand = Expand::new();
+        let mut expand = self.expand.clone();
         expand
             .target_exe(&self.exe)
             .target_options(&self.options)
@@ -138,8 +407,13 @@ impl<'a> LibFuzzer<'a> {
             .env_remove("RUST_LOG")
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .arg("-merge=1")
-            .arg(corpus_dir.as_ref());
+            .arg("-merge=1");
+
+        if let Some(control_file) = control_file {
+            cmd.arg(format!("-merge_control_file={}", control_file.display()));
+        }
+
+        cmd.arg(corpus_dir.as_ref());
 
         for dir in corpus_dirs {
             cmd.arg(dir.as_ref());
@@ -177,48 +451,493 @@ impl<'a> LibFuzzer<'a> {
     }
 }
 
+/// Why a target failed the preflight check of `LibFuzzer::preflight`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PreflightError {
+    /// The target executable does not exist.
+    MissingTarget(String),
+
+    /// The target could not be loaded, because of missing shared libraries.
+    /// The names are unknown on Windows.
+    MissingLibraries(Vec<String>),
+
+    /// The target did not print libFuzzer's usage for `-help=1`.
+    NotLibFuzzer(String),
+
+    /// The target crashed, running the empty input.
+    Crashed(String),
+
+    /// The target exited with an error, without crashing.
+    Failed(Option<i32>, String),
+
+    /// The target ran longer than the preflight check allows, with the given
+    /// arguments.
+    Timeout(String),
+}
+
+impl PreflightError {
+    /// A short name for the kind of failure, for telemetry.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::MissingTarget(_) => "missing_target",
+            Self::MissingLibraries(_) => "missing_libraries",
+            Self::NotLibFuzzer(_) => "not_libfuzzer",
+            Self::Crashed(_) => "crashed",
+            Self::Failed(..) => "failed",
+            Self::Timeout(_) => "timeout",
+        }
+    }
+}
+
+impl fmt::Display for PreflightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingTarget(exe) => write!(f, "target does not exist: {}", exe),
+            Self::MissingLibraries(libraries) if libraries.is_empty() => {
+                write!(f, "target is missing shared libraries")
+            }
+            Self::MissingLibraries(libraries) => write!(
+                f,
+                "target is missing shared libraries: {}",
+                libraries.join(", ")
+            ),
+            Self::NotLibFuzzer(stderr) => {
+                write!(f, "target is not a libFuzzer target. stderr: {:?}", stderr)
+            }
+            Self::Crashed(stderr) => write!(
+                f,
+                "target crashed running an empty input. stderr: {:?}",
+                stderr
+            ),
+            Self::Failed(code, stderr) => write!(
+                f,
+                "target exited with an error. code: {:?} stderr: {:?}",
+                code, stderr
+            ),
+            Self::Timeout(args) => write!(
+                f,
+                "target timed out after {}s with arguments: {}",
+                PREFLIGHT_TIMEOUT.as_secs(),
+                args
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PreflightError {}
+
+// Classify the output of the target for `-help=1`.
+fn check_help(output: &Output) -> Result<(), PreflightError> {
+    if let Some(library) = parse_loader_error(&output.stderr) {
+        return Err(PreflightError::MissingLibraries(vec![library]));
+    }
+
+    if output.exit_status.code == Some(STATUS_DLL_NOT_FOUND) {
+        return Err(PreflightError::MissingLibraries(vec![]));
+    }
+
+    if !output.stderr.contains(HELP_MARKER) {
+        return Err(PreflightError::NotLibFuzzer(tail(&output.stderr)));
+    }
+
+    Ok(())
+}
+
+// Classify the output of the target for `-runs=0` on an empty corpus.
+fn check_run(output: &Output) -> Result<(), PreflightError> {
+    if output.exit_status.success {
+        return Ok(());
+    }
+
+    let crashed = output.exit_status.signal.is_some()
+        || output.stderr.contains("==ERROR:")
+        || output.stderr.contains("deadly signal");
+
+    if crashed {
+        Err(PreflightError::Crashed(tail(&output.stderr)))
+    } else {
+        Err(PreflightError::Failed(
+            output.exit_status.code,
+            tail(&output.stderr),
+        ))
+    }
+}
+
+// Name of the missing library in an error of the Linux loader, like:
+//
+//   ./fuzz: error while loading shared libraries: libfoo.so.1: cannot open
+//   shared object file: No such file or directory
+fn parse_loader_error(stderr: &str) -> Option<String> {
+    let marker = "error while loading shared libraries: ";
+    let start = stderr.find(marker)? + marker.len();
+    let rest = &stderr[start..];
+    let end = rest.find(':')?;
+    Some(rest[..end].to_owned())
+}
+
+// Names of the libraries `ldd` could not find, from lines like:
+//
+//   libfoo.so.1 => not found
+fn parse_ldd_missing(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let name = line.strip_suffix("=> not found")?;
+            Some(name.trim().to_owned())
+        })
+        .collect()
+}
+
+// The last lines of the output, which have the error, for context.
+fn tail(output: &str) -> String {
+    const LINES: usize = 10;
+
+    let lines: Vec<_> = output.lines().collect();
+    let start = lines.len().saturating_sub(LINES);
+    lines[start..].join("\n")
+}
+
+lazy_static! {
+    static ref STATUS_LINE: regex::Regex =
+        regex::Regex::new(r"^#(\d+)\s+(INITED|NEW|REDUCE|RELOAD|pulse|DONE)\s+(.*)$").unwrap();
+    static ref NEW_FUNC_LINE: regex::Regex =
+        regex::Regex::new(r"^\s*NEW_FUNC\[(\d+)/(\d+)\]: (0x[0-9a-fA-F]+)(?: in (.*))?$").unwrap();
+}
+
+/// The event that caused libFuzzer to log a status line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LibFuzzerEvent {
+    Inited,
+    New,
+    Reduce,
+    Reload,
+    Pulse,
+    Done,
+}
+
+impl LibFuzzerEvent {
+    fn parse(event: &str) -> Option<Self> {
+        let event = match event {
+            "INITED" => Self::Inited,
+            "NEW" => Self::New,
+            "REDUCE" => Self::Reduce,
+            "RELOAD" => Self::Reload,
+            "pulse" => Self::Pulse,
+            "DONE" => Self::Done,
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+/// A libFuzzer status line, such as:
+///
+/// ```text
+/// #2097152        pulse  cov: 11 ft: 11 corp: 6/21b lim: 4096 exec/s: 699050 rss: 562Mb
+/// ```
+///
+/// Fields other than the iteration count and `exec/s` are not logged by every
+/// libFuzzer version or configuration, so they are optional.
+#[derive(Clone, Debug)]
 pub struct LibFuzzerLine {
     _line: String,
     iters: u64,
+    event: LibFuzzerEvent,
     execs_sec: f64,
+    coverage: Option<u64>,
+    features: Option<u64>,
+    corpus_units: Option<u64>,
+    corpus_size: Option<u64>,
+    max_len: Option<u64>,
+    rss_mb: Option<u64>,
 }
 
 impl LibFuzzerLine {
-    pub fn new(line: String, iters: u64, execs_sec: f64) -> Self {
-        Self {
-            iters,
-            _line: line,
-            execs_sec,
-        }
-    }
-
     pub fn parse(line: &str) -> Result<Option<Self>> {
-        let re = regex::Regex::new(r"#(\d+)\s*(?:pulse|INITED|NEW|REDUCE).*exec/s: (\d+)")?;
-
-        let caps = match re.captures(line) {
+        let caps = match STATUS_LINE.captures(line.trim_end()) {
             Some(caps) => caps,
             None => return Ok(None),
         };
 
         let iters = caps[1].parse()?;
-        let execs_sec = caps[2].parse()?;
+        let event = LibFuzzerEvent::parse(&caps[2])
+            .ok_or_else(|| format_err!("unknown libFuzzer event: {}", &caps[2]))?;
+
+        let mut execs_sec = None;
+        let mut coverage = None;
+        let mut features = None;
+        let mut corpus_units = None;
+        let mut corpus_size = None;
+        let mut max_len = None;
+        let mut rss_mb = None;
+
+        // The rest of the line is a list of `key: value` pairs. Ignore the keys
+        // we don't track, such as `L:` and `MS:`, which describe the input.
+        let mut fields = caps[3].split_whitespace();
+        while let Some(key) = fields.next() {
+            let value = match fields.next() {
+                Some(value) => value,
+                None => break,
+            };
 
-        Ok(Some(Self::new(line.to_string(), iters, execs_sec)))
+            match key {
+                "cov:" => coverage = Some(value.parse()?),
+                "ft:" => features = Some(value.parse()?),
+                "lim:" => max_len = Some(value.parse()?),
+                "exec/s:" => execs_sec = Some(value.parse()?),
+                "rss:" => rss_mb = Some(parse_size(value)? >> 20),
+                "corp:" => {
+                    let mut parts = value.splitn(2, '/');
+                    let units = parts.next().unwrap_or_default();
+                    let size = parts
+                        .next()
+                        .ok_or_else(|| format_err!("invalid corpus size: {}", value))?;
+                    corpus_units = Some(units.parse()?);
+                    corpus_size = Some(parse_size(size)?);
+                }
+                _ => {}
+            }
+        }
+
+        // Lines without an exec rate, like the `INITED` line of very short runs,
+        // aren't useful as runtime stats.
+        let execs_sec = match execs_sec {
+            Some(execs_sec) => execs_sec,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            _line: line.to_string(),
+            iters,
+            event,
+            execs_sec,
+            coverage,
+            features,
+            corpus_units,
+            corpus_size,
+            max_len,
+            rss_mb,
+        }))
     }
 
     pub fn iters(&self) -> u64 {
         self.iters
     }
 
+    pub fn event(&self) -> LibFuzzerEvent {
+        self.event
+    }
+
     pub fn execs_sec(&self) -> f64 {
         self.execs_sec
     }
+
+    /// Number of covered code blocks or edges (`cov:`).
+    pub fn coverage(&self) -> Option<u64> {
+        self.coverage
+    }
+
+    /// Number of coverage features (`ft:`).
+    pub fn features(&self) -> Option<u64> {
+        self.features
+    }
+
+    /// Number of inputs in the in-memory corpus (`corp: N/...`).
+    pub fn corpus_units(&self) -> Option<u64> {
+        self.corpus_units
+    }
+
+    /// Total size of the in-memory corpus, in bytes (`corp: .../Nb`).
+    ///
+    /// libFuzzer rounds this down to the unit it logs, `Kb` or `Mb`, for larger
+    /// corpora.
+    pub fn corpus_size(&self) -> Option<u64> {
+        self.corpus_size
+    }
+
+    /// Current input length limit (`lim:`).
+    pub fn max_len(&self) -> Option<u64> {
+        self.max_len
+    }
+
+    /// Peak resident set size, in megabytes (`rss:`).
+    pub fn rss_mb(&self) -> Option<u64> {
+        self.rss_mb
+    }
+}
+
+/// A line logged by libFuzzer for each function first covered by a new input,
+/// with `-print_funcs` (on by default), such as:
+///
+/// ```text
+/// NEW_FUNC[1/2]: 0x4f8a20 in LLVMFuzzerTestOneInput /src/fuzz.c:10
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LibFuzzerNewFunc {
+    pub index: u64,
+    pub count: u64,
+    pub address: u64,
+    pub location: Option<String>,
+}
+
+impl LibFuzzerNewFunc {
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let caps = match NEW_FUNC_LINE.captures(line.trim_end()) {
+            Some(caps) => caps,
+            None => return Ok(None),
+        };
+
+        let index = caps[1].parse()?;
+        let count = caps[2].parse()?;
+        let address = u64::from_str_radix(&caps[3][2..], 16)?;
+        let location = caps.get(4).map(|m| m.as_str().to_owned());
+
+        Ok(Some(Self {
+            index,
+            count,
+            address,
+            location,
+        }))
+    }
+}
+
+// Parse a size as logged by libFuzzer, such as `21b`, `4Kb` or `562Mb`.
+fn parse_size(value: &str) -> Result<u64> {
+    let (digits, shift) = if let Some(digits) = value.strip_suffix("Mb") {
+        (digits, 20)
+    } else if let Some(digits) = value.strip_suffix("Kb") {
+        (digits, 10)
+    } else if let Some(digits) = value.strip_suffix('b') {
+        (digits, 0)
+    } else {
+        bail!("invalid size: {}", value);
+    };
+
+    let size: u64 = digits.parse()?;
+    Ok(size << shift)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn output(code: Option<i32>, signal: Option<i32>, stderr: &str) -> Output {
+        Output {
+            exit_status: ExitStatus {
+                code,
+                signal,
+                success: code == Some(0),
+            },
+            stderr: stderr.to_owned(),
+            stdout: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_preflight_check_help() {
+        let help = "Usage:\n\nTo run fuzzing pass 0 or more directories.\nFlags: (strictly in form -flag=value)\n verbosity 1";
+        assert_eq!(check_help(&output(Some(0), None, help)), Ok(()));
+
+        let loader = "./fuzz: error while loading shared libraries: libfoo.so.1: cannot open shared object file: No such file or directory";
+        assert_eq!(
+            check_help(&output(Some(127), None, loader)),
+            Err(PreflightError::MissingLibraries(vec!["libfoo.so.1".into()]))
+        );
+
+        assert_eq!(
+            check_help(&output(Some(STATUS_DLL_NOT_FOUND), None, "")),
+            Err(PreflightError::MissingLibraries(vec![]))
+        );
+
+        assert_eq!(
+            check_help(&output(Some(1), None, "usage: fuzz <file>")),
+            Err(PreflightError::NotLibFuzzer("usage: fuzz <file>".into()))
+        );
+    }
+
+    #[test]
+    fn test_preflight_check_run() {
+        assert_eq!(check_run(&output(Some(0), None, "#2\tINITED")), Ok(()));
+
+        let asan = "==1==ERROR: AddressSanitizer: heap-buffer-overflow";
+        assert_eq!(
+            check_run(&output(Some(1), None, asan)),
+            Err(PreflightError::Crashed(asan.into()))
+        );
+
+        assert_eq!(
+            check_run(&output(None, Some(11), "")),
+            Err(PreflightError::Crashed("".into()))
+        );
+
+        assert_eq!(
+            check_run(&output(Some(1), None, "INFO: -runs=0")),
+            Err(PreflightError::Failed(Some(1), "INFO: -runs=0".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_ldd_missing() {
+        let stdout = "\tlinux-vdso.so.1 (0x00007ffc)\n\tlibfoo.so.1 => not found\n\tlibc.so.6 => /lib/x86_64-linux-gnu/libc.so.6 (0x00007f)\n\tlibbar.so => not found\n";
+        assert_eq!(parse_ldd_missing(stdout), vec!["libfoo.so.1", "libbar.so"]);
+    }
+
+    #[test]
+    fn test_preflight_tail() {
+        let stderr: Vec<_> = (0..20).map(|i| i.to_string()).collect();
+        assert_eq!(tail(&stderr.join("\n")), stderr[10..].join("\n"));
+        assert_eq!(tail("a\nb"), "a\nb");
+    }
+
+    #[test]
+    fn test_artifact_type() {
+        let cases = vec![
+            ("crash-da39a3ee", Some(ArtifactType::Crash)),
+            ("oom-da39a3ee", Some(ArtifactType::Oom)),
+            ("timeout-da39a3ee", Some(ArtifactType::Timeout)),
+            ("leak-da39a3ee", Some(ArtifactType::Leak)),
+            ("slow-unit-da39a3ee", Some(ArtifactType::SlowUnit)),
+            ("da39a3ee", None),
+            ("minimized-from-da39a3ee", None),
+        ];
+
+        for (name, artifact) in cases {
+            assert_eq!(ArtifactType::from_file_name(name), artifact, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_merge_progress() {
+        let control =
+            "3\n1\ncorpus/a\ntmp/b\ntmp/c\nSTARTED 0 10\nFT 0 1 2 3\nCOV 0 4 5\nSTARTED 1 20\n";
+        assert_eq!(
+            LibFuzzerMergeProgress::parse(control),
+            Some(LibFuzzerMergeProgress {
+                processed: 1,
+                total: 3
+            })
+        );
+
+        let control = "2\n0\na\nb\nSTARTED 0 1\nDONE 0 1\nSTARTED 1 1\nDONE 1 1\n";
+        assert_eq!(
+            LibFuzzerMergeProgress::parse(control),
+            Some(LibFuzzerMergeProgress {
+                processed: 2,
+                total: 2
+            })
+        );
+
+        assert_eq!(LibFuzzerMergeProgress::parse(""), None);
+        assert_eq!(
+            LibFuzzerMergeProgress::parse("3"),
+            Some(LibFuzzerMergeProgress {
+                processed: 0,
+                total: 3
+            })
+        );
+    }
+
     #[test]
     fn test_libfuzzer_line_pulse() {
         let line = r"#2097152        pulse  cov: 11 ft: 11 corp: 6/21b lim: 4096 exec/s: 699050 rss: 562Mb".into();
@@ -228,6 +947,96 @@ mod tests {
             .expect("no captures");
 
         assert_eq!(parsed.iters(), 2097152);
+        assert_eq!(parsed.event(), LibFuzzerEvent::Pulse);
         assert_eq!(parsed.execs_sec(), 699050.0);
+        assert_eq!(parsed.coverage(), Some(11));
+        assert_eq!(parsed.features(), Some(11));
+        assert_eq!(parsed.corpus_units(), Some(6));
+        assert_eq!(parsed.corpus_size(), Some(21));
+        assert_eq!(parsed.max_len(), Some(4096));
+        assert_eq!(parsed.rss_mb(), Some(562));
+    }
+
+    #[test]
+    fn test_libfuzzer_line_new() {
+        let line = "#1503\tNEW    cov: 130 ft: 271 corp: 94/17Kb lim: 43 exec/s: 751 rss: 30Mb L: 41/43 MS: 2 ChangeBit-InsertByte-";
+
+        let parsed = LibFuzzerLine::parse(line)
+            .expect("parse error")
+            .expect("no captures");
+
+        assert_eq!(parsed.iters(), 1503);
+        assert_eq!(parsed.event(), LibFuzzerEvent::New);
+        assert_eq!(parsed.coverage(), Some(130));
+        assert_eq!(parsed.features(), Some(271));
+        assert_eq!(parsed.corpus_units(), Some(94));
+        assert_eq!(parsed.corpus_size(), Some(17 << 10));
+        assert_eq!(parsed.max_len(), Some(43));
+        assert_eq!(parsed.rss_mb(), Some(30));
+    }
+
+    #[test]
+    fn test_libfuzzer_line_done() {
+        let line = "#1000000\tDONE   cov: 12 ft: 13 corp: 7/2Mb exec/s: 500000 rss: 1Mb";
+
+        let parsed = LibFuzzerLine::parse(line)
+            .expect("parse error")
+            .expect("no captures");
+
+        assert_eq!(parsed.event(), LibFuzzerEvent::Done);
+        assert_eq!(parsed.corpus_size(), Some(2 << 20));
+        assert_eq!(parsed.max_len(), None);
+        assert_eq!(parsed.execs_sec(), 500000.0);
+    }
+
+    #[test]
+    fn test_libfuzzer_line_ignored() {
+        let lines = vec![
+            "INFO: Seed: 3338750330",
+            "#0\tREAD units: 1",
+            "Done 1000000 runs in 2 second(s)",
+            "\tNEW_FUNC[1/1]: 0x4f8a20 in LLVMFuzzerTestOneInput /src/fuzz.c:10",
+        ];
+
+        for line in lines {
+            assert!(LibFuzzerLine::parse(line).unwrap().is_none(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_libfuzzer_new_func() {
+        let line = "\tNEW_FUNC[1/2]: 0x4f8a20 in LLVMFuzzerTestOneInput /src/fuzz.c:10";
+
+        let parsed = LibFuzzerNewFunc::parse(line)
+            .expect("parse error")
+            .expect("no captures");
+
+        assert_eq!(
+            parsed,
+            LibFuzzerNewFunc {
+                index: 1,
+                count: 2,
+                address: 0x4f8a20,
+                location: Some("LLVMFuzzerTestOneInput /src/fuzz.c:10".to_owned()),
+            }
+        );
+
+        let parsed = LibFuzzerNewFunc::parse("NEW_FUNC[2/2]: 0x10")
+            .expect("parse error")
+            .expect("no captures");
+        assert_eq!(parsed.address, 0x10);
+        assert_eq!(parsed.location, None);
+
+        let line = "#1503\tNEW    cov: 130 ft: 271 corp: 94/17Kb exec/s: 751 rss: 30Mb";
+        assert!(LibFuzzerNewFunc::parse(line).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("21b").unwrap(), 21);
+        assert_eq!(parse_size("4Kb").unwrap(), 4 << 10);
+        assert_eq!(parse_size("562Mb").unwrap(), 562 << 20);
+        assert!(parse_size("562").is_err());
+        assert!(parse_size("Mb").is_err());
     }
 }
@@ -0,0 +1,165 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+/// Minimizes an input by delta debugging: removing ever smaller chunks of it,
+/// while the result is still interesting, such as when it still crashes the
+/// same way.
+///
+/// The caller drives the search, since testing a candidate means running the
+/// target:
+///
+/// ```
+/// # use onefuzz::minimize::DeltaDebugger;
+/// let mut dd = DeltaDebugger::new(b"xxbugxx".to_vec());
+/// while let Some(candidate) = dd.next_candidate() {
+///     let interesting = candidate.windows(3).any(|w| w == b"bug");
+///     dd.report(interesting);
+/// }
+/// assert_eq!(dd.input(), b"bug");
+/// ```
+pub struct DeltaDebugger {
+    input: Vec<u8>,
+    candidate: Option<Vec<u8>>,
+
+    // Number of chunks the input is split into.
+    granularity: usize,
+
+    // Index of the chunk removed from the current candidate.
+    index: usize,
+}
+
+impl DeltaDebugger {
+    pub fn new(input: Vec<u8>) -> Self {
+        Self {
+            input,
+            candidate: None,
+            granularity: 2,
+            index: 0,
+        }
+    }
+
+    /// The smallest interesting input found so far.
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    /// The next candidate to test, or `None` once no chunk, down to single
+    /// bytes, can be removed.
+    pub fn next_candidate(&mut self) -> Option<&[u8]> {
+        loop {
+            let len = self.input.len();
+            if len < 2 {
+                return None;
+            }
+
+            let granularity = self.granularity.min(len);
+            let chunk = div_ceil(len, granularity);
+            let start = self.index * chunk;
+
+            if start >= len {
+                // No chunk of this size can be removed, try smaller ones.
+                if granularity == len {
+                    return None;
+                }
+
+                self.granularity = (granularity * 2).min(len);
+                self.index = 0;
+                continue;
+            }
+
+            let end = (start + chunk).min(len);
+            let mut candidate = self.input[..start].to_vec();
+            candidate.extend_from_slice(&self.input[end..]);
+            self.candidate = Some(candidate);
+
+            return self.candidate.as_deref();
+        }
+    }
+
+    /// Report whether the last candidate is interesting, in which case it
+    /// replaces the input.
+    pub fn report(&mut self, interesting: bool) {
+        let candidate = match self.candidate.take() {
+            Some(candidate) => candidate,
+            None => return,
+        };
+
+        if interesting {
+            self.input = candidate;
+            self.granularity = self.granularity.saturating_sub(1).max(2);
+            self.index = 0;
+        } else {
+            self.index += 1;
+        }
+    }
+}
+
+fn div_ceil(a: usize, b: usize) -> usize {
+    let quotient = a / b;
+    if quotient * b == a {
+        quotient
+    } else {
+        quotient + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimize(input: &[u8], interesting: impl Fn(&[u8]) -> bool) -> (Vec<u8>, usize) {
+        let mut dd = DeltaDebugger::new(input.to_vec());
+        let mut tests = 0;
+
+        while let Some(candidate) = dd.next_candidate() {
+            tests += 1;
+            let result = interesting(candidate);
+            dd.report(result);
+        }
+
+        (dd.input().to_vec(), tests)
+    }
+
+    #[test]
+    fn test_minimize_substring() {
+        let input = b"The quick brown fox jumps over the lazy dog";
+        let (minimized, _) = minimize(input, |c| c.windows(3).any(|w| w == b"fox"));
+        assert_eq!(minimized, b"fox");
+    }
+
+    #[test]
+    fn test_minimize_scattered_bytes() {
+        let input = b"a-----b-----c";
+        let (minimized, _) = minimize(input, |c| {
+            let a = c.iter().position(|&b| b == b'a');
+            let b = c.iter().position(|&b| b == b'b');
+            let c = c.iter().position(|&b| b == b'c');
+            matches!((a, b, c), (Some(a), Some(b), Some(c)) if a < b && b < c)
+        });
+        assert_eq!(minimized, b"abc");
+    }
+
+    #[test]
+    fn test_minimize_uninteresting() {
+        let input = b"abcdef";
+        let (minimized, tests) = minimize(input, |_| false);
+        assert_eq!(minimized, input);
+
+        // Every chunk is tried once, in chunks of 3, 2 and 1 bytes.
+        assert_eq!(tests, 2 + 3 + 6);
+    }
+
+    #[test]
+    fn test_minimize_small_inputs() {
+        assert_eq!(minimize(b"", |_| true), (vec![], 0));
+        assert_eq!(minimize(b"a", |_| true), (b"a".to_vec(), 0));
+        assert_eq!(minimize(b"ab", |_| true), (b"b".to_vec(), 1));
+    }
+
+    #[test]
+    fn test_report_without_candidate() {
+        let mut dd = DeltaDebugger::new(b"abcd".to_vec());
+        dd.report(true);
+        assert_eq!(dd.input(), b"abcd");
+    }
+}
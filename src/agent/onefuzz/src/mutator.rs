@@ -0,0 +1,325 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use rand::{prelude::*, rngs::StdRng};
+
+// Largest value added to or subtracted from an integer by an arithmetic
+// mutation, as in AFL.
+const ARITH_MAX: u32 = 35;
+
+// Most mutations stacked on a single input.
+const MAX_STACKED: usize = 8;
+
+// Largest block inserted or duplicated at once.
+const MAX_BLOCK_SIZE: usize = 128;
+
+const INTERESTING_8: &[u8] = &[0x00, 0x01, 0x10, 0x20, 0x40, 0x64, 0x7f, 0x80, 0xff];
+
+const INTERESTING_16: &[u16] = &[
+    0x0000, 0x0080, 0x00ff, 0x0100, 0x0200, 0x03e8, 0x0400, 0x1000, 0x7fff, 0x8000, 0xff7f, 0xffff,
+];
+
+const INTERESTING_32: &[u32] = &[
+    0x0000_0000,
+    0x0000_8000,
+    0x0000_ffff,
+    0x0001_0000,
+    0x05f5_e100,
+    0x7fff_ffff,
+    0x8000_0000,
+    0xfa0a_1f00,
+    0xffff_7fff,
+    0xffff_ffff,
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mutation {
+    BitFlip,
+    Arithmetic,
+    InterestingValue,
+    InsertBlock,
+    DeleteBlock,
+    DuplicateBlock,
+    Splice,
+}
+
+const MUTATIONS: &[Mutation] = &[
+    Mutation::BitFlip,
+    Mutation::Arithmetic,
+    Mutation::InterestingValue,
+    Mutation::InsertBlock,
+    Mutation::DeleteBlock,
+    Mutation::DuplicateBlock,
+    Mutation::Splice,
+];
+
+/// A mutation engine, in the style of AFL's havoc stage.
+///
+/// Each mutated input is derived from the previous state of the random number
+/// generator, so a `Mutator` with a given seed produces the same inputs from
+/// the same corpus.
+pub struct Mutator {
+    rng: StdRng,
+    max_len: usize,
+}
+
+impl Mutator {
+    /// A random seed, for callers that don't need to reproduce a run, but
+    /// should log it in case they do.
+    pub fn random_seed() -> u64 {
+        rand::random()
+    }
+
+    pub fn new(seed: u64, max_len: usize) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            max_len,
+        }
+    }
+
+    /// Mutate an input of the corpus, picked at random, splicing it with
+    /// other inputs of the corpus.
+    ///
+    /// The corpus may be empty, in which case an empty input is mutated.
+    pub fn generate(&mut self, corpus: &[Vec<u8>]) -> Vec<u8> {
+        let input = match corpus.choose(&mut self.rng) {
+            Some(input) => input.clone(),
+            None => vec![],
+        };
+
+        self.mutate(input, corpus)
+    }
+
+    /// Apply a stack of random mutations to `input`.
+    pub fn mutate(&mut self, mut input: Vec<u8>, corpus: &[Vec<u8>]) -> Vec<u8> {
+        let count = self.rng.gen_range(1, MAX_STACKED + 1);
+        for _ in 0..count {
+            let mutation = *MUTATIONS.choose(&mut self.rng).unwrap();
+            self.apply(mutation, &mut input, corpus);
+        }
+
+        input.truncate(self.max_len);
+        input
+    }
+
+    fn apply(&mut self, mutation: Mutation, input: &mut Vec<u8>, corpus: &[Vec<u8>]) {
+        // Mutations that change bytes in place need some to work with.
+        if input.is_empty() {
+            self.insert_block(input);
+            return;
+        }
+
+        match mutation {
+            Mutation::BitFlip => self.bit_flip(input),
+            Mutation::Arithmetic => self.arithmetic(input),
+            Mutation::InterestingValue => self.interesting_value(input),
+            Mutation::InsertBlock => self.insert_block(input),
+            Mutation::DeleteBlock => self.delete_block(input),
+            Mutation::DuplicateBlock => self.duplicate_block(input),
+            Mutation::Splice => self.splice(input, corpus),
+        }
+    }
+
+    fn bit_flip(&mut self, input: &mut [u8]) {
+        let bit = self.rng.gen_range(0, input.len() * 8);
+        input[bit / 8] ^= 0x80 >> (bit % 8);
+    }
+
+    fn arithmetic(&mut self, input: &mut [u8]) {
+        let delta = self.rng.gen_range(1, ARITH_MAX + 1);
+        let subtract = self.rng.gen();
+
+        let width = self.width(input.len());
+        let offset = self.rng.gen_range(0, input.len() - width + 1);
+        let big_endian = self.rng.gen();
+        let value = read_int(&input[offset..offset + width], big_endian);
+
+        let value = if subtract {
+            value.wrapping_sub(delta)
+        } else {
+            value.wrapping_add(delta)
+        };
+        write_int(&mut input[offset..offset + width], value, big_endian);
+    }
+
+    fn interesting_value(&mut self, input: &mut [u8]) {
+        let width = self.width(input.len());
+        let offset = self.rng.gen_range(0, input.len() - width + 1);
+        let big_endian = self.rng.gen();
+
+        let value = match width {
+            1 => u32::from(*INTERESTING_8.choose(&mut self.rng).unwrap()),
+            2 => u32::from(*INTERESTING_16.choose(&mut self.rng).unwrap()),
+            _ => *INTERESTING_32.choose(&mut self.rng).unwrap(),
+        };
+        write_int(&mut input[offset..offset + width], value, big_endian);
+    }
+
+    fn insert_block(&mut self, input: &mut Vec<u8>) {
+        let len = self.rng.gen_range(1, MAX_BLOCK_SIZE + 1);
+        let offset = self.rng.gen_range(0, input.len() + 1);
+
+        // Either a run of a single byte, or random bytes.
+        let block: Vec<u8> = if self.rng.gen() {
+            vec![self.rng.gen(); len]
+        } else {
+            (0..len).map(|_| self.rng.gen()).collect()
+        };
+        input.splice(offset..offset, block);
+    }
+
+    fn delete_block(&mut self, input: &mut Vec<u8>) {
+        // Keep at least one byte.
+        if input.len() < 2 {
+            return;
+        }
+
+        let len = self.rng.gen_range(1, input.len());
+        let offset = self.rng.gen_range(0, input.len() - len + 1);
+        input.drain(offset..offset + len);
+    }
+
+    fn duplicate_block(&mut self, input: &mut Vec<u8>) {
+        let len = self.rng.gen_range(1, input.len().min(MAX_BLOCK_SIZE) + 1);
+        let src = self.rng.gen_range(0, input.len() - len + 1);
+        let dest = self.rng.gen_range(0, input.len() + 1);
+
+        let block = input[src..src + len].to_vec();
+        input.splice(dest..dest, block);
+    }
+
+    // Replace the tail of the input with the tail of another input of the
+    // corpus, split at random points.
+    fn splice(&mut self, input: &mut Vec<u8>, corpus: &[Vec<u8>]) {
+        let other = match corpus.choose(&mut self.rng) {
+            Some(other) if !other.is_empty() => other,
+            _ => return,
+        };
+
+        let split = self.rng.gen_range(0, input.len() + 1);
+        let other_split = self.rng.gen_range(0, other.len());
+        input.truncate(split);
+        input.extend_from_slice(&other[other_split..]);
+    }
+
+    // Width in bytes of an integer that fits in an input of `len` bytes.
+    fn width(&mut self, len: usize) -> usize {
+        let widths: Vec<usize> = [1, 2, 4].iter().copied().filter(|&w| w <= len).collect();
+        *widths.choose(&mut self.rng).unwrap()
+    }
+}
+
+fn read_int(bytes: &[u8], big_endian: bool) -> u32 {
+    let fold = |value: u32, byte: &u8| (value << 8) | u32::from(*byte);
+    if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    }
+}
+
+fn write_int(bytes: &mut [u8], value: u32, big_endian: bool) {
+    let len = bytes.len();
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let shift = if big_endian { len - 1 - i } else { i };
+        *byte = (value >> (shift * 8)) as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus() -> Vec<Vec<u8>> {
+        vec![b"hello world".to_vec(), b"{\"key\": 1234}".to_vec()]
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let corpus = corpus();
+        let mut a = Mutator::new(42, 1024);
+        let mut b = Mutator::new(42, 1024);
+
+        for _ in 0..100 {
+            assert_eq!(a.generate(&corpus), b.generate(&corpus));
+        }
+    }
+
+    #[test]
+    fn test_seeds_differ() {
+        let corpus = corpus();
+        let mut a = Mutator::new(1, 1024);
+        let mut b = Mutator::new(2, 1024);
+
+        let a: Vec<_> = (0..10).map(|_| a.generate(&corpus)).collect();
+        let b: Vec<_> = (0..10).map(|_| b.generate(&corpus)).collect();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_max_len() {
+        let corpus = corpus();
+        let mut mutator = Mutator::new(0, 8);
+
+        for _ in 0..1000 {
+            assert!(mutator.generate(&corpus).len() <= 8);
+        }
+    }
+
+    #[test]
+    fn test_empty_corpus() {
+        let mut mutator = Mutator::new(0, 1024);
+        for _ in 0..100 {
+            assert!(!mutator.generate(&[]).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_mutations() {
+        let corpus = corpus();
+        let mut mutator = Mutator::new(7, 1024);
+
+        for &mutation in MUTATIONS {
+            for len in 1..16 {
+                let original = vec![0x41; len];
+                let mut input = original.clone();
+                mutator.apply(mutation, &mut input, &corpus);
+
+                match mutation {
+                    Mutation::BitFlip => {
+                        let flipped: u32 = original
+                            .iter()
+                            .zip(&input)
+                            .map(|(a, b)| (a ^ b).count_ones())
+                            .sum();
+                        assert_eq!(flipped, 1);
+                    }
+                    Mutation::Arithmetic | Mutation::InterestingValue => {
+                        assert_eq!(input.len(), len)
+                    }
+                    Mutation::InsertBlock | Mutation::DuplicateBlock => {
+                        assert!(input.len() > len)
+                    }
+                    Mutation::DeleteBlock => assert!(!input.is_empty() && input.len() <= len),
+                    Mutation::Splice => {}
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_int_round_trip() {
+        let mut bytes = [0u8; 4];
+        write_int(&mut bytes, 0x0102_0304, true);
+        assert_eq!(bytes, [1, 2, 3, 4]);
+        assert_eq!(read_int(&bytes, true), 0x0102_0304);
+
+        write_int(&mut bytes, 0x0102_0304, false);
+        assert_eq!(bytes, [4, 3, 2, 1]);
+        assert_eq!(read_int(&bytes, false), 0x0102_0304);
+
+        let mut bytes = [0u8; 2];
+        write_int(&mut bytes, 0xffff_1234, false);
+        assert_eq!(bytes, [0x34, 0x12]);
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{
+    sha256,
+    stack::{parse_call_stack, StackFrame},
+};
+use regex::Regex;
+
+lazy_static! {
+    // Rust < 1.73: thread 'main' panicked at 'message', src/main.rs:4:5
+    static ref RUST_PANIC_QUOTED: Regex =
+        Regex::new(r"(?m)^thread '(.*)' panicked at '(.*)', (\S+:\d+:\d+)$").unwrap();
+
+    // Rust >= 1.73: thread 'main' panicked at src/main.rs:4:5:\nmessage
+    static ref RUST_PANIC: Regex =
+        Regex::new(r"(?m)^thread '(.*)' panicked at (\S+:\d+:\d+):$").unwrap();
+
+    static ref RUST_FRAME: Regex =
+        Regex::new(r"^\s*(\d+):\s+(?:(0x[0-9a-fA-F]+) - )?(.+)$").unwrap();
+    static ref RUST_FRAME_LOCATION: Regex = Regex::new(r"^\s+at (.+)$").unwrap();
+    static ref RUST_SYMBOL_HASH: Regex = Regex::new(r"::h[0-9a-f]{16}$").unwrap();
+
+    static ref GO_PANIC: Regex =
+        Regex::new(r"(?m)^(panic|fatal error): (.+?)(?: \[recovered\])?$").unwrap();
+    static ref GO_GOROUTINE: Regex = Regex::new(r"^goroutine \d+ \[.*\]:$").unwrap();
+    static ref GO_FRAME_LOCATION: Regex =
+        Regex::new(r"^\t(.+?:\d+)(?: \+0x[0-9a-fA-F]+)?(?: .*)?$").unwrap();
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PanicRuntime {
+    Rust,
+    Go,
+}
+
+/// Crash parsed from the panic output of a Rust or Go program.
+///
+/// The call stack is normalized to the numbered frame format used by sanitizers,
+/// so it can be parsed into `StackFrame`s and hashed like an `AsanLog` stack.
+#[derive(Clone, Debug)]
+pub struct PanicLog {
+    text: String,
+    runtime: PanicRuntime,
+    crash_type: String,
+    crash_site: String,
+    call_stack: Vec<String>,
+}
+
+impl PanicLog {
+    pub fn parse(text: String) -> Option<Self> {
+        let (runtime, crash_type, crash_site, call_stack) =
+            parse_rust_panic(&text).or_else(|| parse_go_panic(&text))?;
+
+        Some(Self {
+            text,
+            runtime,
+            crash_type,
+            crash_site,
+            call_stack,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn runtime(&self) -> PanicRuntime {
+        self.runtime
+    }
+
+    pub fn crash_type(&self) -> &str {
+        &self.crash_type
+    }
+
+    pub fn crash_site(&self) -> &str {
+        &self.crash_site
+    }
+
+    pub fn call_stack(&self) -> &[String] {
+        &self.call_stack
+    }
+
+    pub fn call_stack_frames(&self) -> Vec<StackFrame> {
+        parse_call_stack(&self.call_stack)
+    }
+
+    pub fn call_stack_sha256(&self) -> String {
+        sha256::digest_iter(self.call_stack())
+    }
+}
+
+type ParsedPanic = (PanicRuntime, String, String, Vec<String>);
+
+fn parse_rust_panic(text: &str) -> Option<ParsedPanic> {
+    let (thread, message, location) = if let Some(captures) = RUST_PANIC_QUOTED.captures(text) {
+        (
+            captures[1].to_owned(),
+            captures[2].to_owned(),
+            captures[3].to_owned(),
+        )
+    } else {
+        let captures = RUST_PANIC.captures(text)?;
+        let end = captures.get(0)?.end();
+        let message = text[end..].trim_start().lines().next().unwrap_or_default();
+        (
+            captures[1].to_owned(),
+            message.to_owned(),
+            captures[2].to_owned(),
+        )
+    };
+
+    let crash_site = format!(
+        "thread '{}' panicked at '{}', {}",
+        thread, message, location
+    );
+
+    let mut call_stack = parse_rust_backtrace(text);
+    if call_stack.is_empty() {
+        // Without `RUST_BACKTRACE=1`, the panic location is all we have.
+        call_stack.push(format!("#0 {}", location));
+    }
+
+    Some((
+        PanicRuntime::Rust,
+        "rust panic".to_owned(),
+        crash_site,
+        call_stack,
+    ))
+}
+
+// Parse a backtrace printed with `RUST_BACKTRACE=1` or `RUST_BACKTRACE=full`:
+//
+//    3: fuzz_target::parse
+//              at ./src/lib.rs:12:5
+//    3:     0x55d0c3a3b0c0 - fuzz_target::parse::h0123456789abcdef
+//                               at ./src/lib.rs:12:5
+fn parse_rust_backtrace(text: &str) -> Vec<String> {
+    let mut frames: Vec<(String, Option<String>, String, Option<String>)> = vec![];
+
+    let lines = text
+        .lines()
+        .skip_while(|line| line.trim() != "stack backtrace:")
+        .skip(1);
+
+    for line in lines {
+        if let Some(captures) = RUST_FRAME_LOCATION.captures(line) {
+            if let Some(frame) = frames.last_mut() {
+                frame.3 = Some(captures[1].to_owned());
+            }
+            continue;
+        }
+
+        if let Some(captures) = RUST_FRAME.captures(line) {
+            let index = captures[1].to_owned();
+            let address = captures.get(2).map(|a| a.as_str().to_owned());
+            let function = RUST_SYMBOL_HASH
+                .replace(captures[3].trim(), "")
+                .into_owned();
+            frames.push((index, address, function, None));
+            continue;
+        }
+
+        break;
+    }
+
+    frames
+        .into_iter()
+        .map(|(index, address, function, location)| {
+            let mut frame = format!("#{}", index);
+            if let Some(address) = address {
+                frame.push_str(&format!(" {} in", address));
+            }
+            frame.push_str(&format!(" {}", function));
+            if let Some(location) = location {
+                frame.push_str(&format!(" {}", location));
+            }
+            frame
+        })
+        .collect()
+}
+
+fn parse_go_panic(text: &str) -> Option<ParsedPanic> {
+    let captures = GO_PANIC.captures(text)?;
+    let kind = &captures[1];
+    let message = captures[2].to_owned();
+
+    let call_stack = parse_go_goroutine(text);
+
+    let crash_type = if kind == "panic" {
+        "go panic"
+    } else {
+        "go fatal error"
+    };
+
+    let crash_site = match call_stack.first().and_then(|f| StackFrame::parse(f)) {
+        Some(StackFrame {
+            source_file_path: Some(file),
+            source_file_line: Some(line),
+            ..
+        }) => format!("{}: {} at {}:{}", kind, message, file, line),
+        _ => format!("{}: {}", kind, message),
+    };
+
+    Some((
+        PanicRuntime::Go,
+        crash_type.to_owned(),
+        crash_site,
+        call_stack,
+    ))
+}
+
+// Parse the stack of the first goroutine in a goroutine dump, which is the one
+// that panicked:
+//
+//     goroutine 1 [running]:
+//     main.parse(0xc000012345, 0x3, 0x3)
+//             /home/user/go/src/fuzz/parse.go:12 +0x1d
+//     created by main.main
+//             /home/user/go/src/fuzz/main.go:10 +0x3f
+fn parse_go_goroutine(text: &str) -> Vec<String> {
+    let mut lines = text
+        .lines()
+        .skip_while(|line| !GO_GOROUTINE.is_match(line))
+        .skip(1)
+        .peekable();
+
+    let mut call_stack = vec![];
+
+    while let Some(line) = lines.next() {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        let function = line.trim_start_matches("created by ");
+        let function = match function.rfind('(') {
+            Some(index) if function.ends_with(')') => &function[..index],
+            _ => function,
+        };
+
+        // Every frame is followed by its location, so anything else, such as
+        // the `exit status 2` printed by `go run`, ends the stack.
+        let location = match lines
+            .peek()
+            .and_then(|next| GO_FRAME_LOCATION.captures(next))
+        {
+            Some(captures) => captures[1].to_owned(),
+            None => break,
+        };
+        lines.next();
+
+        call_stack.push(format!("#{} {} {}", call_stack.len(), function, location));
+    }
+
+    call_stack
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_log_parse() {
+        let test_cases = vec![
+            (
+                "data/rust-panic-no-backtrace.txt",
+                PanicRuntime::Rust,
+                "rust panic",
+                1,
+            ),
+            (
+                "data/rust-panic-backtrace.txt",
+                PanicRuntime::Rust,
+                "rust panic",
+                10,
+            ),
+            (
+                "data/rust-panic-backtrace-full.txt",
+                PanicRuntime::Rust,
+                "rust panic",
+                7,
+            ),
+            ("data/go-panic.txt", PanicRuntime::Go, "go panic", 3),
+            (
+                "data/go-fatal-error.txt",
+                PanicRuntime::Go,
+                "go fatal error",
+                4,
+            ),
+        ];
+
+        for (log_path, runtime, crash_type, call_stack_len) in test_cases {
+            let data = std::fs::read_to_string(log_path).unwrap();
+            let log = PanicLog::parse(data).unwrap();
+
+            assert_eq!(log.runtime(), runtime, "{}", log_path);
+            assert_eq!(log.crash_type(), crash_type, "{}", log_path);
+            assert_eq!(log.call_stack().len(), call_stack_len, "{}", log_path);
+            assert_eq!(
+                log.call_stack_frames().len(),
+                call_stack_len,
+                "{}",
+                log_path
+            );
+        }
+    }
+
+    #[test]
+    fn test_rust_panic_details() {
+        let data = std::fs::read_to_string("data/rust-panic-no-backtrace.txt").unwrap();
+        let log = PanicLog::parse(data).unwrap();
+        assert_eq!(
+            log.crash_site(),
+            "thread '<unnamed>' panicked at 'index out of bounds: the len is 3 but the index is 10', src/lib.rs:12:5"
+        );
+        assert_eq!(log.call_stack(), &["#0 src/lib.rs:12:5".to_owned()]);
+
+        let data = std::fs::read_to_string("data/rust-panic-backtrace.txt").unwrap();
+        let log = PanicLog::parse(data).unwrap();
+        assert_eq!(
+            log.crash_site(),
+            "thread '<unnamed>' panicked at 'attempt to add with overflow', fuzz_targets/fuzz_parse.rs:8:13"
+        );
+        let frames = log.call_stack_frames();
+        assert_eq!(
+            frames[3].function_name.as_deref(),
+            Some("fuzz_parse::parse")
+        );
+        assert_eq!(
+            frames[3].source_file_path.as_deref(),
+            Some("./fuzz_targets/fuzz_parse.rs")
+        );
+        assert_eq!(frames[3].source_file_line, Some(8));
+
+        let data = std::fs::read_to_string("data/rust-panic-backtrace-full.txt").unwrap();
+        let log = PanicLog::parse(data).unwrap();
+        let frames = log.call_stack_frames();
+        assert_eq!(frames[2].address, Some(0x55d0c3a3c1a4));
+        assert_eq!(frames[2].function_name.as_deref(), Some("parser::parse"));
+    }
+
+    #[test]
+    fn test_go_panic_details() {
+        let data = std::fs::read_to_string("data/go-panic.txt").unwrap();
+        let log = PanicLog::parse(data).unwrap();
+        assert_eq!(
+            log.crash_site(),
+            "panic: runtime error: index out of range [5] with length 3 at /home/user/go/src/fuzz/parse.go:12"
+        );
+
+        let frames = log.call_stack_frames();
+        assert_eq!(frames[0].function_name.as_deref(), Some("fuzz.parse"));
+        assert_eq!(frames[0].source_file_line, Some(12));
+        assert_eq!(frames[2].function_name.as_deref(), Some("main.main"));
+    }
+
+    #[test]
+    fn test_not_a_panic() {
+        assert!(PanicLog::parse("hello world\n".to_owned()).is_none());
+    }
+}
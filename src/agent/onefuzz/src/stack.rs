@@ -0,0 +1,279 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use regex::Regex;
+use std::fmt;
+
+lazy_static! {
+    static ref FRAME_INDEX: Regex = Regex::new(r"^#(\d+)\s+(.*)$").unwrap();
+    static ref FRAME_ADDRESS: Regex = Regex::new(r"^0x([0-9a-fA-F]+)\s*(.*)$").unwrap();
+    static ref FRAME_BUILD_ID: Regex = Regex::new(r"\s*\(BuildId: [0-9a-fA-F]+\)$").unwrap();
+    static ref FRAME_MODULE: Regex = Regex::new(r"\s*\(([^()]+)\+0x([0-9a-fA-F]+)\)$").unwrap();
+    static ref FRAME_SOURCE: Regex = Regex::new(r"(?:^|\s)(\S+?):(\d+)(?::(\d+))?$").unwrap();
+    static ref FRAME_FUNCTION_OFFSET: Regex = Regex::new(r"^(.+)\+0x([0-9a-fA-F]+)$").unwrap();
+}
+
+/// A single frame of a symbolized call stack, as printed by a sanitizer
+/// runtime or a debugger.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StackFrame {
+    /// Original text of the frame.
+    pub line: String,
+
+    /// Position of the frame in the stack, counting from the innermost frame.
+    pub index: Option<u64>,
+
+    /// Virtual address of the frame PC.
+    pub address: Option<u64>,
+
+    /// Name of the function containing the frame PC, if symbolized.
+    pub function_name: Option<String>,
+
+    /// Offset of the frame PC into `function_name`.
+    pub function_offset: Option<u64>,
+
+    /// Path of the module containing the frame PC.
+    pub module_path: Option<String>,
+
+    /// Offset of the frame PC into `module_path`.
+    pub module_offset: Option<u64>,
+
+    /// Source file of the frame PC, if debug info was available.
+    pub source_file_path: Option<String>,
+
+    pub source_file_line: Option<u64>,
+
+    pub source_file_column: Option<u64>,
+
+    /// True if the frame was inlined into the next (outer) frame.
+    #[serde(default)]
+    pub inlined: bool,
+}
+
+impl StackFrame {
+    /// Parse a sanitizer stack frame, such as:
+    ///
+    /// ```text
+    /// #0 0x527475 in LLVMFuzzerTestOneInput /src/fuzz.c:45:51
+    /// #1 0x42fb3a in main (/src/fuzz.exe+0x42fb3a)
+    /// #5 0x7f1ee3f0188f  (/lib/x86_64-linux-gnu/libpthread.so.0+0x1288f)
+    /// #0 Thread1 /src/tiny_race.c:4:10 (tiny_race.exe+0x4ac607)
+    /// ```
+    ///
+    /// Returns `None` if the line is not a numbered frame.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let captures = FRAME_INDEX.captures(line)?;
+        let index = captures.get(1)?.as_str().parse().ok();
+        let mut rest = captures.get(2)?.as_str().trim().to_owned();
+
+        let mut frame = StackFrame {
+            line: line.to_owned(),
+            index,
+            ..Default::default()
+        };
+
+        if let Some(captures) = FRAME_ADDRESS.captures(&rest) {
+            frame.address = u64::from_str_radix(&captures[1], 16).ok();
+            rest = captures[2].to_owned();
+        }
+
+        if let Some(stripped) = rest.strip_prefix("in ") {
+            rest = stripped.to_owned();
+        }
+
+        rest = FRAME_BUILD_ID.replace(&rest, "").into_owned();
+
+        if let Some(captures) = FRAME_MODULE.captures(&rest) {
+            frame.module_path = Some(captures[1].to_owned());
+            frame.module_offset = u64::from_str_radix(&captures[2], 16).ok();
+            let start = captures.get(0)?.start();
+            rest.truncate(start);
+        }
+
+        if let Some(captures) = FRAME_SOURCE.captures(&rest) {
+            frame.source_file_path = Some(captures[1].to_owned());
+            frame.source_file_line = captures[2].parse().ok();
+            frame.source_file_column = captures.get(3).and_then(|c| c.as_str().parse().ok());
+            let start = captures.get(0)?.start();
+            rest.truncate(start);
+        } else if rest.ends_with(" <null>") {
+            // TSan prints `<null>` when a frame has no source location.
+            let len = rest.len() - " <null>".len();
+            rest.truncate(len);
+        }
+
+        let function = rest.trim();
+        if !function.is_empty() && function != "<null>" {
+            if let Some(captures) = FRAME_FUNCTION_OFFSET.captures(function) {
+                frame.function_name = Some(captures[1].to_owned());
+                frame.function_offset = u64::from_str_radix(&captures[2], 16).ok();
+            } else {
+                frame.function_name = Some(function.to_owned());
+            }
+        }
+
+        Some(frame)
+    }
+}
+
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.line)
+    }
+}
+
+/// Parse a list of stack frame lines, skipping any that are not frames.
+///
+/// Consecutive frames that share a PC are the result of inline expansion by the
+/// symbolizer. All but the outermost of them are marked as `inlined`.
+pub fn parse_call_stack(lines: &[impl AsRef<str>]) -> Vec<StackFrame> {
+    let mut frames: Vec<StackFrame> = lines
+        .iter()
+        .filter_map(|line| StackFrame::parse(line.as_ref()))
+        .collect();
+
+    mark_inlined(&mut frames);
+
+    frames
+}
+
+/// Mark frames that were inlined into the frame that follows them.
+pub fn mark_inlined(frames: &mut [StackFrame]) {
+    for i in 1..frames.len() {
+        let (inner, outer) = (&frames[i - 1], &frames[i]);
+        if inner.address.is_some() && inner.address == outer.address {
+            frames[i - 1].inlined = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frame_source() {
+        let frame = StackFrame::parse(
+            "    #0 0x527475 in LLVMFuzzerTestOneInput /home/testuser/fuzz.c:45:51",
+        )
+        .unwrap();
+
+        assert_eq!(frame.index, Some(0));
+        assert_eq!(frame.address, Some(0x527475));
+        assert_eq!(
+            frame.function_name.as_deref(),
+            Some("LLVMFuzzerTestOneInput")
+        );
+        assert_eq!(
+            frame.source_file_path.as_deref(),
+            Some("/home/testuser/fuzz.c")
+        );
+        assert_eq!(frame.source_file_line, Some(45));
+        assert_eq!(frame.source_file_column, Some(51));
+        assert_eq!(frame.module_path, None);
+    }
+
+    #[test]
+    fn test_parse_frame_module() {
+        let frame = StackFrame::parse(
+            "#3 0x424ba1 in fuzzer::FuzzerDriver(int*, char***, int (*)(unsigned char const*, unsigned long)) (/home/testuser/fuzz.exe+0x424ba1)",
+        )
+        .unwrap();
+
+        assert_eq!(frame.index, Some(3));
+        assert_eq!(
+            frame.function_name.as_deref(),
+            Some(
+                "fuzzer::FuzzerDriver(int*, char***, int (*)(unsigned char const*, unsigned long))"
+            )
+        );
+        assert_eq!(
+            frame.module_path.as_deref(),
+            Some("/home/testuser/fuzz.exe")
+        );
+        assert_eq!(frame.module_offset, Some(0x424ba1));
+        assert_eq!(frame.source_file_path, None);
+    }
+
+    #[test]
+    fn test_parse_frame_unsymbolized() {
+        let frame =
+            StackFrame::parse("#5 0x7f1ee3f0188f  (/lib/x86_64-linux-gnu/libpthread.so.0+0x1288f)")
+                .unwrap();
+
+        assert_eq!(frame.address, Some(0x7f1ee3f0188f));
+        assert_eq!(frame.function_name, None);
+        assert_eq!(
+            frame.module_path.as_deref(),
+            Some("/lib/x86_64-linux-gnu/libpthread.so.0")
+        );
+        assert_eq!(frame.module_offset, Some(0x1288f));
+    }
+
+    #[test]
+    fn test_parse_frame_windows() {
+        let frame = StackFrame::parse(
+            r"#0 0x7ff739e118a8 in __sanitizer_print_stack_trace C:\src\compiler-rt\lib\asan\asan_stack.cpp:86",
+        )
+        .unwrap();
+
+        assert_eq!(
+            frame.source_file_path.as_deref(),
+            Some(r"C:\src\compiler-rt\lib\asan\asan_stack.cpp")
+        );
+        assert_eq!(frame.source_file_line, Some(86));
+        assert_eq!(frame.source_file_column, None);
+
+        let frame = StackFrame::parse(
+            r"#8 0x7ff739df1061 in LLVMFuzzerTestOneInput (X:\fuzz\fuzz.exe+0x140001061)",
+        )
+        .unwrap();
+        assert_eq!(frame.module_path.as_deref(), Some(r"X:\fuzz\fuzz.exe"));
+        assert_eq!(frame.module_offset, Some(0x140001061));
+    }
+
+    #[test]
+    fn test_parse_frame_tsan() {
+        let frame =
+            StackFrame::parse("#0 Thread1 /src/tiny_race.c:4:10 (tiny_race.exe+0x4ac607)").unwrap();
+
+        assert_eq!(frame.address, None);
+        assert_eq!(frame.function_name.as_deref(), Some("Thread1"));
+        assert_eq!(frame.source_file_path.as_deref(), Some("/src/tiny_race.c"));
+        assert_eq!(frame.module_path.as_deref(), Some("tiny_race.exe"));
+
+        let frame = StackFrame::parse("#0 pthread_create <null> (tiny_race.exe+0x422fe5)").unwrap();
+        assert_eq!(frame.function_name.as_deref(), Some("pthread_create"));
+        assert_eq!(frame.source_file_path, None);
+    }
+
+    #[test]
+    fn test_parse_frame_function_offset() {
+        let frame =
+            StackFrame::parse("#2 0x4010a3 in parse_header+0x13 (/src/fuzz.exe+0x10a3)").unwrap();
+
+        assert_eq!(frame.function_name.as_deref(), Some("parse_header"));
+        assert_eq!(frame.function_offset, Some(0x13));
+    }
+
+    #[test]
+    fn test_parse_call_stack_inlined() {
+        let lines = vec![
+            "#0 0x4010a3 in inner /src/fuzz.c:4:3",
+            "#1 0x4010a3 in outer /src/fuzz.c:10:5",
+            "#2 0x4011f0 in main /src/fuzz.c:20:1",
+        ];
+        let frames = parse_call_stack(&lines);
+
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].inlined);
+        assert!(!frames[1].inlined);
+        assert!(!frames[2].inlined);
+    }
+
+    #[test]
+    fn test_parse_not_frame() {
+        assert!(StackFrame::parse("SUMMARY: AddressSanitizer: heap-use-after-free").is_none());
+    }
+}
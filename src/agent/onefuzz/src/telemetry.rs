@@ -22,6 +22,10 @@ pub enum Event {
     new_report,
     new_unique_report,
     new_unable_to_reproduce,
+    preflight_failed,
+    new_hang,
+    new_oom,
+    new_leak,
 }
 
 impl Event {
@@ -36,6 +40,10 @@ impl Event {
             Self::new_report => "new_report",
             Self::new_unique_report => "new_unique_report",
             Self::new_unable_to_reproduce => "new_unable_to_reproduce",
+            Self::preflight_failed => "preflight_failed",
+            Self::new_hang => "new_hang",
+            Self::new_oom => "new_oom",
+            Self::new_leak => "new_leak",
         }
     }
 }
@@ -71,6 +79,15 @@ pub enum EventData {
     CoveragePathsImported(u64),
     CoverageMaxDepth(u64),
     ToolName(String),
+    CoverageEdges(u64),
+    CoverageFeatures(u64),
+    CorpusUnits(u64),
+    CorpusSize(u64),
+    MaxInputLength(u64),
+    RssMb(u64),
+    NewFunctions(u64),
+    Crashes(u64),
+    UniqueCrashes(u64),
 }
 
 impl EventData {
@@ -105,6 +122,15 @@ impl EventData {
             Self::CoverageMaxDepth(x) => ("coverage_paths_depth", x.to_string()),
             Self::Coverage(x) => ("coverage", x.to_string()),
             Self::ToolName(x) => ("tool_name", x.to_owned()),
+            Self::CoverageEdges(x) => ("coverage_edges", x.to_string()),
+            Self::CoverageFeatures(x) => ("coverage_features", x.to_string()),
+            Self::CorpusUnits(x) => ("corpus_units", x.to_string()),
+            Self::CorpusSize(x) => ("corpus_size", x.to_string()),
+            Self::MaxInputLength(x) => ("max_input_length", x.to_string()),
+            Self::RssMb(x) => ("rss_mb", x.to_string()),
+            Self::NewFunctions(x) => ("new_functions", x.to_string()),
+            Self::Crashes(x) => ("crashes", x.to_string()),
+            Self::UniqueCrashes(x) => ("unique_crashes", x.to_string()),
         }
     }
 
@@ -141,6 +167,15 @@ impl EventData {
             Self::CoverageMaxDepth(_) => true,
             Self::Coverage(_) => true,
             Self::ToolName(_) => true,
+            Self::CoverageEdges(_) => true,
+            Self::CoverageFeatures(_) => true,
+            Self::CorpusUnits(_) => true,
+            Self::CorpusSize(_) => true,
+            Self::MaxInputLength(_) => true,
+            Self::RssMb(_) => true,
+            Self::NewFunctions(_) => true,
+            Self::Crashes(_) => true,
+            Self::UniqueCrashes(_) => true,
         }
     }
 }
@@ -0,0 +1,391 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::fmt;
+
+/// A parsed command line or environment template, such as a task's
+/// `target_options`.
+///
+/// Grammar:
+///
+/// ```text
+/// template    = { text | "{{" | "}}" | placeholder }
+/// placeholder = "{" name [ "?" | ":-" nested | ":+" nested ] "}"
+/// name        = word [ ":" word ]
+/// word        = ( letter | digit | "_" ) { letter | digit | "_" }
+/// ```
+///
+/// `text` is any run of characters other than `{` and `}`, and `{{` and `}}`
+/// are literal braces. A `nested` template is like a top-level one, except that
+/// it ends at the first unescaped `}`, which closes the placeholder, so it can't
+/// contain a literal `}`.
+///
+/// Placeholder modifiers:
+///
+/// - `{name}`: the value of `name`, which must be set.
+/// - `{name?}`: the value of `name`, or nothing if unset.
+/// - `{name:-nested}`: the value of `name`, or `nested` if unset or empty.
+/// - `{name:+nested}`: `nested` if `name` is set and not empty, or nothing.
+///
+/// Templates parsed with `parse_lenient` have no escapes or syntax errors.
+/// Braces that don't start a valid placeholder, as in JSON values or GUIDs,
+/// are text, and so are `{{` and `}}`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Placeholder {
+    /// Name of the value, such as `input` or `env:HOME`.
+    pub name: String,
+
+    pub modifier: Modifier,
+
+    /// Source text of the placeholder, including braces.
+    pub source: String,
+
+    /// Byte offset of the placeholder in the top-level template.
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Modifier {
+    Required,
+    Optional,
+    Default(Template),
+    Alternate(Template),
+}
+
+/// Syntax error in a template, at a byte `offset`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            text,
+            pos: 0,
+            lenient: false,
+        };
+        parser.template(false)
+    }
+
+    /// Parse a template, treating any brace that doesn't start a valid
+    /// placeholder as text.
+    pub fn parse_lenient(text: &str) -> Self {
+        let mut parser = Parser {
+            text,
+            pos: 0,
+            lenient: true,
+        };
+
+        // Invalid placeholders are recovered from as text, so this can't fail.
+        parser.template(false).unwrap_or_else(|_| Template {
+            segments: vec![Segment::Text(text.to_owned())],
+        })
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// True if the template has a top-level placeholder that may expand to
+    /// nothing, because it is optional (`?`) or alternate (`:+`).
+    pub fn is_conditional(&self) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Placeholder(placeholder) => matches!(
+                placeholder.modifier,
+                Modifier::Optional | Modifier::Alternate(_)
+            ),
+            Segment::Text(_) => false,
+        })
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    lenient: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error<T>(&self, offset: usize, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            offset,
+            message: message.into(),
+        })
+    }
+
+    // Parse a template, up to the end of the input or, if `nested`, up to (but
+    // not including) the `}` that ends it.
+    fn template(&mut self, nested: bool) -> Result<Template, ParseError> {
+        let mut segments = vec![];
+        let mut text = String::new();
+
+        loop {
+            if !self.lenient && self.rest().starts_with("{{") {
+                text.push('{');
+                self.pos += 2;
+                continue;
+            }
+
+            match self.peek() {
+                None if nested => return self.error(self.pos, "unterminated placeholder"),
+                None => break,
+                Some('}') if nested => break,
+                Some('}') if self.lenient => {
+                    text.push('}');
+                    self.pos += 1;
+                }
+                Some('}') if self.rest().starts_with("}}") => {
+                    text.push('}');
+                    self.pos += 2;
+                }
+                Some('}') => {
+                    return self.error(self.pos, "unmatched `}`, use `}}` for a literal brace")
+                }
+                Some('{') => {
+                    let start = self.pos;
+                    match self.placeholder() {
+                        Ok(placeholder) => {
+                            if !text.is_empty() {
+                                segments.push(Segment::Text(std::mem::take(&mut text)));
+                            }
+                            segments.push(Segment::Placeholder(placeholder));
+                        }
+                        Err(_) if self.lenient => {
+                            self.pos = start + 1;
+                            text.push('{');
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        Ok(Template { segments })
+    }
+
+    fn placeholder(&mut self) -> Result<Placeholder, ParseError> {
+        let start = self.pos;
+        self.pos += 1; // `{`
+
+        let mut name = self.word();
+        if name.is_empty() {
+            return self.error(
+                self.pos,
+                "expected a placeholder name, use `{{` for a literal brace",
+            );
+        }
+
+        // Namespaced name, like `env:HOME`, but not a modifier like `:-`.
+        if self.rest().starts_with(':') && self.rest()[1..].starts_with(is_word_char) {
+            self.pos += 1;
+            name.push(':');
+            name.push_str(&self.word());
+        }
+
+        let modifier = if self.rest().starts_with('?') {
+            self.pos += 1;
+            Modifier::Optional
+        } else if self.rest().starts_with(":-") {
+            self.pos += 2;
+            Modifier::Default(self.template(true)?)
+        } else if self.rest().starts_with(":+") {
+            self.pos += 2;
+            Modifier::Alternate(self.template(true)?)
+        } else {
+            Modifier::Required
+        };
+
+        match self.peek() {
+            Some('}') => self.pos += 1,
+            None => return self.error(start, "unterminated placeholder"),
+            Some(_) => return self.error(self.pos, "expected `}`, `?`, `:-` or `:+`"),
+        }
+
+        Ok(Placeholder {
+            name,
+            modifier,
+            source: self.text[start..self.pos].to_owned(),
+            offset: start,
+        })
+    }
+
+    fn word(&mut self) -> String {
+        let len = self
+            .rest()
+            .find(|c| !is_word_char(c))
+            .unwrap_or_else(|| self.rest().len());
+        let word = self.rest()[..len].to_owned();
+        self.pos += len;
+        word
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Segment {
+        Segment::Text(s.to_owned())
+    }
+
+    fn placeholder(template: &Template, index: usize) -> &Placeholder {
+        match &template.segments()[index] {
+            Segment::Placeholder(placeholder) => placeholder,
+            segment => panic!("not a placeholder: {:?}", segment),
+        }
+    }
+
+    #[test]
+    fn test_parse_text_and_escapes() {
+        let template = Template::parse("-dict={{\"a\": 1}}").unwrap();
+        assert_eq!(template.segments(), &[text("-dict={\"a\": 1}")]);
+
+        assert_eq!(Template::parse("").unwrap().segments(), &[]);
+    }
+
+    #[test]
+    fn test_parse_placeholders() {
+        let template = Template::parse("-o={output_dir}/{input_file_name}").unwrap();
+        assert_eq!(template.segments().len(), 4);
+        assert_eq!(template.segments()[0], text("-o="));
+
+        let output_dir = placeholder(&template, 1);
+        assert_eq!(output_dir.name, "output_dir");
+        assert_eq!(output_dir.modifier, Modifier::Required);
+        assert_eq!(output_dir.source, "{output_dir}");
+        assert_eq!(output_dir.offset, 3);
+
+        let template = Template::parse("{env:HOME}").unwrap();
+        assert_eq!(placeholder(&template, 0).name, "env:HOME");
+    }
+
+    #[test]
+    fn test_parse_modifiers() {
+        let template = Template::parse("{reports_dir?}").unwrap();
+        assert_eq!(placeholder(&template, 0).modifier, Modifier::Optional);
+        assert!(template.is_conditional());
+
+        let template = Template::parse("-timeout={target_timeout:-5}").unwrap();
+        assert_eq!(
+            placeholder(&template, 1).modifier,
+            Modifier::Default(Template {
+                segments: vec![text("5")]
+            })
+        );
+        assert!(!template.is_conditional());
+
+        let template = Template::parse("{env:SEED:+-seed={env:SEED}}").unwrap();
+        let seed = placeholder(&template, 0);
+        assert_eq!(seed.name, "env:SEED");
+        match &seed.modifier {
+            Modifier::Alternate(nested) => {
+                assert_eq!(nested.segments().len(), 2);
+                assert_eq!(nested.segments()[0], text("-seed="));
+                assert_eq!(placeholder(nested, 1).offset, 17);
+            }
+            modifier => panic!("unexpected modifier: {:?}", modifier),
+        }
+        assert!(template.is_conditional());
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let cases = vec![
+            "{{input}}",
+            "a}b",
+            "x={ input}",
+            "{}",
+            "{input!}",
+            "{input:-5",
+            "{\"a\": [1, 2]}",
+            "{6f3c2a1e-9b1d-4f7a-8c2e-5d4b3a2f1e0d}",
+        ];
+
+        for case in cases {
+            let template = Template::parse_lenient(case);
+            let text: String = template
+                .segments()
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Text(text) => text.clone(),
+                    Segment::Placeholder(placeholder) => placeholder.source.clone(),
+                })
+                .collect();
+            assert_eq!(text, case);
+        }
+
+        let template = Template::parse_lenient("{\"corpus\": \"{input_corpus}\"}");
+        assert_eq!(template.segments().len(), 3);
+        assert_eq!(template.segments()[0], text("{\"corpus\": \""));
+        assert_eq!(placeholder(&template, 1).name, "input_corpus");
+        assert_eq!(placeholder(&template, 1).offset, 12);
+        assert_eq!(template.segments()[2], text("\"}"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = vec![
+            ("{input", 0, "unterminated placeholder"),
+            ("a}b", 1, "unmatched `}`, use `}}` for a literal brace"),
+            (
+                "x={ input}",
+                3,
+                "expected a placeholder name, use `{{` for a literal brace",
+            ),
+            (
+                "{}",
+                1,
+                "expected a placeholder name, use `{{` for a literal brace",
+            ),
+            ("{input!}", 6, "expected `}`, `?`, `:-` or `:+`"),
+            ("{input:-5", 9, "unterminated placeholder"),
+        ];
+
+        for (template, offset, message) in cases {
+            let err = Template::parse(template).unwrap_err();
+            assert_eq!(err.offset, offset, "{}", template);
+            assert_eq!(err.message, message, "{}", template);
+        }
+    }
+}
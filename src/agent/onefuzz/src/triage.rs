@@ -2,7 +2,14 @@
 // Licensed under the MIT License.
 
 #![allow(clippy::trivially_copy_pass_by_ref)]
+use crate::{
+    coredump::{self, CrashedThread},
+    dwarf::{DwarfSymbolizer, SourceLocation},
+    exploitable::{self, CrashContext, Exploitability, MappedRegion},
+    stack::StackFrame,
+};
 use anyhow::Result;
+use iced_x86::{Code, Decoder, DecoderOptions, Formatter, Instruction, IntelFormatter};
 use pete::{
     Command, Pid, Ptracer, Restart, Siginfo,
     Signal::{self, *},
@@ -12,6 +19,12 @@ use proc_maps::MapRange;
 use serde::Serialize;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc, Arc,
+};
+use std::time::Duration;
 
 pub struct TriageCommand {
     argv: Vec<String>,
@@ -19,6 +32,9 @@ pub struct TriageCommand {
     tracer: Ptracer,
     tracee: Tracee,
     pid: Pid,
+    symbolizer: DwarfSymbolizer,
+    deadline: Option<Duration>,
+    core_dump_dir: Option<PathBuf>,
     _kill_on_drop: KillOnDrop,
 }
 impl TriageCommand {
@@ -44,6 +60,9 @@ impl TriageCommand {
             tracer,
             tracee,
             pid,
+            symbolizer: DwarfSymbolizer::new(),
+            deadline: None,
+            core_dump_dir: None,
             _kill_on_drop,
         })
     }
@@ -52,29 +71,97 @@ impl TriageCommand {
         self.pid
     }
 
+    /// Treat the target as hung if it runs for longer than `timeout`.
+    ///
+    /// On expiry, the stacks of all threads are recorded in the report, and the
+    /// target is killed.
+    pub fn deadline(&mut self, timeout: Duration) -> &mut Self {
+        self.deadline = Some(timeout);
+        self
+    }
+
+    /// Write a core file of the target to `dir` if it dies after a crashing signal.
+    ///
+    /// The core is written when the target exits with an error or is killed by a
+    /// signal, and describes the last crash, whose `Crash` gets its path. The core
+    /// is named `core.<tid>`, after the signaled thread. No core is written for
+    /// crashes that the target recovers from, or if it hangs.
+    pub fn core_dump_dir(&mut self, dir: impl Into<PathBuf>) -> &mut Self {
+        self.core_dump_dir = Some(dir.into());
+        self
+    }
+
     pub fn run(mut self) -> Result<TriageReport> {
+        let expired = Arc::new(AtomicBool::new(false));
+
+        // Dropped when `run()` returns, which stops the watchdog.
+        let _watchdog = self
+            .deadline
+            .map(|timeout| Watchdog::start(self.pid, timeout, expired.clone()));
+
         self.tracer.restart(self.tracee, Restart::Continue)?;
 
         let mut crashes = vec![];
         let mut exit_status = None;
+        let mut hang = None;
+
+        // State of the thread of the last crash, for its core file.
+        let mut crashed_thread = None;
 
-        while let Some(tracee) = self.tracer.wait()? {
-            if let Stop::SignalDeliveryStop(pid, signal) = tracee.stop {
+        while let Some(mut tracee) = self.tracer.wait()? {
+            // The watchdog interrupts the target when the deadline expires, so we
+            // get a stop here, even if every thread was blocked.
+            if hang.is_none() && expired.load(Ordering::SeqCst) {
+                let (threads, _maps) = snapshot_threads(tracee.pid, &mut self.symbolizer)?;
+                hang = Some(Hang { threads });
+                exit_status = Some(ExitStatus::TimedOut);
+
+                nix::sys::signal::kill(self.pid, SIGKILL)?;
+            }
+            if let Stop::SignalDeliveryStop(_pid, signal) = tracee.stop {
                 if CRASH_SIGNALS.contains(&signal) {
                     // Can unwrap due to signal-delivery-stop.
                     let siginfo = tracee.siginfo()?.unwrap();
-                    crashes.push(Crash::new(pid, signal, siginfo)?);
+                    let crash = Crash::new(&mut tracee, signal, siginfo, &mut self.symbolizer)?;
+
+                    // Only save the registers now. Writing the core is deferred until
+                    // we know the target did not recover.
+                    if self.core_dump_dir.is_some() {
+                        crashed_thread = Some(CrashedThread::new(&mut tracee, siginfo)?);
+                    }
+
+                    crashes.push(crash);
+                }
+            }
+
+            // Both exit stops happen before the memory of the target is released.
+            let died = match tracee.stop {
+                Stop::Exiting(pid, exit_code) => pid == self.pid && exit_code != 0,
+                Stop::Signaling(pid, _signal, _core_dump) => pid == self.pid,
+                _ => false,
+            };
+
+            if let (true, None, Some(dir)) = (died, &hang, &self.core_dump_dir) {
+                if let (Some(thread), Some(crash)) = (crashed_thread.take(), crashes.last_mut()) {
+                    let path = dir.join(format!("core.{}", thread.tid));
+
+                    // The core is a debugging aid, so don't fail the triage run
+                    // if it can't be written.
+                    match coredump::write_core(self.pid, &thread, &path) {
+                        Ok(()) => crash.core_dump = Some(path),
+                        Err(err) => warn!("unable to write core file {}: {}", path.display(), err),
+                    }
                 }
             }
 
             if let Stop::Exiting(pid, exit_code) = tracee.stop {
-                if pid == self.pid {
+                if pid == self.pid && hang.is_none() {
                     exit_status = Some(ExitStatus::Exited(exit_code));
                 }
             }
 
             if let Stop::Signaling(pid, signal, _core_dump) = tracee.stop {
-                if pid == self.pid {
+                if pid == self.pid && hang.is_none() {
                     exit_status = Some(ExitStatus::Signaled(signal));
                 }
             }
@@ -82,7 +169,8 @@ impl TriageCommand {
             self.tracer.restart(tracee, Restart::Continue)?;
         }
 
-        // We must observe either a normal or signaled exit for the parent.
+        // We must observe either a normal or signaled exit for the parent, or
+        // have killed it after it hung.
         let exit_status = exit_status.unwrap();
 
         Ok(TriageReport {
@@ -90,10 +178,33 @@ impl TriageCommand {
             env: self.env,
             exit_status,
             crashes,
+            hang,
         })
     }
 }
 
+// Thread that interrupts a traced process with `SIGSTOP` if it outlives a
+// deadline, so that the tracer wakes up. Stopped early when dropped.
+struct Watchdog {
+    _cancel: mpsc::Sender<()>,
+}
+
+impl Watchdog {
+    fn start(pid: Pid, timeout: Duration, expired: Arc<AtomicBool>) -> Self {
+        let (cancel, cancelled) = mpsc::channel::<()>();
+
+        std::thread::spawn(move || {
+            // Either an explicit cancel or a disconnect means the run finished.
+            if let Err(mpsc::RecvTimeoutError::Timeout) = cancelled.recv_timeout(timeout) {
+                expired.store(true, Ordering::SeqCst);
+                let _ = nix::sys::signal::kill(pid, SIGSTOP);
+            }
+        });
+
+        Self { _cancel: cancel }
+    }
+}
+
 // Wrapper for a PID that signals it with SIGKILL when dropped.
 //
 // Lets us avoid an impl of `Drop` for `TriageCommand`, which constraints how
@@ -114,6 +225,9 @@ pub struct TriageReport {
     pub env: HashMap<String, String>,
     pub exit_status: ExitStatus,
     pub crashes: Vec<Crash>,
+
+    /// Set if the target outlived the deadline of the run.
+    pub hang: Option<Hang>,
 }
 
 impl TriageReport {
@@ -126,6 +240,16 @@ impl TriageReport {
     pub fn crashed(&self) -> bool {
         self.signaled() && !self.crashes.is_empty()
     }
+
+    /// Did the target run past its deadline?
+    pub fn hung(&self) -> bool {
+        matches!(self.exit_status, ExitStatus::TimedOut)
+    }
+
+    /// Exploitability of the last crash, if any.
+    pub fn exploitability(&self) -> Option<&Exploitability> {
+        self.crashes.last().map(|c| &c.exploitability)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -135,6 +259,20 @@ pub enum ExitStatus {
 
     #[serde(rename = "signaled")]
     Signaled(#[serde(serialize_with = "se::signal")] Signal),
+
+    /// Killed by the tracer, after running past its deadline.
+    #[serde(rename = "timed_out")]
+    TimedOut,
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExitStatus::Exited(code) => write!(f, "exited with code {}", code),
+            ExitStatus::Signaled(signal) => write!(f, "terminated by signal {}", signal.as_ref()),
+            ExitStatus::TimedOut => write!(f, "timed out"),
+        }
+    }
 }
 
 pub type ExitCode = i32;
@@ -154,75 +292,172 @@ pub struct Crash {
 
     /// All active threads at time of crash, including the crashing thread.
     pub threads: BTreeMap<i32, ThreadInfo>,
+
+    /// Heuristic rating of how likely the crash is to be exploitable.
+    pub exploitability: Exploitability,
+
+    /// General-purpose registers of the crashing thread, if readable.
+    pub registers: Option<Registers>,
+
+    /// Instructions around the PC of the crashing thread, if readable.
+    pub disassembly: Vec<DisassembledInstruction>,
+
+    /// Path of the core file written for the crash, if requested.
+    pub core_dump: Option<PathBuf>,
 }
 
 impl Crash {
-    pub fn new(tid: Pid, signal: Signal, siginfo: Siginfo) -> Result<Self> {
-        let mut stacktrace = rstack::TraceOptions::new();
-        stacktrace
-            .snapshot(true)
-            .thread_names(true)
-            .symbols(true)
-            .ptrace_attach(false);
-
-        let proc = stacktrace.trace(tid.as_raw() as u32)?;
+    pub fn new(
+        tracee: &mut Tracee,
+        signal: Signal,
+        siginfo: Siginfo,
+        symbolizer: &mut DwarfSymbolizer,
+    ) -> Result<Self> {
+        let tid = tracee.pid;
 
         let crashing_access = segv_access_addr(siginfo)?.map(|a| a.into());
 
-        let maps = proc_maps::get_process_maps(tid.as_raw())?;
+        let (threads, maps) = snapshot_threads(tid, symbolizer)?;
 
-        let mut threads = BTreeMap::new();
+        let registers = tracee.registers().ok().map(Registers::from);
 
-        for thread in proc.threads() {
-            let mut callstack = vec![];
+        let pc = registers.as_ref().map(|r| r.rip.0);
+        let sp = registers.as_ref().map(|r| r.rsp.0);
 
-            for frame in thread.frames() {
-                let addr = frame.ip();
+        let exploitability = classify(
+            tracee,
+            signal,
+            siginfo,
+            pc,
+            sp,
+            &maps,
+            threads.get(&tid.as_raw()),
+        );
 
-                let module = find_module_rva(addr, &maps);
+        let disassembly = pc.map(|pc| disassemble(tracee, pc)).unwrap_or_default();
 
-                let function = if let Some(symbol) = frame.symbol() {
-                    let mangled = symbol.name();
-                    let demangled = cpp_demangle::Symbol::new(&mangled)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|_| mangled.into());
+        Ok(Crash {
+            signal,
+            crashing_access,
+            tid,
+            threads,
+            exploitability,
+            registers,
+            disassembly,
+            core_dump: None,
+        })
+    }
+}
+
+// Record the stacks of all threads of the process containing `tid`.
+//
+// Also returns the memory maps of the process, which were used to find the
+// modules of each frame.
+fn snapshot_threads(
+    tid: Pid,
+    symbolizer: &mut DwarfSymbolizer,
+) -> Result<(BTreeMap<i32, ThreadInfo>, Vec<MapRange>)> {
+    let mut stacktrace = rstack::TraceOptions::new();
+    stacktrace
+        .snapshot(true)
+        .thread_names(true)
+        .symbols(true)
+        .ptrace_attach(false);
+
+    let proc = stacktrace.trace(tid.as_raw() as u32)?;
+
+    let maps = proc_maps::get_process_maps(tid.as_raw())?;
+
+    let mut threads = BTreeMap::new();
+
+    for thread in proc.threads() {
+        let mut callstack = vec![];
+
+        for (index, frame) in thread.frames().iter().enumerate() {
+            let addr = frame.ip();
+
+            let module = find_module_rva(addr, &maps);
+
+            let function = if let Some(symbol) = frame.symbol() {
+                let mangled = symbol.name();
+                let demangled = cpp_demangle::Symbol::new(&mangled)
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|_| mangled.into());
+
+                Some(Rva {
+                    name: demangled,
+                    offset: symbol.offset(),
+                })
+            } else {
+                None
+            };
 
-                    Some(Rva {
-                        name: demangled,
-                        offset: symbol.offset(),
-                    })
-                } else {
-                    None
-                };
+            // The PC of every frame but the innermost is a return address, which
+            // may be the first address after the source line of the call.
+            let symbolized = match &module {
+                Some(module) => {
+                    let offset = if index == 0 {
+                        module.offset
+                    } else {
+                        module.offset.saturating_sub(1)
+                    };
+                    symbolizer.symbolize(&module.name, offset)
+                }
+                None => vec![],
+            };
+
+            // All but the last symbolized frame were inlined into the function
+            // containing the PC. Report them like a sanitizer would, as frames
+            // that share the PC of the outer frame.
+            let (outer, inlined) = match symbolized.split_last() {
+                Some((outer, inlined)) => (Some(outer), inlined),
+                None => (None, &[][..]),
+            };
 
-                let addr = addr.into();
+            for inlined in inlined {
+                let function = inlined.function.as_ref().map(|name| Rva {
+                    name: name.clone(),
+                    offset: 0,
+                });
 
                 callstack.push(Frame {
-                    addr,
-                    module,
+                    addr: addr.into(),
+                    module: module.clone(),
                     function,
+                    source: inlined.location.clone(),
+                    inlined: true,
                 });
             }
 
-            let tid = Pid::from_raw(thread.id() as i32);
-            let name = thread.name().map(|n| n.to_owned());
-
-            let info = ThreadInfo {
-                tid,
-                name,
-                callstack,
-            };
-
-            threads.insert(tid.as_raw(), info);
+            callstack.push(Frame {
+                addr: addr.into(),
+                module,
+                function,
+                source: outer.and_then(|f| f.location.clone()),
+                inlined: false,
+            });
         }
 
-        Ok(Crash {
-            signal,
-            crashing_access,
+        let tid = Pid::from_raw(thread.id() as i32);
+        let name = thread.name().map(|n| n.to_owned());
+
+        let info = ThreadInfo {
             tid,
-            threads,
-        })
+            name,
+            callstack,
+        };
+
+        threads.insert(tid.as_raw(), info);
     }
+
+    Ok((threads, maps))
+}
+
+/// State of a target that was killed for running past its deadline.
+#[derive(Debug, Serialize)]
+pub struct Hang {
+    /// All threads at the time of the deadline.
+    pub threads: BTreeMap<i32, ThreadInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -250,8 +485,46 @@ pub struct Frame {
 
     /// Function-relative address of `addr`, if resolved.
     pub function: Option<Rva>,
+
+    /// Source location of `addr`, if the module has DWARF debug info.
+    pub source: Option<SourceLocation>,
+
+    /// True if the frame was inlined into the next (outer) frame.
+    ///
+    /// Inlined frames share the `addr` and `module` of the outer frame, and have
+    /// no meaningful function offset.
+    pub inlined: bool,
 }
 
+impl Frame {
+    /// Structured form of the frame, with its source location.
+    ///
+    /// `index` is the position of the frame in its stack, counting inlined frames.
+    pub fn to_stack_frame(&self, index: usize) -> StackFrame {
+        let source = self.source.as_ref();
+
+        StackFrame {
+            line: self.to_string(),
+            index: Some(index as u64),
+            address: Some(self.addr.0),
+            function_name: self.function.as_ref().map(|f| f.name.clone()),
+            function_offset: self
+                .function
+                .as_ref()
+                .filter(|_| !self.inlined)
+                .map(|f| f.offset),
+            module_path: self.module.as_ref().map(|m| m.name.clone()),
+            module_offset: self.module.as_ref().map(|m| m.offset),
+            source_file_path: source.map(|s| s.file.clone()),
+            source_file_line: source.map(|s| s.line.into()),
+            source_file_column: source.and_then(|s| s.column).map(|c| c.into()),
+            inlined: self.inlined,
+        }
+    }
+}
+
+// Source locations and inlined frames are left out, so that the hash of a call
+// stack does not change with the debug info of the target.
 impl fmt::Display for Frame {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let (Some(function), Some(module)) = (&self.function, &self.module) {
@@ -275,7 +548,7 @@ impl fmt::Display for Frame {
 }
 
 /// Virtual memory address expressed as a symbol-relative offset.
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Rva {
     /// Name of symbol, which may be a function or a module.
     pub name: String,
@@ -295,6 +568,206 @@ impl From<u64> for Address {
     }
 }
 
+// Gather the state of the crashing thread, and rate the crash's exploitability.
+fn classify(
+    tracee: &mut Tracee,
+    signal: Signal,
+    siginfo: Siginfo,
+    pc: Option<u64>,
+    sp: Option<u64>,
+    maps: &[MapRange],
+    thread: Option<&ThreadInfo>,
+) -> Exploitability {
+    let instruction = pc
+        .and_then(|pc| tracee.read_memory(pc, MAX_INSTRUCTION_LEN).ok())
+        .unwrap_or_default();
+
+    let regions = maps
+        .iter()
+        .map(|map| MappedRegion {
+            start: map.start() as u64,
+            end: (map.start() + map.size()) as u64,
+            readable: map.flags.contains('r'),
+            writable: map.flags.contains('w'),
+            executable: map.flags.contains('x'),
+            name: map.filename().clone(),
+        })
+        .collect();
+
+    let functions = thread
+        .map(|t| {
+            t.callstack
+                .iter()
+                .filter_map(|f| f.function.as_ref().map(|f| f.name.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let crash = CrashContext {
+        signal: signal.as_ref().to_owned(),
+        si_code: siginfo.si_code,
+        fault_address: segv_access_addr(siginfo).ok().flatten(),
+        pc,
+        sp,
+        regions,
+        instruction,
+        functions,
+    };
+
+    exploitable::classify(&crash)
+}
+
+// Longest possible x86-64 instruction.
+const MAX_INSTRUCTION_LEN: usize = 15;
+
+// Bytes before the PC to search for instruction boundaries.
+const DISASSEMBLY_BYTES_BEFORE: u64 = 32;
+
+const DISASSEMBLY_INSTRUCTIONS_BEFORE: usize = 4;
+const DISASSEMBLY_INSTRUCTIONS_AFTER: usize = 4;
+
+/// General-purpose registers of an x86-64 thread.
+#[derive(Debug, Serialize)]
+pub struct Registers {
+    pub rax: Address,
+    pub rbx: Address,
+    pub rcx: Address,
+    pub rdx: Address,
+    pub rsi: Address,
+    pub rdi: Address,
+    pub rbp: Address,
+    pub rsp: Address,
+    pub r8: Address,
+    pub r9: Address,
+    pub r10: Address,
+    pub r11: Address,
+    pub r12: Address,
+    pub r13: Address,
+    pub r14: Address,
+    pub r15: Address,
+    pub rip: Address,
+    pub eflags: Address,
+    pub fs_base: Address,
+    pub gs_base: Address,
+}
+
+impl From<pete::Registers> for Registers {
+    fn from(regs: pete::Registers) -> Self {
+        Self {
+            rax: regs.rax.into(),
+            rbx: regs.rbx.into(),
+            rcx: regs.rcx.into(),
+            rdx: regs.rdx.into(),
+            rsi: regs.rsi.into(),
+            rdi: regs.rdi.into(),
+            rbp: regs.rbp.into(),
+            rsp: regs.rsp.into(),
+            r8: regs.r8.into(),
+            r9: regs.r9.into(),
+            r10: regs.r10.into(),
+            r11: regs.r11.into(),
+            r12: regs.r12.into(),
+            r13: regs.r13.into(),
+            r14: regs.r14.into(),
+            r15: regs.r15.into(),
+            rip: regs.rip.into(),
+            eflags: regs.eflags.into(),
+            fs_base: regs.fs_base.into(),
+            gs_base: regs.gs_base.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DisassembledInstruction {
+    /// Virtual address of the instruction.
+    pub addr: Address,
+
+    /// Hex-encoded machine code.
+    pub bytes: String,
+
+    /// Intel syntax assembly.
+    pub text: String,
+
+    /// True if this is the instruction at the crashing PC.
+    pub faulting: bool,
+}
+
+// Disassemble a few instructions before and after `pc`.
+//
+// x86-64 code can't be reliably decoded backwards, so we try decoding from each
+// of the bytes before `pc`, and keep the first run that lands exactly on `pc`.
+fn disassemble(tracee: &mut Tracee, pc: u64) -> Vec<DisassembledInstruction> {
+    let len =
+        DISASSEMBLY_BYTES_BEFORE as usize + MAX_INSTRUCTION_LEN * DISASSEMBLY_INSTRUCTIONS_AFTER;
+
+    // The bytes before the PC may be unmapped, if it is at the start of a page.
+    let start = pc.saturating_sub(DISASSEMBLY_BYTES_BEFORE);
+    let (start, code) = match tracee.read_memory(start, len) {
+        Ok(code) => (start, code),
+        Err(_) => match tracee.read_memory(pc, len - DISASSEMBLY_BYTES_BEFORE as usize) {
+            Ok(code) => (pc, code),
+            Err(_) => return vec![],
+        },
+    };
+
+    let before = (start..pc)
+        .map(|from| decode(&code[(from - start) as usize..], from, pc))
+        .find(|instructions| instructions.iter().any(|i| i.ip() == pc))
+        .unwrap_or_default();
+
+    let mut instructions: Vec<Instruction> = before.into_iter().filter(|i| i.ip() < pc).collect();
+    let skip = instructions
+        .len()
+        .saturating_sub(DISASSEMBLY_INSTRUCTIONS_BEFORE);
+    instructions.drain(..skip);
+
+    let after = decode(&code[(pc - start) as usize..], pc, u64::MAX);
+    instructions.extend(after.into_iter().take(DISASSEMBLY_INSTRUCTIONS_AFTER));
+
+    let mut formatter = IntelFormatter::new();
+
+    instructions
+        .into_iter()
+        .map(|instruction| {
+            let offset = (instruction.ip() - start) as usize;
+            let bytes = &code[offset..offset + instruction.len()];
+
+            let mut text = String::new();
+            formatter.format(&instruction, &mut text);
+
+            DisassembledInstruction {
+                addr: instruction.ip().into(),
+                bytes: hex::encode(bytes),
+                text,
+                faulting: instruction.ip() == pc,
+            }
+        })
+        .collect()
+}
+
+// Decode the instructions in `code`, which is mapped at `ip`, up to but not past
+// `end`. Stops at the first invalid instruction.
+fn decode(code: &[u8], ip: u64, end: u64) -> Vec<Instruction> {
+    let mut decoder = Decoder::new(64, code, DecoderOptions::NONE);
+    decoder.set_ip(ip);
+
+    let mut instructions = vec![];
+    let mut instruction = Instruction::default();
+
+    while decoder.can_decode() && decoder.ip() <= end {
+        decoder.decode_out(&mut instruction);
+
+        if instruction.code() == Code::INVALID {
+            break;
+        }
+
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
 // Find the module-relative address of `addr`, if it exists.
 fn find_module_rva(addr: u64, maps: &[MapRange]) -> Option<Rva> {
     let mapping = find_mapping(addr, &maps)?;
@@ -8,8 +8,20 @@ import os
 from typing import Dict, List, Optional
 from uuid import UUID
 
-from onefuzztypes.enums import Compare, ContainerPermission, ContainerType, TaskFeature
-from onefuzztypes.models import TaskConfig, TaskDefinition, TaskUnitConfig
+from onefuzztypes.enums import (
+    Compare,
+    ContainerPermission,
+    ContainerType,
+    GeneratorType,
+    StatsFormat,
+    TaskFeature,
+)
+from onefuzztypes.models import (
+    DedupConfig,
+    TaskConfig,
+    TaskDefinition,
+    TaskUnitConfig,
+)
 
 from ..azure.containers import blob_exists, container_exists, get_container_sas_url
 from ..azure.creds import get_func_storage, get_fuzz_storage, get_instance_url
@@ -90,6 +102,42 @@ def check_containers(definition: TaskDefinition, config: TaskConfig) -> None:
             )
 
 
+# Placeholders of the task directories that hold the files of a container.
+CONTAINER_DIRS = {
+    ContainerType.setup: "{setup_dir}",
+    ContainerType.tools: "{tools_dir}",
+}
+
+
+def check_container_path(config: TaskConfig, name: str, path: str) -> None:
+    """ Check that a path such as `{setup_dir}/fuzz.dict` exists in its container """
+    for (container_type, placeholder) in CONTAINER_DIRS.items():
+        for separator in ["/", "\\"]:
+            prefix = placeholder + separator
+            if not path.startswith(prefix):
+                continue
+
+            containers = [x for x in config.containers if x.type == container_type]
+            if not containers:
+                err = "%s `%s` requires a %s container" % (
+                    name,
+                    path,
+                    container_type.name,
+                )
+                LOGGER.error(err)
+                raise TaskConfigError(err)
+
+            if not blob_exists(containers[0].name, path[len(prefix) :]):
+                err = "%s `%s` does not exist in the %s container `%s`" % (
+                    name,
+                    path,
+                    container_type.name,
+                    containers[0].name,
+                )
+                LOGGER.error(err)
+                raise TaskConfigError(err)
+
+
 def check_config(config: TaskConfig) -> None:
     if config.task.type not in TASK_DEFINITIONS:
         raise TaskConfigError("unsupported task type: %s" % config.task.type.name)
@@ -139,7 +187,9 @@ def check_config(config: TaskConfig) -> None:
             )
             LOGGER.warning(err)
 
-    if TaskFeature.generator_exe in definition.features:
+    if TaskFeature.generator_exe in definition.features and (
+        config.task.generator_type in [None, GeneratorType.external]
+    ):
         container = [x for x in config.containers if x.type == ContainerType.tools][0]
         if not config.task.generator_exe:
             raise TaskConfigError("generator_exe is not defined")
@@ -159,12 +209,41 @@ def check_config(config: TaskConfig) -> None:
                     LOGGER.error(err)
                     raise TaskConfigError(err)
 
+    if (
+        TaskFeature.generator_grammar in definition.features
+        and config.task.generator_type == GeneratorType.grammar
+    ):
+        if not config.task.generator_grammar:
+            raise TaskConfigError("generator_grammar is not defined")
+
+        check_container_path(config, "generator_grammar", config.task.generator_grammar)
+
+    if TaskFeature.afl_exe in definition.features and not config.task.afl_exe:
+        raise TaskConfigError("afl_exe is not defined")
+
+    if (
+        TaskFeature.honggfuzz_exe in definition.features
+        and not config.task.honggfuzz_exe
+    ):
+        raise TaskConfigError("honggfuzz_exe is not defined")
+
+    if TaskFeature.target_dict in definition.features and config.task.target_dict:
+        check_container_path(config, "target_dict", config.task.target_dict)
+
     if TaskFeature.stats_file in definition.features:
         if config.task.stats_file is not None and config.task.stats_format is None:
             err = "using a stats_file requires a stats_format"
             LOGGER.error(err)
             raise TaskConfigError(err)
 
+        if (
+            config.task.stats_format in [StatsFormat.KeyValue, StatsFormat.Json]
+            and not config.task.stats_fields
+        ):
+            err = "using a KeyValue or Json stats_format requires stats_fields"
+            LOGGER.error(err)
+            raise TaskConfigError(err)
+
 
 def build_task_config(
     job_id: UUID, task_id: UUID, task_config: TaskConfig
@@ -189,6 +268,9 @@ def build_task_config(
         back_channel_address="https://%s/api/back_channel" % (get_instance_url()),
     )
 
+    if task_config.task.strict_placeholders is not None:
+        config.strict_placeholders = task_config.task.strict_placeholders
+
     if definition.monitor_queue:
         config.input_queue = get_queue_sas(
             task_id,
@@ -261,10 +343,18 @@ def build_task_config(
     if TaskFeature.target_options_merge in definition.features:
         config.target_options_merge = task_config.task.target_options_merge or False
 
+    if TaskFeature.target_dict in definition.features:
+        config.target_dict = task_config.task.target_dict
+
     if TaskFeature.rename_output in definition.features:
         config.rename_output = task_config.task.rename_output or False
 
-    if TaskFeature.generator_exe in definition.features:
+    # The built-in generators have no generator_exe, which the agent
+    # defaults to empty.
+    if (
+        TaskFeature.generator_exe in definition.features
+        and task_config.task.generator_exe is not None
+    ):
         config.generator_exe = task_config.task.generator_exe
 
     if TaskFeature.generator_env in definition.features:
@@ -273,12 +363,42 @@ def build_task_config(
     if TaskFeature.generator_options in definition.features:
         config.generator_options = task_config.task.generator_options or EMPTY_LIST
 
+    if (
+        TaskFeature.generator_type in definition.features
+        and task_config.task.generator_type is not None
+    ):
+        config.generator_type = task_config.task.generator_type
+
+    if TaskFeature.generator_seed in definition.features:
+        config.generator_seed = task_config.task.generator_seed
+
+    if TaskFeature.generator_grammar in definition.features:
+        config.generator_grammar = task_config.task.generator_grammar
+
+    if TaskFeature.generator_max_depth in definition.features:
+        config.generator_max_depth = task_config.task.generator_max_depth
+
     if (
         TaskFeature.wait_for_files in definition.features
         and task_config.task.wait_for_files
     ):
         config.wait_for_files = task_config.task.wait_for_files.name
 
+    if TaskFeature.afl_exe in definition.features:
+        config.afl_exe = task_config.task.afl_exe
+
+    if TaskFeature.afl_env in definition.features:
+        config.afl_env = task_config.task.afl_env or EMPTY_DICT
+
+    if TaskFeature.afl_options in definition.features:
+        config.afl_options = task_config.task.afl_options or EMPTY_LIST
+
+    if TaskFeature.honggfuzz_exe in definition.features:
+        config.honggfuzz_exe = task_config.task.honggfuzz_exe
+
+    if TaskFeature.honggfuzz_options in definition.features:
+        config.honggfuzz_options = task_config.task.honggfuzz_options or EMPTY_LIST
+
     if TaskFeature.analyzer_exe in definition.features:
         config.analyzer_exe = task_config.task.analyzer_exe
 
@@ -291,6 +411,8 @@ def build_task_config(
     if TaskFeature.stats_file in definition.features:
         config.stats_file = task_config.task.stats_file
         config.stats_format = task_config.task.stats_format
+        if task_config.task.stats_fields:
+            config.stats_fields = task_config.task.stats_fields
 
     if TaskFeature.target_timeout in definition.features:
         config.target_timeout = task_config.task.target_timeout
@@ -307,6 +429,22 @@ def build_task_config(
     if TaskFeature.ensemble_sync_delay in definition.features:
         config.ensemble_sync_delay = task_config.task.ensemble_sync_delay
 
+    if TaskFeature.dedup in definition.features:
+        config.dedup = task_config.task.dedup or DedupConfig()
+
+    # The agent has defaults for these, so only set them if given.
+    if (
+        TaskFeature.minimize_timeout in definition.features
+        and task_config.task.minimize_timeout is not None
+    ):
+        config.minimize_timeout = task_config.task.minimize_timeout
+
+    if (
+        TaskFeature.minimize_max_tests in definition.features
+        and task_config.task.minimize_max_tests is not None
+    ):
+        config.minimize_max_tests = task_config.task.minimize_max_tests
+
     return config
 
 
@@ -63,6 +63,7 @@ TASK_DEFINITIONS = {
             TaskFeature.target_options,
             TaskFeature.target_workers,
             TaskFeature.ensemble_sync_delay,
+            TaskFeature.target_dict,
         ],
         vm=VmDefinition(compare=Compare.AtLeast, value=1),
         containers=[
@@ -78,6 +79,24 @@ TASK_DEFINITIONS = {
                 value=1,
                 permissions=[ContainerPermission.Write, ContainerPermission.Create],
             ),
+            ContainerDefinition(
+                type=ContainerType.hangs,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
+            ContainerDefinition(
+                type=ContainerType.ooms,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
+            ContainerDefinition(
+                type=ContainerType.leaks,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
             ContainerDefinition(
                 type=ContainerType.inputs,
                 compare=Compare.Equal,
@@ -95,6 +114,17 @@ TASK_DEFINITIONS = {
                 value=0,
                 permissions=[ContainerPermission.Read, ContainerPermission.List],
             ),
+            ContainerDefinition(
+                type=ContainerType.dictionaries,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[
+                    ContainerPermission.Write,
+                    ContainerPermission.Read,
+                    ContainerPermission.List,
+                    ContainerPermission.Create,
+                ],
+            ),
         ],
         monitor_queue=None,
     ),
@@ -105,6 +135,7 @@ TASK_DEFINITIONS = {
             TaskFeature.target_options,
             TaskFeature.target_timeout,
             TaskFeature.check_retry_count,
+            TaskFeature.dedup,
         ],
         vm=VmDefinition(compare=Compare.AtLeast, value=1),
         containers=[
@@ -201,9 +232,150 @@ TASK_DEFINITIONS = {
                 value=1,
                 permissions=[ContainerPermission.Create, ContainerPermission.List],
             ),
+            ContainerDefinition(
+                type=ContainerType.merge_control,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[
+                    ContainerPermission.Read,
+                    ContainerPermission.Write,
+                    ContainerPermission.Create,
+                    ContainerPermission.Delete,
+                    ContainerPermission.List,
+                ],
+            ),
         ],
         monitor_queue=ContainerType.inputs,
     ),
+    TaskType.libfuzzer_minimize: TaskDefinition(
+        features=[
+            TaskFeature.target_exe,
+            TaskFeature.target_env,
+            TaskFeature.target_options,
+            TaskFeature.minimize_timeout,
+        ],
+        vm=VmDefinition(compare=Compare.AtLeast, value=1),
+        containers=[
+            ContainerDefinition(
+                type=ContainerType.setup,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.crashes,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.minimized_crashes,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
+            ContainerDefinition(
+                type=ContainerType.reports,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[
+                    ContainerPermission.Read,
+                    ContainerPermission.Write,
+                    ContainerPermission.Create,
+                ],
+            ),
+        ],
+        monitor_queue=ContainerType.crashes,
+    ),
+    TaskType.afl_fuzz: TaskDefinition(
+        features=[
+            TaskFeature.afl_exe,
+            TaskFeature.afl_env,
+            TaskFeature.afl_options,
+            TaskFeature.target_exe,
+            TaskFeature.target_options,
+            TaskFeature.target_workers,
+            TaskFeature.ensemble_sync_delay,
+        ],
+        vm=VmDefinition(compare=Compare.AtLeast, value=1),
+        containers=[
+            ContainerDefinition(
+                type=ContainerType.setup,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.tools,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.inputs,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.crashes,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
+            ContainerDefinition(
+                type=ContainerType.hangs,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
+        ],
+        monitor_queue=None,
+    ),
+    TaskType.honggfuzz_fuzz: TaskDefinition(
+        features=[
+            TaskFeature.honggfuzz_exe,
+            TaskFeature.honggfuzz_options,
+            TaskFeature.target_exe,
+            TaskFeature.target_env,
+            TaskFeature.target_options,
+            TaskFeature.target_workers,
+            TaskFeature.ensemble_sync_delay,
+        ],
+        vm=VmDefinition(compare=Compare.AtLeast, value=1),
+        containers=[
+            ContainerDefinition(
+                type=ContainerType.setup,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.tools,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.inputs,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[
+                    ContainerPermission.Write,
+                    ContainerPermission.Read,
+                    ContainerPermission.List,
+                    ContainerPermission.Create,
+                ],
+            ),
+            ContainerDefinition(
+                type=ContainerType.crashes,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
+        ],
+        monitor_queue=None,
+    ),
     TaskType.generic_supervisor: TaskDefinition(
         features=[
             TaskFeature.target_exe,
@@ -293,6 +465,10 @@ TASK_DEFINITIONS = {
             TaskFeature.generator_exe,
             TaskFeature.generator_env,
             TaskFeature.generator_options,
+            TaskFeature.generator_type,
+            TaskFeature.generator_seed,
+            TaskFeature.generator_grammar,
+            TaskFeature.generator_max_depth,
             TaskFeature.target_exe,
             TaskFeature.target_env,
             TaskFeature.target_options,
@@ -341,6 +517,7 @@ TASK_DEFINITIONS = {
             TaskFeature.check_asan_log,
             TaskFeature.check_debugger,
             TaskFeature.check_retry_count,
+            TaskFeature.dedup,
         ],
         vm=VmDefinition(compare=Compare.AtLeast, value=1),
         containers=[
@@ -374,6 +551,57 @@ TASK_DEFINITIONS = {
                 value=1,
                 permissions=[ContainerPermission.Create],
             ),
+            ContainerDefinition(
+                type=ContainerType.cores,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[ContainerPermission.Create],
+            ),
+        ],
+        monitor_queue=ContainerType.crashes,
+    ),
+    TaskType.generic_minimize: TaskDefinition(
+        features=[
+            TaskFeature.target_exe,
+            TaskFeature.target_env,
+            TaskFeature.target_options,
+            TaskFeature.target_timeout,
+            TaskFeature.check_asan_log,
+            TaskFeature.check_debugger,
+            TaskFeature.check_retry_count,
+            TaskFeature.minimize_max_tests,
+            TaskFeature.dedup,
+        ],
+        vm=VmDefinition(compare=Compare.AtLeast, value=1),
+        containers=[
+            ContainerDefinition(
+                type=ContainerType.setup,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.crashes,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Read, ContainerPermission.List],
+            ),
+            ContainerDefinition(
+                type=ContainerType.minimized_crashes,
+                compare=Compare.Equal,
+                value=1,
+                permissions=[ContainerPermission.Write, ContainerPermission.Create],
+            ),
+            ContainerDefinition(
+                type=ContainerType.reports,
+                compare=Compare.AtMost,
+                value=1,
+                permissions=[
+                    ContainerPermission.Read,
+                    ContainerPermission.Write,
+                    ContainerPermission.Create,
+                ],
+            ),
         ],
         monitor_queue=ContainerType.crashes,
     ),
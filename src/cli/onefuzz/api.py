@@ -663,8 +663,14 @@ class Tasks(Endpoint):
         supervisor_input_marker: Optional[str] = None,
         stats_file: Optional[str] = None,
         stats_format: Optional[enums.StatsFormat] = None,
+        stats_fields: Optional[Dict[str, str]] = None,
+        dedup: Optional[models.DedupConfig] = None,
         generator_exe: Optional[str] = None,
         generator_options: Optional[List[str]] = None,
+        generator_type: Optional[enums.GeneratorType] = None,
+        generator_seed: Optional[int] = None,
+        generator_grammar: Optional[str] = None,
+        generator_max_depth: Optional[int] = None,
         task_wait_for_files: Optional[enums.ContainerType] = None,
         analyzer_exe: Optional[str] = None,
         analyzer_options: Optional[List[str]] = None,
@@ -673,12 +679,41 @@ class Tasks(Endpoint):
         prereq_tasks: Optional[List[UUID]] = None,
         debug: Optional[List[enums.TaskDebugFlag]] = None,
         ensemble_sync_delay: Optional[int] = None,
+        minimize_timeout: Optional[int] = None,
+        minimize_max_tests: Optional[int] = None,
+        afl_exe: Optional[str] = None,
+        afl_env: Optional[Dict[str, str]] = None,
+        afl_options: Optional[List[str]] = None,
+        honggfuzz_exe: Optional[str] = None,
+        honggfuzz_options: Optional[List[str]] = None,
+        strict_placeholders: Optional[bool] = None,
+        target_dict: Optional[str] = None,
     ) -> models.Task:
         """
         Create a task
 
+        :param str generator_type: Generator of generic_generator tasks:
+            `external` to run generator_exe, or the built-in `mutator` or
+            `grammar` generators.
+        :param int generator_seed: Seed of the built-in generators.
+        :param str generator_grammar: Grammar of the `grammar` generator, such
+            as `{tools_dir}/sql.bnf`.
+        :param int generator_max_depth: Depth of the derivations of the
+            `grammar` generator past which they are ended as soon as possible.
         :param bool ensemble_sync_delay: Specify duration between
             syncing inputs during ensemble fuzzing (0 to disable).
+        :param int minimize_timeout: Time limit for minimizing each input with
+            libFuzzer, in seconds.
+        :param int minimize_max_tests: Maximum number of candidates to test
+            when minimizing each input by delta debugging.
+        :param str afl_exe: Path to `afl-fuzz` for afl_fuzz tasks, such as
+            `{tools_dir}/afl-fuzz`.
+        :param str honggfuzz_exe: Path to `honggfuzz` for honggfuzz_fuzz tasks,
+            such as `{tools_dir}/honggfuzz`.
+        :param bool strict_placeholders: Fail the task on unknown placeholders
+            and unescaped braces in its command lines and environment.
+        :param str target_dict: Dictionary for libfuzzer_fuzz tasks, such as
+            `{setup_dir}/fuzz.dict`.
         """
 
         self.logger.debug("creating task: %s", task_type)
@@ -731,14 +766,29 @@ class Tasks(Endpoint):
                 analyzer_options=analyzer_options,
                 stats_file=stats_file,
                 stats_format=stats_format,
+                stats_fields=stats_fields,
+                dedup=dedup,
                 generator_exe=generator_exe,
                 generator_options=generator_options,
+                generator_type=generator_type,
+                generator_seed=generator_seed,
+                generator_grammar=generator_grammar,
+                generator_max_depth=generator_max_depth,
                 wait_for_files=task_wait_for_files,
                 reboot_after_setup=reboot_after_setup,
                 check_asan_log=check_asan_log,
                 check_debugger=check_debugger,
                 check_retry_count=check_retry_count,
                 ensemble_sync_delay=ensemble_sync_delay,
+                minimize_timeout=minimize_timeout,
+                minimize_max_tests=minimize_max_tests,
+                afl_exe=afl_exe,
+                afl_env=afl_env,
+                afl_options=afl_options,
+                honggfuzz_exe=honggfuzz_exe,
+                honggfuzz_options=honggfuzz_options,
+                strict_placeholders=strict_placeholders,
+                target_dict=target_dict,
             ),
             pool=models.TaskPool(count=vm_count, pool_name=pool_name),
             containers=containers_submit,
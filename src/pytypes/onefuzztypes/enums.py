@@ -68,12 +68,25 @@ class TaskFeature(Enum):
     generator_exe = "generator_exe"
     generator_env = "generator_env"
     generator_options = "generator_options"
+    generator_type = "generator_type"
+    generator_seed = "generator_seed"
+    generator_grammar = "generator_grammar"
+    generator_max_depth = "generator_max_depth"
     wait_for_files = "wait_for_files"
     target_timeout = "target_timeout"
     check_asan_log = "check_asan_log"
     check_debugger = "check_debugger"
     check_retry_count = "check_retry_count"
     ensemble_sync_delay = "ensemble_sync_delay"
+    dedup = "dedup"
+    minimize_timeout = "minimize_timeout"
+    minimize_max_tests = "minimize_max_tests"
+    afl_exe = "afl_exe"
+    afl_env = "afl_env"
+    afl_options = "afl_options"
+    honggfuzz_exe = "honggfuzz_exe"
+    honggfuzz_options = "honggfuzz_options"
+    target_dict = "target_dict"
 
 
 # Permissions for an Azure Blob Storage Container.
@@ -143,11 +156,15 @@ class TaskType(Enum):
     libfuzzer_coverage = "libfuzzer_coverage"
     libfuzzer_crash_report = "libfuzzer_crash_report"
     libfuzzer_merge = "libfuzzer_merge"
+    libfuzzer_minimize = "libfuzzer_minimize"
+    afl_fuzz = "afl_fuzz"
+    honggfuzz_fuzz = "honggfuzz_fuzz"
     generic_analysis = "generic_analysis"
     generic_supervisor = "generic_supervisor"
     generic_merge = "generic_merge"
     generic_generator = "generic_generator"
     generic_crash_report = "generic_crash_report"
+    generic_minimize = "generic_minimize"
 
 
 class VmState(Enum):
@@ -191,10 +208,17 @@ class Compare(Enum):
 
 class ContainerType(Enum):
     analysis = "analysis"
+    cores = "cores"
     coverage = "coverage"
     crashes = "crashes"
+    dictionaries = "dictionaries"
+    hangs = "hangs"
     inputs = "inputs"
+    leaks = "leaks"
+    merge_control = "merge_control"
+    minimized_crashes = "minimized_crashes"
     no_repro = "no_repro"
+    ooms = "ooms"
     readonly_inputs = "readonly_inputs"
     reports = "reports"
     setup = "setup"
@@ -219,6 +243,16 @@ class ContainerType(Enum):
 
 class StatsFormat(Enum):
     AFL = "AFL"
+    Honggfuzz = "Honggfuzz"
+    LibFuzzer = "LibFuzzer"
+    KeyValue = "KeyValue"
+    Json = "Json"
+
+
+class GeneratorType(Enum):
+    external = "external"
+    mutator = "mutator"
+    grammar = "grammar"
 
 
 class ErrorCode(Enum):
@@ -248,6 +282,7 @@ class ErrorCode(Enum):
 class HeartbeatType(Enum):
     MachineAlive = "MachineAlive"
     TaskAlive = "TaskAlive"
+    MergeProgress = "MergeProgress"
 
 
 class PoolType(Enum):
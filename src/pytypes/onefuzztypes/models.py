@@ -17,6 +17,7 @@ from .enums import (
     ContainerPermission,
     ContainerType,
     ErrorCode,
+    GeneratorType,
     GithubIssueSearchMatch,
     GithubIssueState,
     HeartbeatType,
@@ -95,6 +96,19 @@ class ReproConfig(BaseModel):
         return value
 
 
+class DedupConfig(BaseModel):
+    max_frames: Optional[int]
+    function_names_only: bool = Field(default=False)
+    ignore_frames: List[str] = Field(default_factory=list)
+    ignore_default_frames: bool = Field(default=False)
+
+    @validator("max_frames", allow_reuse=True)
+    def validate_max_frames(cls, value: Optional[int]) -> Optional[int]:
+        if value is not None and value < 1:
+            raise ValueError("invalid max_frames")
+        return value
+
+
 class TaskDetails(BaseModel):
     type: TaskType
     duration: int
@@ -114,15 +128,30 @@ class TaskDetails(BaseModel):
     generator_exe: Optional[str]
     generator_env: Optional[Dict[str, str]]
     generator_options: Optional[List[str]]
+    generator_type: Optional[GeneratorType]
+    generator_seed: Optional[int]
+    generator_grammar: Optional[str]
+    generator_max_depth: Optional[int]
     analyzer_exe: Optional[str]
     analyzer_env: Optional[Dict[str, str]]
     analyzer_options: Optional[List[str]]
     wait_for_files: Optional[ContainerType]
     stats_file: Optional[str]
     stats_format: Optional[StatsFormat]
+    stats_fields: Optional[Dict[str, str]]
+    dedup: Optional[DedupConfig]
     reboot_after_setup: Optional[bool]
     target_timeout: Optional[int]
     ensemble_sync_delay: Optional[int]
+    minimize_timeout: Optional[int]
+    minimize_max_tests: Optional[int]
+    afl_exe: Optional[str]
+    afl_env: Optional[Dict[str, str]]
+    afl_options: Optional[List[str]]
+    honggfuzz_exe: Optional[str]
+    honggfuzz_options: Optional[List[str]]
+    strict_placeholders: Optional[bool]
+    target_dict: Optional[str]
 
     @validator("check_retry_count", allow_reuse=True)
     def validate_check_retry_count(cls, value: int) -> int:
@@ -138,6 +167,20 @@ class TaskDetails(BaseModel):
                 raise ValueError("invalid target_timeout")
         return value
 
+    @validator("generator_max_depth", allow_reuse=True)
+    def check_generator_max_depth(cls, value: Optional[int]) -> Optional[int]:
+        if value is not None:
+            if value < 1:
+                raise ValueError("invalid generator_max_depth")
+        return value
+
+    @validator("minimize_timeout", "minimize_max_tests", allow_reuse=True)
+    def check_minimize_limits(cls, value: Optional[int]) -> Optional[int]:
+        if value is not None:
+            if value < 1:
+                raise ValueError("invalid minimize limit")
+        return value
+
     @validator("duration", allow_reuse=True)
     def check_duration(cls, value: int) -> int:
         if value < ONE_HOUR or value > SEVEN_DAYS:
@@ -298,21 +341,43 @@ class TaskUnitConfig(BaseModel):
     generator_exe: Optional[str]
     generator_env: Optional[Dict[str, str]]
     generator_options: Optional[List[str]]
+    generator_type: Optional[GeneratorType]
+    generator_seed: Optional[int]
+    generator_grammar: Optional[str]
+    generator_max_depth: Optional[int]
     wait_for_files: Optional[str]
     analyzer_exe: Optional[str]
     analyzer_env: Optional[Dict[str, str]]
     analyzer_options: Optional[List[str]]
     stats_file: Optional[str]
     stats_format: Optional[StatsFormat]
+    stats_fields: Optional[Dict[str, str]]
+    dedup: Optional[DedupConfig]
     ensemble_sync_delay: Optional[int]
+    minimize_timeout: Optional[int]
+    minimize_max_tests: Optional[int]
+    afl_exe: Optional[str]
+    afl_env: Optional[Dict[str, str]]
+    afl_options: Optional[List[str]]
+    honggfuzz_exe: Optional[str]
+    honggfuzz_options: Optional[List[str]]
+    strict_placeholders: Optional[bool]
+    target_dict: Optional[str]
 
     # from here forwards are Container definitions.  These need to be inline
     # with TaskDefinitions and ContainerTypes
     analysis: CONTAINER_DEF
+    cores: CONTAINER_DEF
     coverage: CONTAINER_DEF
     crashes: CONTAINER_DEF
+    dictionaries: CONTAINER_DEF
+    hangs: CONTAINER_DEF
     inputs: CONTAINER_DEF
+    leaks: CONTAINER_DEF
+    merge_control: CONTAINER_DEF
+    minimized_crashes: CONTAINER_DEF
     no_repro: CONTAINER_DEF
+    ooms: CONTAINER_DEF
     readonly_inputs: CONTAINER_DEF
     reports: CONTAINER_DEF
     tools: CONTAINER_DEF
@@ -430,7 +495,9 @@ class Job(BaseModel):
 class TaskHeartbeatEntry(BaseModel):
     task_id: UUID
     machine_id: UUID
-    data: List[Dict[str, HeartbeatType]]
+    # Entries are tagged by `type`, and some have counters, such as the
+    # `processed` and `total` inputs of `MergeProgress`
+    data: List[Dict[str, Union[HeartbeatType, int]]]
 
 
 class NodeHeartbeatEntry(BaseModel):